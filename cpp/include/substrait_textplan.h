@@ -44,11 +44,13 @@ class TextPlan {
    * protobuf
    *
    * @param text The textplan string to parse
+   * @param error If not null, receives the reason when parsing fails
    * @return std::optional<std::vector<uint8_t>> The serialized substrait::Plan
    * protobuf message, or std::nullopt if parsing failed
    */
   std::optional<std::vector<uint8_t>> LoadFromText(
-      const std::string& text) const;
+      const std::string& text,
+      std::string* error = nullptr) const;
 
   /**
    * @brief Convert a serialized Substrait protobuf plan to textplan format
@@ -58,20 +60,37 @@ class TextPlan {
    * SerializeToString() or SerializeToArray().
    *
    * @param data The serialized Substrait protobuf plan (substrait::Plan)
+   * @param error If not null, receives the reason when the conversion fails
    * @return std::optional<std::string> The textplan representation of the plan,
    * or std::nullopt if an error occurred (e.g., invalid protobuf data)
    */
-  std::optional<std::string> SaveToText(const std::vector<uint8_t>& data) const;
+  std::optional<std::string> SaveToText(
+      const std::vector<uint8_t>& data,
+      std::string* error = nullptr) const;
+
+  /**
+   * @brief Check a textplan string for problems without converting it
+   *
+   * @param text The textplan string to check
+   * @return std::optional<std::vector<std::string>> One diagnostic per problem
+   * in the form `error[E0001] 3:5-3:6: message`, which is empty if the plan has
+   * no problems, or std::nullopt if the check itself failed
+   */
+  std::optional<std::vector<std::string>> CheckText(
+      const std::string& text) const;
 
   /**
    * @brief Static helper: Parse a textplan string and convert it to a
    * serialized Substrait protobuf
    *
    * @param text The textplan string to parse
+   * @param error If not null, receives the reason when parsing fails
    * @return std::optional<std::vector<uint8_t>> The serialized substrait::Plan
    * protobuf message, or std::nullopt if parsing failed
    */
-  static std::optional<std::vector<uint8_t>> LoadFromText(const char* text);
+  static std::optional<std::vector<uint8_t>> LoadFromText(
+      const char* text,
+      std::string* error = nullptr);
 
   /**
    * @brief Static helper: Convert a serialized Substrait protobuf plan to
@@ -84,12 +103,25 @@ class TextPlan {
    * @param data Pointer to the serialized Substrait protobuf plan
    * (substrait::Plan)
    * @param size Size of the serialized data in bytes
+   * @param error If not null, receives the reason when the conversion fails
    * @return std::optional<std::string> The textplan representation of the plan,
    * or std::nullopt if an error occurred (e.g., invalid protobuf data)
    */
   static std::optional<std::string> SaveToText(
       const uint8_t* data,
-      size_t size);
+      size_t size,
+      std::string* error = nullptr);
+
+  /**
+   * @brief Static helper: Check a textplan string for problems without
+   * converting it
+   *
+   * @param text The textplan string to check
+   * @return std::optional<std::vector<std::string>> One diagnostic per problem
+   * in the form `error[E0001] 3:5-3:6: message`, which is empty if the plan has
+   * no problems, or std::nullopt if the check itself failed
+   */
+  static std::optional<std::vector<std::string>> CheckText(const char* text);
 
  private:
   class Impl;
@@ -11,12 +11,28 @@ namespace textplan {
 
 // Declare external C functions from the Rust library
 extern "C" {
-void* load_from_text(const char* text);
+void* load_from_text(const char* text, char** error);
+char* check_text(const char* text);
 void free_plan_bytes(void* ptr);
-char* save_to_text(const uint8_t* bytes, size_t bytes_len);
+char* save_to_text(const uint8_t* bytes, size_t bytes_len, char** error);
 void free_text_plan(char* text_ptr);
 }
 
+namespace {
+
+// Moves an error message returned by the library into the caller's string.
+void TakeError(char* error_ptr, std::string* error) {
+  if (error_ptr == nullptr) {
+    return;
+  }
+  if (error != nullptr) {
+    *error = error_ptr;
+  }
+  free_text_plan(error_ptr);
+}
+
+} // namespace
+
 // Pimpl implementation
 class TextPlan::Impl {
  public:
@@ -41,22 +57,33 @@ TextPlan::TextPlan(TextPlan&&) noexcept = default;
 TextPlan& TextPlan::operator=(TextPlan&&) noexcept = default;
 
 std::optional<std::vector<uint8_t>> TextPlan::LoadFromText(
-    const std::string& text) const {
-  return LoadFromText(text.c_str());
+    const std::string& text,
+    std::string* error) const {
+  return LoadFromText(text.c_str(), error);
 }
 
 std::optional<std::string> TextPlan::SaveToText(
-    const std::vector<uint8_t>& data) const {
-  return SaveToText(data.data(), data.size());
+    const std::vector<uint8_t>& data,
+    std::string* error) const {
+  return SaveToText(data.data(), data.size(), error);
 }
 
-std::optional<std::vector<uint8_t>> TextPlan::LoadFromText(const char* text) {
+std::optional<std::vector<std::string>> TextPlan::CheckText(
+    const std::string& text) const {
+  return CheckText(text.c_str());
+}
+
+std::optional<std::vector<uint8_t>> TextPlan::LoadFromText(
+    const char* text,
+    std::string* error) {
   if (text == nullptr) {
     return std::nullopt;
   }
 
-  void* ptr = load_from_text(text);
+  char* error_ptr = nullptr;
+  void* ptr = load_from_text(text, &error_ptr);
   if (ptr == nullptr) {
+    TakeError(error_ptr, error);
     return std::nullopt;
   }
 
@@ -79,13 +106,16 @@ std::optional<std::vector<uint8_t>> TextPlan::LoadFromText(const char* text) {
 
 std::optional<std::string> TextPlan::SaveToText(
     const uint8_t* data,
-    size_t size) {
+    size_t size,
+    std::string* error) {
   if (data == nullptr || size == 0) {
     return std::nullopt;
   }
 
-  char* text_ptr = save_to_text(data, size);
+  char* error_ptr = nullptr;
+  char* text_ptr = save_to_text(data, size, &error_ptr);
   if (text_ptr == nullptr) {
+    TakeError(error_ptr, error);
     return std::nullopt;
   }
 
@@ -98,5 +128,32 @@ std::optional<std::string> TextPlan::SaveToText(
   return result;
 }
 
+std::optional<std::vector<std::string>> TextPlan::CheckText(const char* text) {
+  if (text == nullptr) {
+    return std::nullopt;
+  }
+
+  char* diagnostics_ptr = check_text(text);
+  if (diagnostics_ptr == nullptr) {
+    return std::nullopt;
+  }
+
+  // The diagnostics come one per line
+  std::vector<std::string> diagnostics;
+  std::string remaining(diagnostics_ptr);
+  free_text_plan(diagnostics_ptr);
+  size_t start = 0;
+  while (start < remaining.size()) {
+    size_t end = remaining.find('\n', start);
+    if (end == std::string::npos) {
+      end = remaining.size();
+    }
+    diagnostics.push_back(remaining.substr(start, end - start));
+    start = end + 1;
+  }
+
+  return diagnostics;
+}
+
 } // namespace textplan
 } // namespace substrait
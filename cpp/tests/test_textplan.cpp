@@ -167,6 +167,12 @@ int test_invalid_textplan() {
       !result.has_value(),
       "LoadFromText should return nullopt for invalid input");
 
+  std::string error;
+  result = substrait::textplan::TextPlan::LoadFromText(
+      invalid_text.c_str(), &error);
+  TEST_ASSERT(!result.has_value(), "LoadFromText should fail again");
+  TEST_ASSERT(!error.empty(), "LoadFromText should explain the failure");
+
   std::cout << "  PASSED" << std::endl;
   return 0;
 }
@@ -204,7 +210,23 @@ int test_save_to_text_empty() {
   return 0;
 }
 
-// Test 8: Move semantics
+// Test 8: Checking a textplan
+int test_check_text() {
+  std::cout << "Running: test_check_text" << std::endl;
+
+  auto diagnostics = substrait::textplan::TextPlan::CheckText(
+      "schema s {\n  a i64;\n  b i64;;\n}\n");
+  TEST_ASSERT(diagnostics.has_value(), "CheckText should return a value");
+  TEST_ASSERT(!diagnostics->empty(), "CheckText should find the problem");
+  TEST_ASSERT(
+      (*diagnostics)[0].rfind("error[E0001] 3:", 0) == 0,
+      "The problem should be located on the third line");
+
+  std::cout << "  PASSED" << std::endl;
+  return 0;
+}
+
+// Test 9: Move semantics
 int test_move_semantics() {
   std::cout << "Running: test_move_semantics" << std::endl;
 
@@ -259,6 +281,7 @@ int main() {
   failures += test_invalid_textplan();
   failures += test_empty_input();
   failures += test_save_to_text_empty();
+  failures += test_check_text();
   failures += test_move_semantics();
 
   std::cout << std::endl;
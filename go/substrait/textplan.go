@@ -11,14 +11,16 @@ package substrait
 // #include <stdlib.h>
 // #include <stdint.h>
 // #include <string.h>
-// void* load_from_text(const char* text);
+// void* load_from_text(const char* text, char** error);
+// char* check_text(const char* text);
 // void free_plan_bytes(void* ptr);
-// char* save_to_text(const uint8_t* bytes, size_t bytes_len);
+// char* save_to_text(const uint8_t* bytes, size_t bytes_len, char** error);
 // void free_text_plan(char* text_ptr);
 import "C"
 import (
 	"errors"
 	"runtime"
+	"strings"
 	"unsafe"
 )
 
@@ -40,9 +42,10 @@ func (tp *TextPlan) LoadFromText(text string) ([]byte, error) {
 	cText := C.CString(text)
 	defer C.free(unsafe.Pointer(cText))
 
-	ptr := C.load_from_text(cText)
+	var cErr *C.char
+	ptr := C.load_from_text(cText, &cErr)
 	if ptr == nil {
-		return nil, errors.New("failed to load text plan")
+		return nil, takeError(cErr, "failed to load text plan")
 	}
 
 	// First sizeof(size_t) bytes contain the length
@@ -71,9 +74,10 @@ func (tp *TextPlan) SaveToText(data []byte) (string, error) {
 		return "", errors.New("empty binary data")
 	}
 
-	cPtr := C.save_to_text((*C.uint8_t)(unsafe.Pointer(&data[0])), C.size_t(len(data)))
+	var cErr *C.char
+	cPtr := C.save_to_text((*C.uint8_t)(unsafe.Pointer(&data[0])), C.size_t(len(data)), &cErr)
 	if cPtr == nil {
-		return "", errors.New("failed to convert binary plan to text")
+		return "", takeError(cErr, "failed to convert binary plan to text")
 	}
 
 	// Convert C string to Go string
@@ -85,6 +89,36 @@ func (tp *TextPlan) SaveToText(data []byte) (string, error) {
 	return result, nil
 }
 
+// CheckText checks a textplan string for problems without converting it.
+// Returns one diagnostic per problem in the form `error[E0001] 3:5-3:6: message`,
+// which is empty if the plan has no problems.
+func (tp *TextPlan) CheckText(text string) ([]string, error) {
+	cText := C.CString(text)
+	defer C.free(unsafe.Pointer(cText))
+
+	cPtr := C.check_text(cText)
+	if cPtr == nil {
+		return nil, errors.New("failed to check text plan")
+	}
+	result := C.GoString(cPtr)
+	C.free_text_plan(cPtr)
+
+	if result == "" {
+		return []string{}, nil
+	}
+	return strings.Split(result, "\n"), nil
+}
+
+// takeError converts an error message returned by the library into a Go error,
+// freeing the message.
+func takeError(cErr *C.char, fallback string) error {
+	if cErr == nil {
+		return errors.New(fallback)
+	}
+	defer C.free_text_plan(cErr)
+	return errors.New(C.GoString(cErr))
+}
+
 // Helper functions for easier use
 
 // LoadFromText parses a textplan string and converts it to a serialized Substrait protobuf.
@@ -103,6 +137,12 @@ func SaveToText(data []byte) (string, error) {
 	return tp.SaveToText(data)
 }
 
+// CheckText checks a textplan string for problems without converting it.
+func CheckText(text string) ([]string, error) {
+	tp := New()
+	return tp.CheckText(text)
+}
+
 // Initialize the library automatically
 func init() {
 	// This ensures the library stays loaded
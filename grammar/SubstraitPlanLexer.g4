@@ -45,6 +45,10 @@ COUNT: [Cc][Oo][Uu][Nn][Tt];
 OFFSET: [Oo][Ff][Ff][Ss][Ee][Tt];
 TYPE: [Tt][Yy][Pp][Ee];
 EMIT: [Ee][Mm][Ii][Tt];
+DETAIL: [Dd][Ee][Tt][Aa][Ii][Ll];
+ON_FAILURE: [Oo][Nn]'_'[Ff][Aa][Ii][Ll][Uu][Rr][Ee];
+PARAMETERS: [Pp][Aa][Rr][Aa][Mm][Ee][Tt][Ee][Rr][Ss];
+MASK: [Mm][Aa][Ss][Kk];
 
 SUBQUERY: [Ss][Uu][Bb][Qq][Uu][Ee][Rr][Yy];
 EXISTS: [Ee][Xx][Ii][Ss][Tt][Ss];
@@ -104,12 +108,17 @@ IDENTIFIER
     : [A-Za-z][A-Za-z0-9_$]*
     ;
 
+PARAMETER
+    : '$' [A-Za-z0-9_]+
+    ;
+
 NUMBER
     : MINUS? [0-9]+ ( PERIOD [0-9]+ )?
     | MINUS? [0-9]+ ( PERIOD [0-9]+ )? 'E' ('+' | MINUS) [0-9]+
     ;
 
 STRING : '"' (ESCAPEDQUOTE | ~["])* '"' ;
+HEX_STRING : [xX] '"' HEX* '"' ;
 fragment ESCAPEDQUOTE : '\\' '"' ;
 fragment HEX : [0-9A-Fa-f] ;
 fragment DIGIT : [0-9] ;
@@ -27,6 +27,17 @@ plan_detail
    | schema_definition
    | source_definition
    | extensionspace
+   | advanced_extension
+   | parameters
+   ;
+
+// Values bound to dynamic parameters such as $1 or $region.
+parameters
+   : PARAMETERS LEFTBRACE parameter_binding* RIGHTBRACE
+   ;
+
+parameter_binding
+   : PARAMETER (COLON literal_complex_type)? (EQUAL constant)? SEMICOLON
    ;
 
 pipelines
@@ -61,7 +72,6 @@ relation_filter_behavior
    | id id
    ;
 
-// TODO -- Can the type be determined automatically from the function definition?
 // TODO -- Consider moving the run phase to an optional third detail line.
 measure_detail
    : MEASURE expression (ARROW literal_complex_type)? (ATSIGN id)? (NAMED id)? SEMICOLON
@@ -71,11 +81,11 @@ measure_detail
    ;
 
 relation_detail
-   : COMMON SEMICOLON                                       # relationCommon
+   : COMMON (SEMICOLON | LEFTBRACE common_hint* RIGHTBRACE)  # relationCommon
    | BASE_SCHEMA id SEMICOLON                               # relationUsesSchema
    | relation_filter_behavior? FILTER expression SEMICOLON  # relationFilter
    | EXPRESSION expression (NAMED id)? SEMICOLON            # relationExpression
-   | ADVANCED_EXTENSION SEMICOLON                           # relationAdvancedExtension
+   | advanced_extension                                     # relationAdvancedExtension
    | source_reference SEMICOLON                             # relationSourceReference
    | GROUPING expression SEMICOLON                          # relationGrouping
    | MEASURE LEFTBRACE measure_detail* RIGHTBRACE           # relationMeasure
@@ -84,17 +94,50 @@ relation_detail
    | OFFSET NUMBER SEMICOLON                                # relationOffset
    | TYPE id SEMICOLON                                      # relationJoinType
    | EMIT column_name SEMICOLON                             # relationEmit
+   | PROJECTION mask_item (COMMA mask_item)* SEMICOLON      # relationProjection
+   | DETAIL STRING STRING? SEMICOLON                        # relationExtensionDetail
+   ;
+
+// Optimization and enhancement payloads given as a type URL followed by the serialized message.
+advanced_extension
+   : ADVANCED_EXTENSION (SEMICOLON | LEFTBRACE advanced_extension_detail* RIGHTBRACE)
+   ;
+
+advanced_extension_detail
+   : id STRING STRING? SEMICOLON
+   ;
+
+// Hints stored in RelCommon (stats, constraints, alias, output names and computations).
+common_hint
+   : id (common_hint_value COMMA?)* SEMICOLON
+   ;
+
+common_hint_value
+   : NUMBER
+   | STRING
+   | id
    ;
 
 expression
-   : id LEFTPAREN (expression COMMA?)* RIGHTPAREN (ARROW literal_complex_type)? # expressionFunctionUse
+   : id LEFTPAREN (expression COMMA?)* RIGHTPAREN function_options? (ARROW literal_complex_type)? # expressionFunctionUse
    | constant                                               # expressionConstant
-   | column_name                                            # expressionColumn
-   | expression AS literal_complex_type                     # expressionCast
+   | column_name reference_segment*                         # expressionColumn
+   | expression AS literal_complex_type (ON_FAILURE id)?    # expressionCast
    | SUBQUERY relation_ref                                  # expressionScalarSubquery
    | expression_list IN SUBQUERY relation_ref               # expressionInPredicateSubquery
    | (UNIQUE|EXISTS) IN SUBQUERY relation_ref               # expressionSetPredicateSubquery
    | expression COMPARISON (ALL|ANY) SUBQUERY relation_ref  # expressionSetComparisonSubquery
+   | PARAMETER (COLON literal_complex_type)?                 # expressionParameter
+   | MASK LEFTBRACE mask_item (COMMA mask_item)* RIGHTBRACE  # expressionMask
+   ;
+
+// Function options such as [overflow: SILENT]. Repeating an option adds another preference.
+function_options
+   : LEFTBRACKET function_option (COMMA function_option)* RIGHTBRACKET
+   ;
+
+function_option
+   : id COLON id
    ;
 
 expression_list
@@ -104,6 +147,7 @@ expression_list
 constant
    : NUMBER (UNDERSCORE literal_basic_type)?
    | STRING (UNDERSCORE literal_basic_type)?
+   | HEX_STRING (UNDERSCORE literal_basic_type)?
    | map_literal (UNDERSCORE literal_complex_type)?
    | struct_literal (UNDERSCORE literal_complex_type)?
    | NULLVAL (UNDERSCORE literal_complex_type)?
@@ -119,7 +163,12 @@ literal_complex_type
    : literal_basic_type
    | LIST QUESTIONMARK? LEFTANGLEBRACKET literal_complex_type? RIGHTANGLEBRACKET
    | MAP QUESTIONMARK? LEFTANGLEBRACKET literal_basic_type? COMMA? literal_complex_type? RIGHTANGLEBRACKET
-   | STRUCT QUESTIONMARK? LEFTANGLEBRACKET literal_complex_type? (COMMA literal_complex_type)* RIGHTANGLEBRACKET
+   | STRUCT QUESTIONMARK? LEFTANGLEBRACKET struct_field_type? (COMMA struct_field_type)* RIGHTANGLEBRACKET
+   ;
+
+// The fields of a struct may be named, as in struct<city: string, zip: string>.
+struct_field_type
+   : (id COLON)? literal_complex_type
    ;
 
 literal_specifier   // Rename to literal_basic_type_specifier?
@@ -144,6 +193,35 @@ column_name
    : (id PERIOD)? id
    ;
 
+// Drills into a nested column: .N selects a struct field by position, .name a struct
+// field by name, [N] a list element and [constant] a map key.
+reference_segment
+   : PERIOD NUMBER
+   | PERIOD id
+   | LEFTBRACKET constant RIGHTBRACKET
+   ;
+
+// A column of a projection mask, optionally narrowed to parts of its nested value.
+mask_item
+   : column_name mask_select?
+   ;
+
+// {N, ...} selects struct fields by position, [N, M:K, ...] list elements and slices,
+// ["key"] a map key and [EXPRESSION "pattern"] the map keys matching a pattern.
+mask_select
+   : LEFTBRACE mask_struct_item (COMMA mask_struct_item)* RIGHTBRACE            # maskStructSelect
+   | LEFTBRACKET mask_list_item (COMMA mask_list_item)* RIGHTBRACKET mask_select? # maskListSelect
+   | LEFTBRACKET EXPRESSION? STRING RIGHTBRACKET mask_select?                   # maskMapSelect
+   ;
+
+mask_struct_item
+   : NUMBER mask_select?
+   ;
+
+mask_list_item
+   : NUMBER (COLON NUMBER)?
+   ;
+
 source_reference
    : SOURCE id
    ;
@@ -170,11 +248,13 @@ file
 
 local_files_detail
    : ADVANCED_EXTENSION id
+   | advanced_extension
    | ITEMS EQUAL LEFTBRACKET file (COMMA file)* COMMA? RIGHTBRACKET
    ;
 
 named_table_detail
    : ADVANCED_EXTENSION id
+   | advanced_extension
    | NAMES EQUAL LEFTBRACKET STRING (COMMA STRING)* COMMA? RIGHTBRACKET
    ;
 
@@ -235,6 +315,10 @@ simple_id
    | COUNT
    | TYPE
    | EMIT
+   | DETAIL
+   | ON_FAILURE
+   | PARAMETERS
+   | MASK
    | NAMED
    | ALL
    | ANY
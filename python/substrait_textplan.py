@@ -61,7 +61,7 @@ class TextPlan:
         self._lib = ctypes.CDLL(lib_path)
 
         # Configure function signatures
-        self._lib.load_from_text.argtypes = [ctypes.c_char_p]
+        self._lib.load_from_text.argtypes = [ctypes.c_char_p, ctypes.c_void_p]
         self._lib.load_from_text.restype = ctypes.POINTER(ctypes.c_uint8)
 
         self._lib.free_plan_bytes.argtypes = [ctypes.POINTER(ctypes.c_uint8)]
@@ -70,6 +70,7 @@ class TextPlan:
         self._lib.save_to_text.argtypes = [
             ctypes.POINTER(ctypes.c_uint8),
             ctypes.c_size_t,
+            ctypes.c_void_p,
         ]
         self._lib.save_to_text.restype = (
             ctypes.c_void_p
@@ -89,7 +90,7 @@ class TextPlan:
             The serialized substrait.Plan protobuf message, or None if parsing failed.
         """
         text_bytes = text.encode("utf-8")
-        ptr = self._lib.load_from_text(text_bytes)
+        ptr = self._lib.load_from_text(text_bytes, None)
 
         if not ptr:
             return None
@@ -127,7 +128,7 @@ class TextPlan:
             (e.g., invalid protobuf data).
         """
         data_array = (ctypes.c_uint8 * len(data))(*data)
-        ptr = self._lib.save_to_text(data_array, len(data))
+        ptr = self._lib.save_to_text(data_array, len(data), None)
 
         if not ptr:
             return None
@@ -11,8 +11,11 @@ use std::ffi::{c_char, CStr, CString};
 use std::ptr;
 
 // Re-export key types
+pub use textplan::common::diagnostic::{Diagnostic, DiagnosticCode, Severity};
 pub use textplan::common::error::TextPlanError;
 pub use textplan::common::parse_result::ParseResult;
+pub use textplan::common::relation_schema::{RelationSchema, SchemaField};
+pub use textplan::common::source_map::{LineColumn, SourceMap};
 pub use textplan::printer::plan_printer::TextPlanFormat;
 pub use textplan::symbol_table::SymbolTable;
 
@@ -59,99 +62,190 @@ pub fn symbol_table_to_text_plan(
     textplan::parser::parse_text::serialize_to_text(symbol_table, format)
 }
 
+/// Runs the body of an FFI function, returning `on_panic` if it panics.
+///
+/// Unwinding out of an `extern "C"` function is undefined behaviour, so every FFI entry
+/// point catches panics and reports them as a failure to the caller.
+fn catch_panic<T>(
+    on_panic: T,
+    error_ptr: *mut *mut c_char,
+    body: impl FnOnce() -> T + std::panic::UnwindSafe,
+) -> T {
+    std::panic::catch_unwind(body).unwrap_or_else(|_| {
+        set_error(error_ptr, "the textplan library panicked");
+        on_panic
+    })
+}
+
+/// Hands an error message to the caller of an FFI function, if it asked for one.
+///
+/// The message must be freed with free_text_plan.
+fn set_error(error_ptr: *mut *mut c_char, message: &str) {
+    if error_ptr.is_null() {
+        return;
+    }
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    unsafe {
+        *error_ptr = message.into_raw();
+    }
+}
+
 /// FFI API for loading a textplan from a string and converting it to binary protobuf
+///
+/// Returns null on failure, in which case the reason is stored in `error_ptr` unless it
+/// is null.  The reason must be freed with free_text_plan.
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
-pub extern "C" fn load_from_text(text_ptr: *const c_char) -> *mut u8 {
-    if text_ptr.is_null() {
-        return ptr::null_mut();
-    }
+pub extern "C" fn load_from_text(text_ptr: *const c_char, error_ptr: *mut *mut c_char) -> *mut u8 {
+    catch_panic(ptr::null_mut(), error_ptr, || {
+        if text_ptr.is_null() {
+            set_error(error_ptr, "the text is null");
+            return ptr::null_mut();
+        }
 
-    let c_str = unsafe { CStr::from_ptr(text_ptr) };
-    let text = match c_str.to_str() {
-        Ok(s) => s,
-        Err(_) => return ptr::null_mut(),
-    };
+        let c_str = unsafe { CStr::from_ptr(text_ptr) };
+        let text = match c_str.to_str() {
+            Ok(s) => s,
+            Err(err) => {
+                set_error(error_ptr, &format!("the text is not valid UTF-8: {}", err));
+                return ptr::null_mut();
+            }
+        };
 
-    match textplan::parser::load_from_text(text) {
-        Ok(plan_bytes) => {
-            // Allocate memory for the binary plan that will be returned to C/C++
-            let len = plan_bytes.len();
-            let result_size = len + std::mem::size_of::<usize>();
+        match textplan::parser::load_from_text(text) {
+            Ok(plan_bytes) => {
+                // Allocate memory for the binary plan that will be returned to C/C++
+                let len = plan_bytes.len();
+                let result_size = len + std::mem::size_of::<usize>();
 
-            let layout =
-                std::alloc::Layout::from_size_align(result_size, 8).expect("Invalid layout");
+                let layout =
+                    std::alloc::Layout::from_size_align(result_size, 8).expect("Invalid layout");
 
-            unsafe {
-                let ptr = std::alloc::alloc(layout);
-                if ptr.is_null() {
-                    return ptr::null_mut();
-                }
+                unsafe {
+                    let ptr = std::alloc::alloc(layout);
+                    if ptr.is_null() {
+                        set_error(error_ptr, "unable to allocate the binary plan");
+                        return ptr::null_mut();
+                    }
 
-                // First write the length
-                let len_ptr = ptr as *mut usize;
-                *len_ptr = len;
+                    // First write the length
+                    let len_ptr = ptr as *mut usize;
+                    *len_ptr = len;
 
-                // Then write the actual data
-                let data_ptr = ptr.add(std::mem::size_of::<usize>());
-                std::ptr::copy_nonoverlapping(plan_bytes.as_ptr(), data_ptr, len);
+                    // Then write the actual data
+                    let data_ptr = ptr.add(std::mem::size_of::<usize>());
+                    std::ptr::copy_nonoverlapping(plan_bytes.as_ptr(), data_ptr, len);
 
-                ptr
+                    ptr
+                }
+            }
+            Err(e) => {
+                set_error(error_ptr, &e.to_string());
+                ptr::null_mut()
             }
         }
-        Err(e) => {
-            eprintln!("Error loading textplan: {}", e);
-            ptr::null_mut()
+    })
+}
+
+/// FFI API for checking a textplan, returning its diagnostics with one per line
+///
+/// Each line has the form `error[E0001] 3:5-3:6: message` so that editors can locate
+/// the text it refers to.  An empty string means no problems were found.  The result
+/// must be freed with free_text_plan.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn check_text(text_ptr: *const c_char) -> *mut c_char {
+    catch_panic(ptr::null_mut(), ptr::null_mut(), || {
+        if text_ptr.is_null() {
+            return ptr::null_mut();
         }
-    }
+
+        let c_str = unsafe { CStr::from_ptr(text_ptr) };
+        let text = match c_str.to_str() {
+            Ok(s) => s,
+            Err(_) => return ptr::null_mut(),
+        };
+
+        let result = parse_text_plan(text);
+        let diagnostics: Vec<String> = result
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.format_with(result.source_map()))
+            .collect();
+        match CString::new(diagnostics.join("\n")) {
+            Ok(c_string) => c_string.into_raw(),
+            Err(_) => ptr::null_mut(),
+        }
+    })
 }
 
 /// FFI API for freeing memory allocated by this library
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn free_plan_bytes(ptr: *mut u8) {
-    if ptr.is_null() {
-        return;
-    }
+    catch_panic((), ptr::null_mut(), || {
+        if ptr.is_null() {
+            return;
+        }
 
-    unsafe {
-        let len_ptr = ptr as *const usize;
-        let len = *len_ptr;
-        let result_size = len + std::mem::size_of::<usize>();
+        unsafe {
+            let len_ptr = ptr as *const usize;
+            let len = *len_ptr;
+            let result_size = len + std::mem::size_of::<usize>();
 
-        let layout = std::alloc::Layout::from_size_align(result_size, 8).expect("Invalid layout");
+            let layout =
+                std::alloc::Layout::from_size_align(result_size, 8).expect("Invalid layout");
 
-        std::alloc::dealloc(ptr, layout);
-    }
+            std::alloc::dealloc(ptr, layout);
+        }
+    })
 }
 
 /// FFI API for saving a binary plan to textplan format
+///
+/// Returns null on failure, in which case the reason is stored in `error_ptr` unless it
+/// is null.  Both the result and the reason must be freed with free_text_plan.
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
-pub extern "C" fn save_to_text(bytes_ptr: *const u8, bytes_len: usize) -> *mut c_char {
-    if bytes_ptr.is_null() {
-        return ptr::null_mut();
-    }
+pub extern "C" fn save_to_text(
+    bytes_ptr: *const u8,
+    bytes_len: usize,
+    error_ptr: *mut *mut c_char,
+) -> *mut c_char {
+    catch_panic(ptr::null_mut(), error_ptr, || {
+        if bytes_ptr.is_null() {
+            set_error(error_ptr, "the binary plan is null");
+            return ptr::null_mut();
+        }
 
-    let bytes = unsafe { std::slice::from_raw_parts(bytes_ptr, bytes_len) };
+        let bytes = unsafe { std::slice::from_raw_parts(bytes_ptr, bytes_len) };
 
-    match textplan::converter::save_to_text(bytes) {
-        Ok(text_plan) => match CString::new(text_plan) {
-            Ok(c_string) => c_string.into_raw(),
-            Err(_) => ptr::null_mut(),
-        },
-        Err(_) => ptr::null_mut(),
-    }
+        match textplan::converter::save_to_text(bytes) {
+            Ok(text_plan) => match CString::new(text_plan) {
+                Ok(c_string) => c_string.into_raw(),
+                Err(err) => {
+                    set_error(error_ptr, &err.to_string());
+                    ptr::null_mut()
+                }
+            },
+            Err(err) => {
+                set_error(error_ptr, &err.to_string());
+                ptr::null_mut()
+            }
+        }
+    })
 }
 
 /// FFI API for freeing memory allocated by this library
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn free_text_plan(text_ptr: *mut c_char) {
-    if !text_ptr.is_null() {
-        unsafe {
-            let _ = CString::from_raw(text_ptr);
-            // CString destructor will free the memory
+    catch_panic((), ptr::null_mut(), || {
+        if !text_ptr.is_null() {
+            unsafe {
+                let _ = CString::from_raw(text_ptr);
+                // CString destructor will free the memory
+            }
         }
-    }
+    })
 }
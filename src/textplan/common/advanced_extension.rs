@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for the `AdvancedExtension` messages attached to plans, relations and sources.
+//!
+//! The payloads of the well-known protobuf wrapper types and of `google.protobuf.Struct`
+//! are written as JSON between backticks.  Any other payload is written as a quoted string
+//! holding the serialized message.
+
+use ::prost::Message;
+use ::prost_types::value::Kind;
+use ::substrait::proto::extensions::AdvancedExtension;
+use ::substrait::proto::rel::RelType;
+use ::substrait::proto::Rel;
+
+use crate::textplan::common::string_utils::{escape_bytes, unescape_bytes};
+
+/// Returns the `advanced_extension` of a relation, if the relation type has one and it is set.
+pub fn rel_advanced_extension(rel: &Rel) -> Option<&AdvancedExtension> {
+    match rel.rel_type.as_ref()? {
+        RelType::Read(r) => r.advanced_extension.as_ref(),
+        RelType::Filter(r) => r.advanced_extension.as_ref(),
+        RelType::Fetch(r) => r.advanced_extension.as_ref(),
+        RelType::Aggregate(r) => r.advanced_extension.as_ref(),
+        RelType::Sort(r) => r.advanced_extension.as_ref(),
+        RelType::Join(r) => r.advanced_extension.as_ref(),
+        RelType::Project(r) => r.advanced_extension.as_ref(),
+        RelType::Set(r) => r.advanced_extension.as_ref(),
+        RelType::Cross(r) => r.advanced_extension.as_ref(),
+        RelType::HashJoin(r) => r.advanced_extension.as_ref(),
+        RelType::MergeJoin(r) => r.advanced_extension.as_ref(),
+        RelType::NestedLoopJoin(r) => r.advanced_extension.as_ref(),
+        RelType::Window(r) => r.advanced_extension.as_ref(),
+        RelType::Exchange(r) => r.advanced_extension.as_ref(),
+        _ => None,
+    }
+}
+
+/// Returns a mutable handle on the `advanced_extension` field of a relation.
+///
+/// Returns `None` when the relation type has no `advanced_extension` field (extension
+/// relations carry their payload in `detail` instead).
+pub fn rel_advanced_extension_mut(rel: &mut Rel) -> Option<&mut Option<AdvancedExtension>> {
+    match rel.rel_type.as_mut()? {
+        RelType::Read(r) => Some(&mut r.advanced_extension),
+        RelType::Filter(r) => Some(&mut r.advanced_extension),
+        RelType::Fetch(r) => Some(&mut r.advanced_extension),
+        RelType::Aggregate(r) => Some(&mut r.advanced_extension),
+        RelType::Sort(r) => Some(&mut r.advanced_extension),
+        RelType::Join(r) => Some(&mut r.advanced_extension),
+        RelType::Project(r) => Some(&mut r.advanced_extension),
+        RelType::Set(r) => Some(&mut r.advanced_extension),
+        RelType::Cross(r) => Some(&mut r.advanced_extension),
+        RelType::HashJoin(r) => Some(&mut r.advanced_extension),
+        RelType::MergeJoin(r) => Some(&mut r.advanced_extension),
+        RelType::NestedLoopJoin(r) => Some(&mut r.advanced_extension),
+        RelType::Window(r) => Some(&mut r.advanced_extension),
+        RelType::Exchange(r) => Some(&mut r.advanced_extension),
+        _ => None,
+    }
+}
+
+/// Adds one `optimization` or `enhancement` payload to an advanced extension.
+///
+/// # Arguments
+///
+/// * `extension` - The extension to update
+/// * `kind` - Either `optimization` (may repeat) or `enhancement` (at most one)
+/// * `type_url` - The type URL of the packed message
+/// * `value` - The serialized message
+pub fn add_advanced_extension_detail(
+    extension: &mut AdvancedExtension,
+    kind: &str,
+    type_url: String,
+    value: Vec<u8>,
+) -> Result<(), String> {
+    match kind.to_lowercase().as_str() {
+        "optimization" => {
+            extension.optimization.push(Default::default());
+            if let Some(any) = extension.optimization.last_mut() {
+                any.type_url = type_url;
+                any.value = value.into();
+            }
+        }
+        "enhancement" => {
+            if extension.enhancement.is_some() {
+                return Err("An advanced extension may only have one enhancement".to_string());
+            }
+            let mut any = extension.enhancement.take().unwrap_or_default();
+            any.type_url = type_url;
+            any.value = value.into();
+            extension.enhancement = Some(any);
+        }
+        _ => {
+            return Err(format!(
+                "Unrecognized advanced extension detail '{}', expected optimization or enhancement",
+                kind
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Formats the payload of an `Any` message for a textplan.
+///
+/// Payloads of a well-known type are printed as JSON unless the JSON would not read back
+/// to the same bytes, in which case they are printed like any other payload.
+pub fn format_payload(type_url: &str, value: &[u8]) -> String {
+    let json = match payload_to_json(type_url, value) {
+        Some(json) => json.to_string(),
+        None => return escape_bytes(value),
+    };
+    if json.contains('`') || json_to_payload(type_url, &json).as_deref() != Ok(value) {
+        return escape_bytes(value);
+    }
+    format!("`{}`", json)
+}
+
+/// Parses a payload written by [`format_payload`] back into the serialized message.
+///
+/// `text` is the payload as written, including its quotes or backticks.
+pub fn parse_payload(type_url: &str, text: &str) -> Result<Vec<u8>, String> {
+    match text
+        .strip_prefix('`')
+        .and_then(|json| json.strip_suffix('`'))
+    {
+        Some(json) => json_to_payload(type_url, json.trim_matches('`')),
+        None => unescape_bytes(text),
+    }
+}
+
+/// Returns the fully qualified message name of a type URL.
+fn message_name(type_url: &str) -> &str {
+    type_url.rsplit('/').next().unwrap_or(type_url)
+}
+
+/// Decodes a payload of a well-known type, returning None for any other type.
+fn payload_to_json(type_url: &str, value: &[u8]) -> Option<serde_json::Value> {
+    match message_name(type_url) {
+        "google.protobuf.BoolValue" => bool::decode(value).ok().map(Into::into),
+        "google.protobuf.Int32Value" => i32::decode(value).ok().map(Into::into),
+        "google.protobuf.Int64Value" => i64::decode(value).ok().map(Into::into),
+        "google.protobuf.UInt32Value" => u32::decode(value).ok().map(Into::into),
+        "google.protobuf.UInt64Value" => u64::decode(value).ok().map(Into::into),
+        "google.protobuf.FloatValue" => f32::decode(value).ok().map(Into::into),
+        "google.protobuf.DoubleValue" => f64::decode(value).ok().map(Into::into),
+        "google.protobuf.StringValue" => String::decode(value).ok().map(Into::into),
+        "google.protobuf.Struct" => ::prost_types::Struct::decode(value)
+            .ok()
+            .map(|message| struct_to_json(&message)),
+        _ => None,
+    }
+}
+
+/// Serializes the JSON form of a payload of a well-known type.
+fn json_to_payload(type_url: &str, json: &str) -> Result<Vec<u8>, String> {
+    let name = message_name(type_url);
+    let expected = |kind: &str| format!("A {} payload must be {}", name, kind);
+    let json: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Invalid JSON payload: {}", e))?;
+    match name {
+        "google.protobuf.BoolValue" => json
+            .as_bool()
+            .map(|value| value.encode_to_vec())
+            .ok_or_else(|| expected("a boolean")),
+        "google.protobuf.Int32Value" => json
+            .as_i64()
+            .and_then(|value| i32::try_from(value).ok())
+            .map(|value| value.encode_to_vec())
+            .ok_or_else(|| expected("a 32-bit integer")),
+        "google.protobuf.Int64Value" => json
+            .as_i64()
+            .map(|value| value.encode_to_vec())
+            .ok_or_else(|| expected("a 64-bit integer")),
+        "google.protobuf.UInt32Value" => json
+            .as_u64()
+            .and_then(|value| u32::try_from(value).ok())
+            .map(|value| value.encode_to_vec())
+            .ok_or_else(|| expected("an unsigned 32-bit integer")),
+        "google.protobuf.UInt64Value" => json
+            .as_u64()
+            .map(|value| value.encode_to_vec())
+            .ok_or_else(|| expected("an unsigned 64-bit integer")),
+        "google.protobuf.FloatValue" => json
+            .as_f64()
+            .map(|value| (value as f32).encode_to_vec())
+            .ok_or_else(|| expected("a number")),
+        "google.protobuf.DoubleValue" => json
+            .as_f64()
+            .map(|value| value.encode_to_vec())
+            .ok_or_else(|| expected("a number")),
+        "google.protobuf.StringValue" => json
+            .as_str()
+            .map(|value| value.to_string().encode_to_vec())
+            .ok_or_else(|| expected("a string")),
+        "google.protobuf.Struct" => match &json {
+            serde_json::Value::Object(fields) => Ok(json_to_struct(fields).encode_to_vec()),
+            _ => Err(expected("an object")),
+        },
+        _ => Err(format!(
+            "Payloads of type '{}' must be written as a quoted string",
+            name
+        )),
+    }
+}
+
+fn struct_to_json(message: &::prost_types::Struct) -> serde_json::Value {
+    serde_json::Value::Object(
+        message
+            .fields
+            .iter()
+            .map(|(name, value)| (name.clone(), value_to_json(value)))
+            .collect(),
+    )
+}
+
+fn value_to_json(value: &::prost_types::Value) -> serde_json::Value {
+    match &value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::NumberValue(number)) => (*number).into(),
+        Some(Kind::StringValue(text)) => text.clone().into(),
+        Some(Kind::BoolValue(flag)) => (*flag).into(),
+        Some(Kind::StructValue(message)) => struct_to_json(message),
+        Some(Kind::ListValue(list)) => {
+            serde_json::Value::Array(list.values.iter().map(value_to_json).collect())
+        }
+    }
+}
+
+fn json_to_struct(fields: &serde_json::Map<String, serde_json::Value>) -> ::prost_types::Struct {
+    ::prost_types::Struct {
+        fields: fields
+            .iter()
+            .map(|(name, value)| (name.clone(), json_to_value(value)))
+            .collect(),
+    }
+}
+
+fn json_to_value(json: &serde_json::Value) -> ::prost_types::Value {
+    let kind = match json {
+        serde_json::Value::Null => Kind::NullValue(::prost_types::NullValue::NullValue.into()),
+        serde_json::Value::Bool(flag) => Kind::BoolValue(*flag),
+        serde_json::Value::Number(number) => Kind::NumberValue(number.as_f64().unwrap_or_default()),
+        serde_json::Value::String(text) => Kind::StringValue(text.clone()),
+        serde_json::Value::Array(items) => Kind::ListValue(::prost_types::ListValue {
+            values: items.iter().map(json_to_value).collect(),
+        }),
+        serde_json::Value::Object(fields) => Kind::StructValue(json_to_struct(fields)),
+    };
+    ::prost_types::Value { kind: Some(kind) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_well_known_payloads_print_as_json() {
+        let url = "type.googleapis.com/google.protobuf.StringValue";
+        let value = "fast".to_string().encode_to_vec();
+        assert_eq!(format_payload(url, &value), r#"`"fast"`"#);
+        assert_eq!(parse_payload(url, r#"`"fast"`"#), Ok(value));
+
+        let url = "type.googleapis.com/google.protobuf.Struct";
+        let text = r#"`{"rows":10.0,"tables":["a","b"]}`"#;
+        let value = parse_payload(url, text).unwrap();
+        assert_eq!(format_payload(url, &value), text);
+
+        let url = "type.googleapis.com/google.protobuf.Int64Value";
+        assert_eq!(format_payload(url, &42_i64.encode_to_vec()), "`42`");
+        assert!(parse_payload(url, r#"`"42"`"#).is_err());
+    }
+
+    #[test]
+    fn test_other_payloads_print_as_bytes() {
+        let url = "type.googleapis.com/acme.TableStats";
+        assert_eq!(format_payload(url, &[0x08, 0x01]), r#""\x08\x01""#);
+        assert_eq!(parse_payload(url, r#""\x08\x01""#), Ok(vec![0x08, 0x01]));
+        assert!(parse_payload(url, "`{}`").is_err());
+
+        // Payloads that would not read back identically keep their serialized form
+        let url = "type.googleapis.com/google.protobuf.BoolValue";
+        assert_eq!(format_payload(url, &[0xff]), r#""\xff""#);
+    }
+}
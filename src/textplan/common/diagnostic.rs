@@ -0,0 +1,446 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured diagnostics reported while parsing a textplan.
+//!
+//! Each diagnostic carries a severity, a stable code that tools can match on, the span
+//! of text it is about and optionally related spans and suggested replacements.
+//! Diagnostics about binary plans have no text and give the path of the message they are
+//! about instead.
+
+use std::fmt;
+
+use crate::textplan::common::location::Location;
+use crate::textplan::common::source_map::{LineColumn, SourceMap};
+use crate::textplan::common::text_location::TextLocation;
+
+/// How serious a diagnostic is.  Only errors make a parse unsuccessful.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// The kind of problem a diagnostic reports.
+///
+/// The codes returned by [`DiagnosticCode::as_str`] are stable and may be relied upon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    /// The text does not follow the textplan grammar.
+    SyntaxError,
+    /// The parser could not be run on the text at all.
+    ParserFailure,
+    /// The plan is grammatically valid but does not make sense.
+    InvalidPlan,
+    /// A function declaration or call does not match its extension file.
+    ExtensionMismatch,
+    /// A type annotation disagrees with the type that was determined.
+    TypeMismatch,
+    /// A column is not in the input of the relation using it.
+    UnknownColumn,
+    /// A function is neither declared nor defined by a known extension.
+    UnknownFunction,
+    /// Pipelines lead from a relation back to itself.
+    Cycle,
+    /// A relation has more or fewer inputs than its type takes.
+    ArityMismatch,
+    /// A relation is defined more than once.
+    DuplicateDefinition,
+}
+
+impl DiagnosticCode {
+    /// Returns the stable identifier of the code (e.g. `E0001`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticCode::SyntaxError => "E0001",
+            DiagnosticCode::ParserFailure => "E0002",
+            DiagnosticCode::InvalidPlan => "E0100",
+            DiagnosticCode::ExtensionMismatch => "E0101",
+            DiagnosticCode::TypeMismatch => "E0102",
+            DiagnosticCode::UnknownColumn => "E0103",
+            DiagnosticCode::UnknownFunction => "E0104",
+            DiagnosticCode::Cycle => "E0105",
+            DiagnosticCode::ArityMismatch => "E0106",
+            DiagnosticCode::DuplicateDefinition => "E0107",
+        }
+    }
+
+    /// Returns true for the codes reported before the plan is analyzed.
+    pub fn is_syntax(&self) -> bool {
+        matches!(
+            self,
+            DiagnosticCode::SyntaxError | DiagnosticCode::ParserFailure
+        )
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Another span of text that helps explain a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedLocation {
+    pub location: TextLocation,
+    pub message: String,
+}
+
+/// A suggested edit replacing the text at a location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixIt {
+    pub location: TextLocation,
+    pub replacement: String,
+}
+
+/// A problem found in a textplan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: DiagnosticCode,
+    message: String,
+    location: TextLocation,
+    related: Vec<RelatedLocation>,
+    fixes: Vec<FixIt>,
+    proto_path: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic.
+    pub fn new(
+        severity: Severity,
+        code: DiagnosticCode,
+        message: impl Into<String>,
+        location: TextLocation,
+    ) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            location,
+            related: Vec::new(),
+            fixes: Vec::new(),
+            proto_path: None,
+        }
+    }
+
+    /// Creates an error diagnostic.
+    pub fn error(code: DiagnosticCode, message: impl Into<String>, location: TextLocation) -> Self {
+        Self::new(Severity::Error, code, message, location)
+    }
+
+    /// Creates a warning diagnostic.
+    pub fn warning(
+        code: DiagnosticCode,
+        message: impl Into<String>,
+        location: TextLocation,
+    ) -> Self {
+        Self::new(Severity::Warning, code, message, location)
+    }
+
+    /// Adds a related location to the diagnostic.
+    pub fn with_related(mut self, location: TextLocation, message: impl Into<String>) -> Self {
+        self.related.push(RelatedLocation {
+            location,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds a suggested replacement to the diagnostic.
+    pub fn with_fix(mut self, location: TextLocation, replacement: impl Into<String>) -> Self {
+        self.fixes.push(FixIt {
+            location,
+            replacement: replacement.into(),
+        });
+        self
+    }
+
+    /// Suggests the name that was likely meant instead of the text at the location.
+    ///
+    /// The suggestion is added to the message and as a replacement of the misspelled text.
+    pub fn with_suggestion(mut self, location: TextLocation, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.message = format!("{}; did you mean '{}'?", self.message, name);
+        self.with_fix(location, name)
+    }
+
+    /// Sets the path (such as `relations[0].root.input`) of the message in a binary plan
+    /// that the diagnostic is about.
+    pub fn with_proto_path(mut self, path: impl Into<String>) -> Self {
+        self.proto_path = Some(path.into());
+        self
+    }
+
+    /// Returns the severity of the diagnostic.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the code identifying the kind of problem.
+    pub fn code(&self) -> DiagnosticCode {
+        self.code
+    }
+
+    /// Returns the message describing the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the span of text the diagnostic is about.
+    pub fn location(&self) -> TextLocation {
+        self.location
+    }
+
+    /// Returns the other spans of text that help explain the diagnostic.
+    pub fn related(&self) -> &[RelatedLocation] {
+        &self.related
+    }
+
+    /// Returns the suggested replacements.
+    pub fn fixes(&self) -> &[FixIt] {
+        &self.fixes
+    }
+
+    /// Returns the path of the message in a binary plan the diagnostic is about, if any.
+    pub fn proto_path(&self) -> Option<&str> {
+        self.proto_path.as_deref()
+    }
+
+    /// Returns true if the diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Formats the diagnostic on one line as `error[E0001] at 42..45: message`.
+///
+/// Without the text the span can only be given as byte offsets, use
+/// [`Diagnostic::format_with`] to show its line and column instead.  Diagnostics about a
+/// binary plan show their path instead, as in `error[E0100] at relations[0].rel: message`.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.severity, self.code)?;
+        if !self.location.is_unknown() {
+            write!(
+                f,
+                " at {}..{}",
+                self.location.position(),
+                self.location.position() + self.location.length()
+            )?;
+        } else if let Some(path) = &self.proto_path {
+            write!(f, " at {}", path)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Diagnostic {
+    /// Formats the diagnostic on one line as `error[E0001] 3:5-3:9: message`.
+    ///
+    /// The span is given as 1-based line and column numbers, and is left out if the
+    /// location is unknown.
+    pub fn format_with(&self, source_map: &SourceMap) -> String {
+        match span(source_map, self.location) {
+            Some((start, end)) => format!(
+                "{}[{}] {}:{}-{}:{}: {}",
+                self.severity,
+                self.code,
+                start.line,
+                start.column,
+                end.line,
+                end.column,
+                self.message
+            ),
+            None => match &self.proto_path {
+                Some(path) => format!(
+                    "{}[{}] {}: {}",
+                    self.severity, self.code, path, self.message
+                ),
+                None => format!("{}[{}]: {}", self.severity, self.code, self.message),
+            },
+        }
+    }
+
+    /// Renders the diagnostic with the lines of text it refers to, in the style of rustc.
+    ///
+    /// ```text
+    /// error[E0001]: extraneous input 'i64' expecting ';'
+    ///  --> 3:9
+    ///   |
+    /// 3 |   b i64 i64;
+    ///   |         ^^^
+    /// ```
+    pub fn render(&self, source_map: &SourceMap) -> String {
+        let mut lines = Vec::new();
+        let mut snippets = vec![(self.location, '^', String::new())];
+        snippets.extend(
+            self.related
+                .iter()
+                .map(|related| (related.location, '-', related.message.clone())),
+        );
+        let gutter = snippets
+            .iter()
+            .filter_map(|(location, _, _)| span(source_map, *location))
+            .map(|(start, _)| start.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let padding = " ".repeat(gutter);
+
+        lines.push(format!(
+            "{}[{}]: {}",
+            self.severity, self.code, self.message
+        ));
+        let mut notes = Vec::new();
+        let mut shown_header = false;
+        for (location, marker, label) in snippets {
+            let Some((start, end)) = span(source_map, location) else {
+                if !label.is_empty() {
+                    notes.push(format!("{} = note: {}", padding, label));
+                }
+                continue;
+            };
+            let Some(text) = source_map.line_text(start.line) else {
+                continue;
+            };
+            if !shown_header {
+                lines.push(format!("{}--> {}:{}", padding, start.line, start.column));
+                shown_header = true;
+            }
+            // Only the first line of a span that covers several lines is underlined
+            let width = if end.line == start.line {
+                end.column.saturating_sub(start.column)
+            } else {
+                text.chars().count() + 1 - start.column
+            };
+            // Keep the tabs before the span so that the markers line up with the text
+            let indent: String = text
+                .chars()
+                .take(start.column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let underline = marker.to_string().repeat(width.max(1));
+            lines.push(format!("{} |", padding));
+            lines.push(format!("{:>gutter$} | {}", start.line, text));
+            let label = if label.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", label)
+            };
+            lines.push(format!("{} | {}{}{}", padding, indent, underline, label));
+        }
+        if shown_header {
+            lines.push(format!("{} |", padding));
+        } else if let Some(path) = &self.proto_path {
+            lines.push(format!("{}--> {}", padding, path));
+        }
+        lines.extend(notes);
+        for fix in &self.fixes {
+            lines.push(format!(
+                "{} = help: replace with `{}`",
+                padding, fix.replacement
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Returns the first and last line and column covered by a location.
+fn span(source_map: &SourceMap, location: TextLocation) -> Option<(LineColumn, LineColumn)> {
+    if location.is_unknown() {
+        return None;
+    }
+    let start = location.position() as usize;
+    let end = start + location.length().max(0) as usize;
+    Some((source_map.line_column(start), source_map.line_column(end)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let source_map = SourceMap::new("schema s {\n  a i64;\n  b i64;;\n}\n");
+        let diagnostic = Diagnostic::error(
+            DiagnosticCode::SyntaxError,
+            "extraneous input ';'",
+            TextLocation::new(28, 1),
+        );
+        assert_eq!(
+            diagnostic.to_string(),
+            "error[E0001] at 28..29: extraneous input ';'"
+        );
+        assert_eq!(
+            diagnostic.format_with(&source_map),
+            "error[E0001] 3:9-3:10: extraneous input ';'"
+        );
+        assert!(diagnostic.code().is_syntax());
+
+        let diagnostic = Diagnostic::warning(
+            DiagnosticCode::InvalidPlan,
+            "unused schema",
+            TextLocation::unknown(),
+        );
+        assert_eq!(diagnostic.to_string(), "warning[E0100]: unused schema");
+        assert_eq!(
+            diagnostic.format_with(&source_map),
+            "warning[E0100]: unused schema"
+        );
+        assert!(!diagnostic.is_error());
+
+        let diagnostic = Diagnostic::error(
+            DiagnosticCode::InvalidPlan,
+            "missing input",
+            TextLocation::unknown(),
+        )
+        .with_proto_path("relations[0].root");
+        assert_eq!(
+            diagnostic.to_string(),
+            "error[E0100] at relations[0].root: missing input"
+        );
+        assert_eq!(
+            diagnostic.format_with(&source_map),
+            "error[E0100] relations[0].root: missing input"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let source_map = SourceMap::new("schema s {\n  a i64;\n  a\ti64;\n}\n");
+        let diagnostic = Diagnostic::error(
+            DiagnosticCode::InvalidPlan,
+            "duplicate field a",
+            TextLocation::new(22, 1),
+        )
+        .with_related(TextLocation::new(13, 1), "first defined here")
+        .with_fix(TextLocation::new(22, 1), "b");
+        assert_eq!(
+            diagnostic.render(&source_map),
+            [
+                "error[E0100]: duplicate field a",
+                " --> 3:3",
+                "  |",
+                "3 |   a\ti64;",
+                "  |   ^",
+                "  |",
+                "2 |   a i64;",
+                "  |   - first defined here",
+                "  |",
+                "  = help: replace with `b`",
+            ]
+            .join("\n")
+        );
+    }
+}
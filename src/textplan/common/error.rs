@@ -11,6 +11,10 @@ pub enum TextPlanError {
     #[error("Parse error: {0}")]
     ParseError(String),
 
+    /// An error in the meaning of a plan that parsed correctly.
+    #[error("Semantic error: {0}")]
+    SemanticError(String),
+
     /// An error that occurred during binary conversion.
     #[error("Binary conversion error: {0}")]
     BinaryConversionError(String),
@@ -0,0 +1,831 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! A registry of the functions defined by Substrait simple extension YAML files.
+//!
+//! The registry is used to check the functions declared in `extension_space` blocks
+//! against the extension files they claim to come from, and to pick the implementation
+//! of functions that are called by their plain name without being declared.
+
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use ::substrait::proto::Type;
+use serde::Deserialize;
+
+use crate::textplan::common::error::TextPlanError;
+use crate::textplan::common::type_derivation;
+use crate::textplan::common::type_utils;
+
+/// The standard extension files, vendored unmodified from the Substrait release the library
+/// is built against so that they are available wherever the library is used.
+const STANDARD_EXTENSIONS: &[(&str, &str)] = &[
+    (
+        "functions_aggregate_approx.yaml",
+        include_str!("extensions/functions_aggregate_approx.yaml"),
+    ),
+    (
+        "functions_aggregate_decimal_output.yaml",
+        include_str!("extensions/functions_aggregate_decimal_output.yaml"),
+    ),
+    (
+        "functions_aggregate_generic.yaml",
+        include_str!("extensions/functions_aggregate_generic.yaml"),
+    ),
+    (
+        "functions_arithmetic.yaml",
+        include_str!("extensions/functions_arithmetic.yaml"),
+    ),
+    (
+        "functions_arithmetic_decimal.yaml",
+        include_str!("extensions/functions_arithmetic_decimal.yaml"),
+    ),
+    (
+        "functions_boolean.yaml",
+        include_str!("extensions/functions_boolean.yaml"),
+    ),
+    (
+        "functions_comparison.yaml",
+        include_str!("extensions/functions_comparison.yaml"),
+    ),
+    (
+        "functions_datetime.yaml",
+        include_str!("extensions/functions_datetime.yaml"),
+    ),
+    (
+        "functions_geometry.yaml",
+        include_str!("extensions/functions_geometry.yaml"),
+    ),
+    (
+        "functions_logarithmic.yaml",
+        include_str!("extensions/functions_logarithmic.yaml"),
+    ),
+    (
+        "functions_rounding.yaml",
+        include_str!("extensions/functions_rounding.yaml"),
+    ),
+    (
+        "functions_rounding_decimal.yaml",
+        include_str!("extensions/functions_rounding_decimal.yaml"),
+    ),
+    (
+        "functions_set.yaml",
+        include_str!("extensions/functions_set.yaml"),
+    ),
+    (
+        "functions_string.yaml",
+        include_str!("extensions/functions_string.yaml"),
+    ),
+];
+
+/// Where the standard extension files are published, used as the URI of extension files
+/// that were registered by file name only.
+pub const STANDARD_EXTENSIONS_URI_PREFIX: &str =
+    "https://github.com/substrait-io/substrait/blob/main/extensions/";
+
+/// The kind of function an extension defines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    Scalar,
+    Aggregate,
+    Window,
+}
+
+/// One argument of a function implementation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtensionArgument {
+    /// A value argument with its type expression (e.g. `i32`, `decimal<P1,S1>`, `any1`).
+    Value(String),
+    /// An enumeration argument with its allowed options.
+    Enum(Vec<String>),
+}
+
+/// One implementation (overload) of an extension function.
+#[derive(Debug, Clone)]
+pub struct FunctionImplementation {
+    pub args: Vec<ExtensionArgument>,
+    /// The minimum number of times the last argument repeats, if it is variadic.
+    pub variadic_min: Option<u32>,
+    /// The nullability handling (`MIRROR`, `DECLARED_OUTPUT` or `DISCRETE`).
+    pub nullability: Option<String>,
+    /// The return type expression, which may be a multi-line derivation program.
+    pub return_type: String,
+}
+
+impl FunctionImplementation {
+    /// Returns the compound signature suffix of the implementation (e.g. `i32_i32`).
+    pub fn signature(&self) -> String {
+        self.args
+            .iter()
+            .map(|arg| match arg {
+                ExtensionArgument::Value(type_text) => signature_type_code(type_text),
+                ExtensionArgument::Enum(_) => "req".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("_")
+    }
+}
+
+/// A function defined by an extension file, with all of its implementations.
+#[derive(Debug, Clone)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub kind: FunctionKind,
+    pub implementations: Vec<FunctionImplementation>,
+}
+
+/// The functions defined by one extension file.
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionFile {
+    pub uri: String,
+    /// The URN newer extension files are also identified by.
+    pub urn: Option<String>,
+    pub functions: Vec<FunctionDefinition>,
+}
+
+impl ExtensionFile {
+    /// Returns the definition of the named function, if the file defines it.
+    pub fn function(&self, name: &str) -> Option<&FunctionDefinition> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Returns every implementation of the named function along with its definition, as a
+    /// file may define a function more than once (e.g. `count` of values and of rows).
+    pub fn implementations<'a, 'b>(
+        &'a self,
+        name: &'b str,
+    ) -> impl Iterator<Item = (&'a FunctionDefinition, &'a FunctionImplementation)> + 'b
+    where
+        'a: 'b,
+    {
+        self.functions
+            .iter()
+            .filter(move |function| function.name == name)
+            .flat_map(|function| {
+                function
+                    .implementations
+                    .iter()
+                    .map(move |implementation| (function, implementation))
+            })
+    }
+}
+
+/// One argument of a function call, used to pick an implementation.
+#[derive(Debug, Clone)]
+pub enum CallArgument {
+    /// A value argument with its type, if the type could be determined.
+    Value(Option<Type>),
+    /// An enumeration argument with the option it selects.
+    Enum(String),
+}
+
+/// The implementation chosen for a function call.
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedFunction<'a> {
+    pub file: &'a ExtensionFile,
+    pub function: &'a FunctionDefinition,
+    pub implementation: &'a FunctionImplementation,
+}
+
+impl ResolvedFunction<'_> {
+    /// Returns the compound name to declare the implementation with (e.g. `add:i32_i32`).
+    pub fn declaration_name(&self) -> String {
+        format!("{}:{}", self.function.name, self.implementation.signature())
+    }
+
+    /// Returns the URI to declare the extension file with.
+    pub fn extension_uri(&self) -> String {
+        if self.file.uri.contains('/') || self.file.uri.contains(':') {
+            self.file.uri.clone()
+        } else {
+            format!("{}{}", STANDARD_EXTENSIONS_URI_PREFIX, self.file.uri)
+        }
+    }
+
+    /// Derives the return type of the implementation for the call arguments, if possible.
+    pub fn return_type(&self, arguments: &[CallArgument]) -> Option<Type> {
+        type_derivation::derive_return_type(self.implementation, arguments)
+    }
+}
+
+/// A collection of extension files, looked up by URI.
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionRegistry {
+    files: Vec<ExtensionFile>,
+}
+
+#[derive(Deserialize)]
+struct YamlExtensionFile {
+    #[serde(default)]
+    urn: Option<String>,
+    #[serde(default)]
+    scalar_functions: Vec<YamlFunction>,
+    #[serde(default)]
+    aggregate_functions: Vec<YamlFunction>,
+    #[serde(default)]
+    window_functions: Vec<YamlFunction>,
+}
+
+#[derive(Deserialize)]
+struct YamlFunction {
+    name: String,
+    #[serde(default)]
+    impls: Vec<YamlImplementation>,
+}
+
+#[derive(Deserialize)]
+struct YamlImplementation {
+    #[serde(default)]
+    args: Vec<YamlArgument>,
+    #[serde(default)]
+    variadic: Option<YamlVariadic>,
+    #[serde(default)]
+    nullability: Option<String>,
+    #[serde(rename = "return", default)]
+    return_type: String,
+}
+
+#[derive(Deserialize)]
+struct YamlArgument {
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default)]
+    options: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct YamlVariadic {
+    #[serde(default)]
+    min: Option<u32>,
+}
+
+impl ExtensionRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the registry of the standard extension files bundled with the library.
+    pub fn standard() -> Arc<ExtensionRegistry> {
+        static STANDARD: OnceLock<Arc<ExtensionRegistry>> = OnceLock::new();
+        STANDARD
+            .get_or_init(|| {
+                let mut registry = ExtensionRegistry::new();
+                for (uri, text) in STANDARD_EXTENSIONS {
+                    if let Err(err) = registry.add_yaml(uri, text) {
+                        log::warn!("Unable to load the standard extensions: {}", err);
+                    }
+                }
+                Arc::new(registry)
+            })
+            .clone()
+    }
+
+    /// Loads every `.yaml` file in a directory.
+    pub fn load_directory(&mut self, dir: impl AsRef<Path>) -> Result<(), TextPlanError> {
+        let mut paths = std::fs::read_dir(dir.as_ref())?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            self.load_file(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Loads a single extension file, registering it under its file name.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), TextPlanError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let uri = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.add_yaml(&uri, &text)
+    }
+
+    /// Parses the text of an extension file and registers it under the given URI.
+    pub fn add_yaml(&mut self, uri: &str, text: &str) -> Result<(), TextPlanError> {
+        let yaml: YamlExtensionFile = serde_yaml::from_str(text).map_err(|err| {
+            TextPlanError::ParseError(format!("Invalid extension file '{}': {}", uri, err))
+        })?;
+
+        let mut file = ExtensionFile {
+            uri: uri.to_string(),
+            urn: yaml.urn,
+            functions: Vec::new(),
+        };
+        for (kind, functions) in [
+            (FunctionKind::Scalar, yaml.scalar_functions),
+            (FunctionKind::Aggregate, yaml.aggregate_functions),
+            (FunctionKind::Window, yaml.window_functions),
+        ] {
+            for function in functions {
+                file.functions.push(FunctionDefinition {
+                    name: function.name,
+                    kind,
+                    implementations: function
+                        .impls
+                        .into_iter()
+                        .map(|implementation| FunctionImplementation {
+                            args: implementation
+                                .args
+                                .into_iter()
+                                .filter_map(|arg| match (arg.value, arg.options) {
+                                    (Some(value), _) => Some(ExtensionArgument::Value(value)),
+                                    (None, Some(options)) => Some(ExtensionArgument::Enum(options)),
+                                    (None, None) => None,
+                                })
+                                .collect(),
+                            variadic_min: implementation
+                                .variadic
+                                .map(|variadic| variadic.min.unwrap_or(0)),
+                            nullability: implementation.nullability,
+                            return_type: implementation.return_type.trim().to_string(),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        self.files.push(file);
+        Ok(())
+    }
+
+    /// Returns true if no extension files have been loaded.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns all of the loaded extension files.
+    pub fn files(&self) -> &[ExtensionFile] {
+        &self.files
+    }
+
+    /// Finds the extension file for a URI.
+    ///
+    /// Files are found by their URI or URN.  Plans usually refer to the standard extensions
+    /// by a full URL, so a file registered under its file name also matches any URI ending
+    /// in that file name.
+    pub fn find_extension(&self, uri: &str) -> Option<&ExtensionFile> {
+        let file_name = uri.rsplit('/').next().unwrap_or(uri);
+        self.files
+            .iter()
+            .find(|file| file.uri == uri || file.urn.as_deref() == Some(uri))
+            .or_else(|| self.files.iter().find(|file| file.uri == file_name))
+    }
+
+    /// Checks a function declaration such as `add:i32_i32` against the extension at `uri`.
+    ///
+    /// Declarations without a signature only need the function to exist.  Declarations
+    /// from extensions that are not in the registry are accepted as they cannot be checked,
+    /// as are signatures using the type codes from before the interval types were renamed.
+    pub fn validate_declaration(&self, uri: &str, declaration: &str) -> Result<(), String> {
+        let Some(file) = self.find_extension(uri) else {
+            return Ok(());
+        };
+        let (name, signature) = match declaration.split_once(':') {
+            Some((name, signature)) => (name, Some(signature)),
+            None => (declaration, None),
+        };
+        if file.function(name).is_none() {
+            return Err(format!(
+                "Function '{}' is not defined in extension '{}'",
+                name, uri
+            ));
+        }
+        let Some(signature) = signature else {
+            return Ok(());
+        };
+        let signatures: Vec<String> = file
+            .implementations(name)
+            .map(|(_, implementation)| implementation.signature())
+            .collect();
+        let signature = current_signature(signature);
+        if signatures.contains(&signature) {
+            return Ok(());
+        }
+        Err(format!(
+            "Function '{}' in extension '{}' has no implementation with signature '{}' (available: {})",
+            name,
+            uri,
+            signature,
+            signatures.join(", ")
+        ))
+    }
+
+    /// Finds the implementation a declaration such as `add:i32_i32` refers to.
+    ///
+    /// Declarations without a signature only refer to an implementation if it is the one
+    /// implementation accepting the call arguments.  Signatures are matched exactly, as an
+    /// implementation named with legacy type codes may have been defined differently at
+    /// the time.
+    pub fn find_declared_function(
+        &self,
+        uri: &str,
+        declaration: &str,
+        arguments: &[CallArgument],
+    ) -> Option<ResolvedFunction<'_>> {
+        let file = self.find_extension(uri)?;
+        let (name, signature) = match declaration.split_once(':') {
+            Some((name, signature)) => (name, Some(signature)),
+            None => (declaration, None),
+        };
+        let mut matches =
+            file.implementations(name)
+                .filter(|(_, implementation)| match signature {
+                    Some(signature) => implementation.signature() == signature,
+                    None => implementation_accepts(implementation, arguments),
+                });
+        let (function, implementation) = matches.next()?;
+        if matches.next().is_some() {
+            return None;
+        }
+        Some(ResolvedFunction {
+            file,
+            function,
+            implementation,
+        })
+    }
+
+    /// Picks the implementation of a function called by its plain name.
+    ///
+    /// Returns `Ok(None)` if no extension file defines a function of that name and kind,
+    /// and an error if one does but none of its implementations accepts the arguments.
+    /// Arguments of unknown type match any parameter type, so a call that more than one
+    /// implementation accepts is reported as ambiguous rather than guessed at.
+    pub fn resolve_function(
+        &self,
+        name: &str,
+        kind: FunctionKind,
+        arguments: &[CallArgument],
+    ) -> Result<Option<ResolvedFunction<'_>>, String> {
+        let mut matches = Vec::new();
+        let mut candidates = Vec::new();
+        // A file may define a function more than once (e.g. `count` of values and of rows)
+        for (file, function) in self.files.iter().flat_map(|file| {
+            file.functions
+                .iter()
+                .filter(move |function| function.name == name && function.kind == kind)
+                .map(move |function| (file, function))
+        }) {
+            for implementation in &function.implementations {
+                if implementation_accepts(implementation, arguments) {
+                    matches.push(ResolvedFunction {
+                        file,
+                        function,
+                        implementation,
+                    });
+                }
+                let signature = implementation.signature();
+                if !candidates.contains(&signature) {
+                    candidates.push(signature);
+                }
+            }
+        }
+        match matches.as_slice() {
+            [resolved] => return Ok(Some(*resolved)),
+            [] if candidates.is_empty() => return Ok(None),
+            [] => {}
+            _ => {
+                return Err(format!(
+                    "Function '{}' is ambiguous for arguments ({}) as it matches {}; declare the \
+                     implementation to use in an extension_space",
+                    name,
+                    argument_codes(arguments),
+                    matches
+                        .iter()
+                        .map(ResolvedFunction::declaration_name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        }
+        Err(format!(
+            "No implementation of function '{}' accepts arguments ({}) (available: {})",
+            name,
+            argument_codes(arguments),
+            candidates.join(", ")
+        ))
+    }
+}
+
+/// Describes call arguments by their signature codes, using `?` for unknown types.
+fn argument_codes(arguments: &[CallArgument]) -> String {
+    arguments
+        .iter()
+        .map(|argument| match argument {
+            CallArgument::Value(Some(type_val)) => type_signature_code(type_val),
+            CallArgument::Value(None) => "?".to_string(),
+            CallArgument::Enum(_) => "req".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Signature codes that older plans use for the interval types.
+const LEGACY_SIGNATURE_CODES: &[(&str, &str)] = &[("year", "iyear"), ("day", "iday")];
+
+/// Rewrites the legacy type codes in a compound signature (e.g. `date_year`) to the ones
+/// the extension files use today (e.g. `date_iyear`).
+fn current_signature(signature: &str) -> String {
+    signature
+        .split('_')
+        .map(|code| {
+            LEGACY_SIGNATURE_CODES
+                .iter()
+                .find(|(legacy, _)| *legacy == code)
+                .map_or(code, |(_, current)| current)
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Returns true if the implementation accepts the call arguments.
+fn implementation_accepts(
+    implementation: &FunctionImplementation,
+    arguments: &[CallArgument],
+) -> bool {
+    let parameters = &implementation.args;
+    let arity_matches = match implementation.variadic_min {
+        Some(min) => {
+            !parameters.is_empty() && arguments.len() + 1 >= parameters.len() + min as usize
+        }
+        None => arguments.len() == parameters.len(),
+    };
+    if !arity_matches {
+        return false;
+    }
+
+    // Type parameters such as any1 must bind to the same type for every argument
+    let mut bindings: Vec<(String, &Type)> = Vec::new();
+    arguments.iter().enumerate().all(|(index, argument)| {
+        let parameter = &parameters[index.min(parameters.len() - 1)];
+        match (parameter, argument) {
+            (ExtensionArgument::Enum(options), CallArgument::Enum(option)) => options
+                .iter()
+                .any(|candidate| candidate.eq_ignore_ascii_case(option)),
+            (ExtensionArgument::Value(_), CallArgument::Value(None)) => true,
+            (ExtensionArgument::Value(type_text), CallArgument::Value(Some(type_val))) => {
+                if signature_type_code(type_text) != "any" {
+                    return signature_type_code(type_text) == type_signature_code(type_val);
+                }
+                let variable = type_text.trim().trim_end_matches('?').to_lowercase();
+                match bindings.iter().find(|(name, _)| *name == variable) {
+                    Some((_, bound)) => type_utils::same_type_kind(bound, type_val),
+                    None => {
+                        bindings.push((variable, type_val));
+                        true
+                    }
+                }
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Returns the compound signature code of a type (e.g. `i32`, `str`, `dec`).
+pub fn type_signature_code(type_val: &Type) -> String {
+    signature_type_code(type_utils::type_kind_name(type_val))
+}
+
+/// Returns the compound signature code of an extension type expression.
+///
+/// For instance `i32` stays `i32`, `string` becomes `str` and `decimal<P1,S1>` becomes `dec`.
+pub fn signature_type_code(type_text: &str) -> String {
+    let base = type_text
+        .split('<')
+        .next()
+        .unwrap_or(type_text)
+        .trim()
+        .trim_end_matches('?')
+        .to_lowercase();
+    if base.starts_with("any") {
+        return "any".to_string();
+    }
+    if base.starts_with("u!") {
+        return base;
+    }
+    match base.as_str() {
+        "boolean" => "bool",
+        "string" => "str",
+        "binary" => "vbin",
+        "timestamp" => "ts",
+        "timestamp_tz" => "tstz",
+        "interval_year" => "iyear",
+        "interval_day" => "iday",
+        "interval_compound" => "icompound",
+        "fixedchar" => "fchar",
+        "varchar" => "vchar",
+        "fixedbinary" => "fbin",
+        "decimal" => "dec",
+        "precision_time" => "pt",
+        "precision_timestamp" => "pts",
+        "precision_timestamp_tz" => "ptstz",
+        other => other,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARITHMETIC: &str = r#"
+scalar_functions:
+  - name: "add"
+    impls:
+      - args:
+          - name: x
+            value: i32
+          - name: y
+            value: i32
+        options:
+          overflow:
+            values: [ SILENT, SATURATE, ERROR ]
+        return: i32
+      - args:
+          - value: decimal<P1,S1>
+          - value: decimal<P2,S2>
+        return: |-
+          init_scale = max(S1,S2)
+          DECIMAL<38, init_scale>
+aggregate_functions:
+  - name: "count"
+    impls:
+      - args:
+          - value: any
+        nullability: DECLARED_OUTPUT
+        return: i64
+"#;
+
+    #[test]
+    fn test_validate_declaration() {
+        let mut registry = ExtensionRegistry::new();
+        registry
+            .add_yaml("functions_arithmetic.yaml", ARITHMETIC)
+            .unwrap();
+        let uri = "https://github.com/substrait-io/substrait/blob/main/extensions/functions_arithmetic.yaml";
+
+        assert!(registry.validate_declaration(uri, "add:i32_i32").is_ok());
+        assert!(registry.validate_declaration(uri, "add:dec_dec").is_ok());
+        assert!(registry.validate_declaration(uri, "count:any").is_ok());
+        assert!(registry.validate_declaration(uri, "add").is_ok());
+        assert!(registry.validate_declaration(uri, "add:str_str").is_err());
+        assert!(registry
+            .validate_declaration(uri, "subtract:i32_i32")
+            .is_err());
+        // Extensions the registry does not know about cannot be checked
+        assert!(registry
+            .validate_declaration("/custom.yaml", "anything:i32")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_resolve_function() {
+        use ::substrait::proto::r#type::{Kind, String as StringType, I32};
+
+        let mut registry = ExtensionRegistry::new();
+        registry
+            .add_yaml("functions_arithmetic.yaml", ARITHMETIC)
+            .unwrap();
+        let i32_type = Type {
+            kind: Some(Kind::I32(I32::default())),
+        };
+        let string_type = Type {
+            kind: Some(Kind::String(StringType::default())),
+        };
+
+        let resolved = registry
+            .resolve_function(
+                "add",
+                FunctionKind::Scalar,
+                &[
+                    CallArgument::Value(Some(i32_type.clone())),
+                    CallArgument::Value(None),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.declaration_name(), "add:i32_i32");
+        assert_eq!(
+            resolved.extension_uri(),
+            format!(
+                "{}functions_arithmetic.yaml",
+                STANDARD_EXTENSIONS_URI_PREFIX
+            )
+        );
+
+        let resolved = registry
+            .resolve_function(
+                "count",
+                FunctionKind::Aggregate,
+                &[CallArgument::Value(Some(string_type.clone()))],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.declaration_name(), "count:any");
+
+        assert!(registry
+            .resolve_function(
+                "add",
+                FunctionKind::Scalar,
+                &[
+                    CallArgument::Value(Some(i32_type)),
+                    CallArgument::Value(Some(string_type)),
+                ],
+            )
+            .is_err());
+        // Arguments of unknown type cannot tell the implementations apart
+        let ambiguous = registry
+            .resolve_function(
+                "add",
+                FunctionKind::Scalar,
+                &[CallArgument::Value(None), CallArgument::Value(None)],
+            )
+            .unwrap_err();
+        assert!(ambiguous.contains("ambiguous"), "{}", ambiguous);
+        assert!(
+            ambiguous.contains("add:i32_i32, add:dec_dec"),
+            "{}",
+            ambiguous
+        );
+        assert!(registry
+            .find_declared_function(
+                "functions_arithmetic.yaml",
+                "add",
+                &[CallArgument::Value(None), CallArgument::Value(None)],
+            )
+            .is_none());
+        assert!(registry
+            .resolve_function("count", FunctionKind::Scalar, &[])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_standard_extensions() {
+        use ::substrait::proto::r#type::{Kind, I64};
+
+        let registry = ExtensionRegistry::standard();
+        assert!(registry
+            .find_extension(
+                "https://github.com/substrait-io/substrait/blob/main/extensions/functions_comparison.yaml"
+            )
+            .is_some());
+        assert!(registry
+            .validate_declaration("/functions_arithmetic_decimal.yaml", "sum:dec")
+            .is_ok());
+        assert!(registry
+            .validate_declaration("/functions_rounding.yaml", "ceil:fp64")
+            .is_ok());
+        assert!(registry
+            .validate_declaration(
+                "extension:io.substrait:functions_string",
+                "like:vchar_vchar"
+            )
+            .is_ok());
+        // Older plans name the interval types by their legacy codes
+        assert!(registry
+            .validate_declaration("/functions_datetime.yaml", "add:date_year")
+            .is_ok());
+        assert!(registry
+            .validate_declaration("/functions_datetime.yaml", "add:date_str")
+            .is_err());
+
+        // Each file is registered once and also found by its URN
+        assert_eq!(registry.files().len(), STANDARD_EXTENSIONS.len());
+        let file = registry
+            .find_extension("extension:io.substrait:functions_comparison")
+            .unwrap();
+        assert_eq!(file.uri, "functions_comparison.yaml");
+
+        let i64_type = Type {
+            kind: Some(Kind::I64(I64::default())),
+        };
+        let resolved = registry
+            .resolve_function(
+                "add",
+                FunctionKind::Scalar,
+                &[
+                    CallArgument::Value(Some(i64_type.clone())),
+                    CallArgument::Value(Some(i64_type)),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.declaration_name(), "add:i64_i64");
+        assert_eq!(resolved.file.uri, "functions_arithmetic.yaml");
+
+        // Counting rows is a separate definition of the same function, which is defined
+        // again by the extension returning decimals
+        let ambiguous = registry
+            .resolve_function("count", FunctionKind::Aggregate, &[])
+            .unwrap_err();
+        assert!(ambiguous.contains("ambiguous"), "{}", ambiguous);
+        let resolved = registry
+            .find_declared_function("/functions_aggregate_generic.yaml", "count:", &[])
+            .unwrap();
+        assert_eq!(resolved.implementation.return_type, "i64");
+    }
+}
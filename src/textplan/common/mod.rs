@@ -2,13 +2,23 @@
 
 //! Common utilities and types for the textplan module.
 
+pub mod advanced_extension;
+pub mod diagnostic;
 pub mod error;
+pub mod extension_registry;
 pub mod location;
 pub mod parse_result;
+pub mod projection_mask;
 pub mod proto_location;
+pub mod rel_common;
+pub mod relation_schema;
+pub mod source_map;
 pub mod string_utils;
 pub mod structured_symbol_data;
+pub mod temporal;
 pub mod text_location;
+pub mod type_derivation;
+pub mod type_utils;
 pub mod unknown_location;
 
 // Re-export the trait and concrete location types
@@ -4,35 +4,38 @@
 
 use std::fmt;
 
+use crate::textplan::common::diagnostic::Diagnostic;
+use crate::textplan::common::source_map::SourceMap;
 use crate::textplan::symbol_table::SymbolTable;
 
 /// Represents the result of parsing a textplan.
 ///
-/// This contains the symbol table, any syntax errors, and any semantic errors.
+/// This contains the symbol table, the diagnostics reported while parsing and the source
+/// map used to show where in the text they were found.
 #[derive(Debug)]
 pub struct ParseResult {
     symbol_table: SymbolTable,
-    syntax_errors: Vec<String>,
-    semantic_errors: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+    source_map: SourceMap,
 }
 
 impl ParseResult {
-    /// Creates a new ParseResult with the given symbol table and errors.
+    /// Creates a new ParseResult with the given symbol table, diagnostics and source map.
     pub fn new(
         symbol_table: SymbolTable,
-        syntax_errors: Vec<String>,
-        semantic_errors: Vec<String>,
+        diagnostics: Vec<Diagnostic>,
+        source_map: SourceMap,
     ) -> Self {
         Self {
             symbol_table,
-            syntax_errors,
-            semantic_errors,
+            diagnostics,
+            source_map,
         }
     }
 
     /// Returns true if the parse was successful (no errors).
     pub fn successful(&self) -> bool {
-        self.syntax_errors.is_empty() && self.semantic_errors.is_empty()
+        !self.diagnostics.iter().any(Diagnostic::is_error)
     }
 
     /// Returns a reference to the symbol table.
@@ -40,26 +43,52 @@ impl ParseResult {
         &self.symbol_table
     }
 
-    /// Returns a reference to the syntax errors.
-    pub fn syntax_errors(&self) -> &[String] {
-        &self.syntax_errors
+    /// Returns all of the diagnostics, including warnings.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
     }
 
-    /// Returns a reference to the semantic errors.
-    pub fn semantic_errors(&self) -> &[String] {
-        &self.semantic_errors
+    /// Returns the source map of the parsed text.
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
     }
 
-    /// Returns a vector of all errors (syntax and semantic).
+    /// Returns the errors rendered with the lines of text they refer to.
+    pub fn render_errors(&self) -> String {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error())
+            .map(|diagnostic| diagnostic.render(&self.source_map))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Returns the syntax errors as strings.
+    pub fn syntax_errors(&self) -> Vec<String> {
+        self.format_errors(|diagnostic| diagnostic.code().is_syntax())
+    }
+
+    /// Returns the semantic errors as strings.
+    pub fn semantic_errors(&self) -> Vec<String> {
+        self.format_errors(|diagnostic| !diagnostic.code().is_syntax())
+    }
+
+    /// Returns a vector of all errors (syntax and semantic) as strings.
     pub fn all_errors(&self) -> Vec<String> {
-        let mut errors = self.syntax_errors.clone();
-        errors.extend_from_slice(&self.semantic_errors);
-        errors
+        self.format_errors(|_| true)
+    }
+
+    /// Adds the given diagnostics.
+    pub fn add_diagnostics(&mut self, diagnostics: impl IntoIterator<Item = Diagnostic>) {
+        self.diagnostics.extend(diagnostics);
     }
 
-    /// Adds the given errors to the syntax errors.
-    pub fn add_errors(&mut self, errors: &[String]) {
-        self.syntax_errors.extend_from_slice(errors);
+    fn format_errors(&self, filter: impl Fn(&Diagnostic) -> bool) -> Vec<String> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error() && filter(diagnostic))
+            .map(|diagnostic| diagnostic.format_with(&self.source_map))
+            .collect()
     }
 }
 
@@ -69,10 +98,7 @@ impl fmt::Display for ParseResult {
             write!(f, "Successful parse")
         } else {
             writeln!(f, "Parse failed with {} errors:", self.all_errors().len())?;
-            for error in self.all_errors() {
-                writeln!(f, "  {}", error)?;
-            }
-            Ok(())
+            writeln!(f, "{}", self.render_errors())
         }
     }
 }
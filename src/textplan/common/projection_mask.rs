@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Text form of the nested selections inside projection masks.
+//!
+//! `{N, ...}` selects struct fields by position, `[N, M:K, ...]` list elements and
+//! slices, `["key"]` a map key and `[expression "pattern"]` the map keys matching a
+//! pattern.  List and map selections may be followed by a selection on their elements.
+
+use ::substrait::proto::expression::mask_expression::list_select::list_select_item::{
+    self, ListElement, ListSlice,
+};
+use ::substrait::proto::expression::mask_expression::list_select::ListSelectItem;
+use ::substrait::proto::expression::mask_expression::map_select::{self, MapKey, MapKeyExpression};
+use ::substrait::proto::expression::mask_expression::{
+    select, ListSelect, MapSelect, Select, StructSelect,
+};
+
+use crate::textplan::common::string_utils::escape_string;
+
+/// Formats a nested selection, e.g. `{0, 2[1:3]}`.
+pub fn format_select(selection: &Select) -> String {
+    match &selection.r#type {
+        Some(select::Type::Struct(struct_select)) => format_struct_select(struct_select),
+        Some(select::Type::List(list_select)) => format_list_select(list_select),
+        Some(select::Type::Map(map_select)) => format_map_select(map_select),
+        None => String::new(),
+    }
+}
+
+/// Formats a struct selection as `{N, ...}`.
+pub fn format_struct_select(struct_select: &StructSelect) -> String {
+    let items: Vec<String> = struct_select
+        .struct_items
+        .iter()
+        .map(|item| {
+            let child = item.child.as_ref().map(format_select).unwrap_or_default();
+            format!("{}{}", item.field, child)
+        })
+        .collect();
+    format!("{{{}}}", items.join(", "))
+}
+
+fn format_list_select(list_select: &ListSelect) -> String {
+    let items: Vec<String> = list_select
+        .selection
+        .iter()
+        .filter_map(|item| match &item.r#type {
+            Some(list_select_item::Type::Item(element)) => Some(element.field.to_string()),
+            Some(list_select_item::Type::Slice(slice)) => {
+                Some(format!("{}:{}", slice.start, slice.end))
+            }
+            None => None,
+        })
+        .collect();
+    let child = list_select
+        .child
+        .as_deref()
+        .map(format_select)
+        .unwrap_or_default();
+    format!("[{}]{}", items.join(", "), child)
+}
+
+fn format_map_select(map_select: &MapSelect) -> String {
+    let key = match &map_select.select {
+        Some(map_select::Select::Key(key)) => format!("\"{}\"", escape_string(&key.map_key)),
+        Some(map_select::Select::Expression(expression)) => format!(
+            "expression \"{}\"",
+            escape_string(&expression.map_key_expression)
+        ),
+        None => String::new(),
+    };
+    let child = map_select
+        .child
+        .as_deref()
+        .map(format_select)
+        .unwrap_or_default();
+    format!("[{}]{}", key, child)
+}
+
+/// Builds a list selection item for a single element.
+pub fn list_element(field: i32) -> ListSelectItem {
+    ListSelectItem {
+        r#type: Some(list_select_item::Type::Item(ListElement { field })),
+    }
+}
+
+/// Builds a list selection item for the elements from `start` to `end`.
+pub fn list_slice(start: i32, end: i32) -> ListSelectItem {
+    ListSelectItem {
+        r#type: Some(list_select_item::Type::Slice(ListSlice { start, end })),
+    }
+}
+
+/// Builds a map selection of a single key, or of the keys matching a pattern.
+pub fn map_key_select(key: String, is_expression: bool, child: Option<Select>) -> MapSelect {
+    let select = if is_expression {
+        map_select::Select::Expression(MapKeyExpression {
+            map_key_expression: key,
+        })
+    } else {
+        map_select::Select::Key(MapKey { map_key: key })
+    };
+    MapSelect {
+        select: Some(select),
+        child: child.map(Box::new),
+    }
+}
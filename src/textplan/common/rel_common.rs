@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for accessing the `RelCommon` message shared by most relation types.
+
+use ::substrait::proto::rel::RelType;
+use ::substrait::proto::rel_common::hint::ComputationType;
+use ::substrait::proto::{Rel, RelCommon};
+
+/// Returns the `common` field of a relation, if the relation type has one and it is set.
+pub fn rel_common(rel: &Rel) -> Option<&RelCommon> {
+    match rel.rel_type.as_ref()? {
+        RelType::Read(r) => r.common.as_ref(),
+        RelType::Filter(r) => r.common.as_ref(),
+        RelType::Fetch(r) => r.common.as_ref(),
+        RelType::Aggregate(r) => r.common.as_ref(),
+        RelType::Sort(r) => r.common.as_ref(),
+        RelType::Join(r) => r.common.as_ref(),
+        RelType::Project(r) => r.common.as_ref(),
+        RelType::Set(r) => r.common.as_ref(),
+        RelType::ExtensionSingle(r) => r.common.as_ref(),
+        RelType::ExtensionMulti(r) => r.common.as_ref(),
+        RelType::ExtensionLeaf(r) => r.common.as_ref(),
+        RelType::Cross(r) => r.common.as_ref(),
+        RelType::Write(r) => r.common.as_ref(),
+        RelType::Ddl(r) => r.common.as_ref(),
+        RelType::HashJoin(r) => r.common.as_ref(),
+        RelType::MergeJoin(r) => r.common.as_ref(),
+        RelType::NestedLoopJoin(r) => r.common.as_ref(),
+        RelType::Window(r) => r.common.as_ref(),
+        RelType::Exchange(r) => r.common.as_ref(),
+        RelType::Expand(r) => r.common.as_ref(),
+        // ReferenceRel and UpdateRel have no common field
+        RelType::Reference(_) | RelType::Update(_) => None,
+    }
+}
+
+/// Returns a mutable handle on the `common` field of a relation.
+///
+/// Returns `None` when the relation type has no `common` field.
+pub fn rel_common_mut(rel: &mut Rel) -> Option<&mut Option<RelCommon>> {
+    match rel.rel_type.as_mut()? {
+        RelType::Read(r) => Some(&mut r.common),
+        RelType::Filter(r) => Some(&mut r.common),
+        RelType::Fetch(r) => Some(&mut r.common),
+        RelType::Aggregate(r) => Some(&mut r.common),
+        RelType::Sort(r) => Some(&mut r.common),
+        RelType::Join(r) => Some(&mut r.common),
+        RelType::Project(r) => Some(&mut r.common),
+        RelType::Set(r) => Some(&mut r.common),
+        RelType::ExtensionSingle(r) => Some(&mut r.common),
+        RelType::ExtensionMulti(r) => Some(&mut r.common),
+        RelType::ExtensionLeaf(r) => Some(&mut r.common),
+        RelType::Cross(r) => Some(&mut r.common),
+        RelType::Write(r) => Some(&mut r.common),
+        RelType::Ddl(r) => Some(&mut r.common),
+        RelType::HashJoin(r) => Some(&mut r.common),
+        RelType::MergeJoin(r) => Some(&mut r.common),
+        RelType::NestedLoopJoin(r) => Some(&mut r.common),
+        RelType::Window(r) => Some(&mut r.common),
+        RelType::Exchange(r) => Some(&mut r.common),
+        RelType::Expand(r) => Some(&mut r.common),
+        RelType::Reference(_) | RelType::Update(_) => None,
+    }
+}
+
+/// Parses the textplan spelling of a computation type (e.g. `hash_table`).
+pub fn parse_computation_type(text: &str) -> Option<ComputationType> {
+    let normalized = text
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>();
+    match normalized.as_str() {
+        "unspecified" => Some(ComputationType::Unspecified),
+        "hashtable" => Some(ComputationType::HashTable),
+        "bloomfilter" => Some(ComputationType::BloomFilter),
+        "unknown" => Some(ComputationType::Unknown),
+        _ => None,
+    }
+}
+
+/// Returns the textplan spelling of a computation type.
+pub fn computation_type_to_string(computation_type: i32) -> &'static str {
+    match ComputationType::try_from(computation_type) {
+        Ok(ComputationType::HashTable) => "hash_table",
+        Ok(ComputationType::BloomFilter) => "bloom_filter",
+        Ok(ComputationType::Unknown) => "unknown",
+        _ => "unspecified",
+    }
+}
@@ -0,0 +1,985 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inference of the output schema (names, types and nullability) of every relation.
+//!
+//! The schema of a relation is computed from its [`RelationData`] by following the
+//! pipeline links to its inputs.  Read relations take their columns from their schema
+//! symbol (or from the base schema of plans loaded from binary), and every other relation
+//! derives its fields from the schemas of its inputs and the types of its expressions.
+//! Finally the relation's emit, if any, selects and reorders the fields.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use ::substrait::proto::expression::field_reference::{ReferenceType, RootType};
+use ::substrait::proto::expression::{nested, reference_segment, subquery, RexType};
+use ::substrait::proto::r#type::{self, Kind, Nullability};
+use ::substrait::proto::rel::RelType;
+use ::substrait::proto::rel_common::EmitKind;
+use ::substrait::proto::{
+    hash_join_rel, join_rel, merge_join_rel, nested_loop_join_rel, Expression, NamedStruct, Type,
+};
+
+use crate::textplan::common::error::TextPlanError;
+use crate::textplan::common::rel_common;
+use crate::textplan::common::structured_symbol_data::RelationData;
+use crate::textplan::common::type_utils;
+use crate::textplan::symbol_table::{SymbolInfo, SymbolTable, SymbolType};
+
+/// A single field produced by a relation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaField {
+    /// The name of the field.  Fields computed by expressions without a known name are
+    /// named after their position (`field3`).
+    pub name: String,
+    /// The type of the field, if it could be determined.
+    pub r#type: Option<Type>,
+    /// The names of the fields of nested structs in depth first order, if they are known.
+    pub nested_names: Vec<String>,
+}
+
+impl SchemaField {
+    /// Creates a field with the given name and type.
+    pub fn new(name: impl Into<String>, r#type: Option<Type>) -> Self {
+        Self {
+            name: name.into(),
+            r#type,
+            nested_names: Vec::new(),
+        }
+    }
+
+    /// Returns the names of the fields of nested structs in depth first order.
+    ///
+    /// Nested fields without known names are named after their position (`field1`).
+    pub fn depth_first_nested_names(&self) -> Vec<String> {
+        fn placeholder_names(type_val: &Type, names: &mut Vec<String>) {
+            match &type_val.kind {
+                Some(Kind::Struct(value)) => {
+                    for (index, field) in value.types.iter().enumerate() {
+                        names.push(format!("field{}", index));
+                        placeholder_names(field, names);
+                    }
+                }
+                Some(Kind::List(value)) => {
+                    if let Some(element) = value.r#type.as_deref() {
+                        placeholder_names(element, names);
+                    }
+                }
+                Some(Kind::Map(value)) => {
+                    if let Some(key) = value.key.as_deref() {
+                        placeholder_names(key, names);
+                    }
+                    if let Some(value) = value.value.as_deref() {
+                        placeholder_names(value, names);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let Some(type_val) = &self.r#type else {
+            return Vec::new();
+        };
+        if self.nested_names.len() == nested_name_count(type_val) {
+            return self.nested_names.clone();
+        }
+        let mut names = Vec::new();
+        placeholder_names(type_val, &mut names);
+        names
+    }
+
+    /// Returns true if the field is known to be nullable.
+    pub fn is_nullable(&self) -> bool {
+        self.r#type.as_ref().is_some_and(type_utils::is_nullable)
+    }
+
+    fn with_nullable(mut self, nullable: bool) -> Self {
+        if let Some(type_val) = &mut self.r#type {
+            type_utils::set_nullable(type_val, nullable);
+        }
+        self
+    }
+}
+
+/// The fields produced by a relation, in output order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelationSchema {
+    pub fields: Vec<SchemaField>,
+}
+
+impl RelationSchema {
+    /// Creates a schema from its fields.
+    pub fn new(fields: Vec<SchemaField>) -> Self {
+        Self { fields }
+    }
+
+    /// Returns the number of fields.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns true if the relation produces no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns the field at the given position.
+    pub fn field(&self, index: usize) -> Option<&SchemaField> {
+        self.fields.get(index)
+    }
+
+    /// Returns the names of the fields.
+    pub fn names(&self) -> Vec<String> {
+        self.fields.iter().map(|field| field.name.clone()).collect()
+    }
+
+    /// Returns the names of the fields followed by the names of their nested struct
+    /// fields in depth first order, as listed by a root relation or a named struct.
+    pub fn depth_first_names(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .flat_map(|field| {
+                std::iter::once(field.name.clone()).chain(field.depth_first_nested_names())
+            })
+            .collect()
+    }
+
+    /// Renames the fields from names listed in depth first order, or from only the top
+    /// level names.  The names are ignored if they do not match the shape of the schema.
+    fn rename_depth_first(&mut self, names: &[String]) {
+        let expected: usize = self
+            .fields
+            .iter()
+            .map(|field| 1 + field.r#type.as_ref().map_or(0, nested_name_count))
+            .sum();
+        if names.len() != expected {
+            if names.len() == self.fields.len() {
+                for (field, name) in self.fields.iter_mut().zip(names) {
+                    field.name = name.clone();
+                }
+            }
+            return;
+        }
+        let mut position = 0;
+        for field in &mut self.fields {
+            let nested_count = field.r#type.as_ref().map_or(0, nested_name_count);
+            field.name = names[position].clone();
+            field.nested_names = names[position + 1..position + 1 + nested_count].to_vec();
+            position += 1 + nested_count;
+        }
+    }
+
+    /// Returns the type of the field at the given position, if it is known.
+    pub fn field_type(&self, index: usize) -> Option<&Type> {
+        self.fields.get(index)?.r#type.as_ref()
+    }
+
+    fn extend(&mut self, other: RelationSchema) {
+        self.fields.extend(other.fields);
+    }
+
+    fn nullable(self) -> Self {
+        Self::new(
+            self.fields
+                .into_iter()
+                .map(|field| field.with_nullable(true))
+                .collect(),
+        )
+    }
+}
+
+/// Computes the output schema of a relation symbol.
+pub fn relation_output_schema(
+    symbol_table: &SymbolTable,
+    symbol: &SymbolInfo,
+) -> Result<RelationSchema, TextPlanError> {
+    SchemaInference::new(symbol_table).output_schema(symbol)
+}
+
+/// Computes the schema seen by the expressions of a relation: the concatenated output
+/// schemas of its inputs, or the base schema for the filters of a read relation.
+pub fn relation_input_schema(
+    symbol_table: &SymbolTable,
+    symbol: &SymbolInfo,
+) -> Result<RelationSchema, TextPlanError> {
+    let relation_data = relation_data(symbol)?;
+    let mut inference = SchemaInference::new(symbol_table);
+    if let Some(RelType::Read(read_rel)) = &relation_data.relation.rel_type {
+        return inference.base_schema(symbol, &relation_data, read_rel);
+    }
+    let mut schema = RelationSchema::default();
+    for input in inference.input_schemas(&relation_data)? {
+        schema.extend(input);
+    }
+    Ok(schema)
+}
+
+/// Determines the type of an expression evaluated against the given input schema.
+///
+/// Returns `None` when the type cannot be determined, such as for functions without a
+/// recorded output type or for references to an outer query.
+pub fn expression_type(expression: &Expression, input: &RelationSchema) -> Option<Type> {
+    match expression.rex_type.as_ref()? {
+        RexType::Literal(literal) => type_utils::literal_to_type(literal),
+        RexType::Selection(reference) => {
+            let root = match reference.root_type.as_ref()? {
+                RootType::RootReference(_) => None,
+                RootType::Expression(expression) => Some(expression_type(expression, input)?),
+                _ => return None,
+            };
+            let Some(ReferenceType::DirectReference(segment)) = &reference.reference_type else {
+                return None;
+            };
+            match root {
+                Some(root) => segment_type(&root, segment),
+                None => {
+                    let Some(reference_segment::ReferenceType::StructField(field)) =
+                        &segment.reference_type
+                    else {
+                        return None;
+                    };
+                    let field_type = input.field_type(usize::try_from(field.field).ok()?)?;
+                    match &field.child {
+                        Some(child) => segment_type(field_type, child),
+                        None => Some(field_type.clone()),
+                    }
+                }
+            }
+        }
+        RexType::ScalarFunction(function) => function.output_type.clone(),
+        RexType::WindowFunction(function) => function.output_type.clone(),
+        RexType::Cast(cast) => cast.r#type.clone(),
+        RexType::DynamicParameter(parameter) => parameter.r#type.clone(),
+        RexType::IfThen(if_then) => {
+            let branches = if_then
+                .ifs
+                .iter()
+                .map(|clause| clause.then.as_ref())
+                .chain(std::iter::once(if_then.r#else.as_deref()));
+            branches_type(branches, input)
+        }
+        RexType::SwitchExpression(switch) => {
+            let branches = switch
+                .ifs
+                .iter()
+                .map(|clause| clause.then.as_ref())
+                .chain(std::iter::once(switch.r#else.as_deref()));
+            branches_type(branches, input)
+        }
+        RexType::SingularOrList(or_list) => {
+            let value = or_list.value.as_ref()?;
+            Some(boolean_type(
+                expression_type(value, input).is_none_or(|value| type_utils::is_nullable(&value)),
+            ))
+        }
+        RexType::MultiOrList(_) => Some(boolean_type(true)),
+        RexType::Subquery(subquery) => match subquery.subquery_type.as_ref()? {
+            subquery::SubqueryType::SetPredicate(_) => Some(boolean_type(false)),
+            subquery::SubqueryType::InPredicate(_) | subquery::SubqueryType::SetComparison(_) => {
+                Some(boolean_type(true))
+            }
+            // The subquery's relation is only attached when the plan is saved
+            subquery::SubqueryType::Scalar(_) => None,
+        },
+        RexType::Nested(nested) => {
+            let nullability = if nested.nullable {
+                Nullability::Nullable
+            } else {
+                Nullability::Required
+            } as i32;
+            let kind = match nested.nested_type.as_ref()? {
+                nested::NestedType::Struct(value) => Kind::Struct(r#type::Struct {
+                    types: value
+                        .fields
+                        .iter()
+                        .map(|field| expression_type(field, input))
+                        .collect::<Option<Vec<_>>>()?,
+                    type_variation_reference: nested.type_variation_reference,
+                    nullability,
+                }),
+                nested::NestedType::List(value) => Kind::List(Box::new(r#type::List {
+                    r#type: Some(Box::new(expression_type(value.values.first()?, input)?)),
+                    type_variation_reference: nested.type_variation_reference,
+                    nullability,
+                })),
+                nested::NestedType::Map(value) => {
+                    let entry = value.key_values.first()?;
+                    Kind::Map(Box::new(r#type::Map {
+                        key: Some(Box::new(expression_type(entry.key.as_ref()?, input)?)),
+                        value: Some(Box::new(expression_type(entry.value.as_ref()?, input)?)),
+                        type_variation_reference: nested.type_variation_reference,
+                        nullability,
+                    }))
+                }
+            };
+            Some(Type { kind: Some(kind) })
+        }
+        _ => None,
+    }
+}
+
+/// Returns the type reached by following a reference segment into a value of the given type.
+fn segment_type(
+    type_val: &Type,
+    segment: &::substrait::proto::expression::ReferenceSegment,
+) -> Option<Type> {
+    let (child_type, child) = match (segment.reference_type.as_ref()?, type_val.kind.as_ref()?) {
+        (reference_segment::ReferenceType::StructField(field), Kind::Struct(value)) => (
+            value.types.get(usize::try_from(field.field).ok()?)?.clone(),
+            &field.child,
+        ),
+        (reference_segment::ReferenceType::ListElement(element), Kind::List(value)) => {
+            (value.r#type.as_deref()?.clone(), &element.child)
+        }
+        (reference_segment::ReferenceType::MapKey(key), Kind::Map(value)) => {
+            (value.value.as_deref()?.clone(), &key.child)
+        }
+        _ => return None,
+    };
+    match child {
+        Some(child) => segment_type(&child_type, child),
+        None => Some(child_type),
+    }
+}
+
+/// Returns the common type of the branches of a conditional, which is nullable if any
+/// branch is nullable or if there is no else branch.
+fn branches_type<'a>(
+    branches: impl Iterator<Item = Option<&'a Expression>>,
+    input: &RelationSchema,
+) -> Option<Type> {
+    let mut result: Option<Type> = None;
+    let mut nullable = false;
+    for branch in branches {
+        let Some(branch) = branch else {
+            nullable = true;
+            continue;
+        };
+        let branch_type = expression_type(branch, input)?;
+        nullable |= type_utils::is_nullable(&branch_type);
+        result.get_or_insert(branch_type);
+    }
+    let mut result = result?;
+    type_utils::set_nullable(&mut result, nullable);
+    Some(result)
+}
+
+fn boolean_type(nullable: bool) -> Type {
+    let nullability = if nullable {
+        Nullability::Nullable
+    } else {
+        Nullability::Required
+    } as i32;
+    Type {
+        kind: Some(Kind::Bool(r#type::Boolean {
+            type_variation_reference: 0,
+            nullability,
+        })),
+    }
+}
+
+fn i32_type() -> Type {
+    Type {
+        kind: Some(Kind::I32(r#type::I32 {
+            type_variation_reference: 0,
+            nullability: Nullability::Required as i32,
+        })),
+    }
+}
+
+fn relation_data(symbol: &SymbolInfo) -> Result<RelationData, TextPlanError> {
+    symbol
+        .with_blob::<RelationData, _, _>(|relation_data| relation_data.clone())
+        .ok_or_else(|| {
+            TextPlanError::SymbolTableError(format!("'{}' is not a relation", symbol.name()))
+        })
+}
+
+/// The sides of a join that contribute to its output.
+enum JoinOutput {
+    /// Both sides, with the given sides made nullable.
+    Both {
+        left_nullable: bool,
+        right_nullable: bool,
+    },
+    /// Only the left side (semi and anti joins).
+    Left,
+    /// Only the right side.
+    Right,
+    /// The left side followed by a boolean match indicator.
+    LeftMark,
+    /// The right side followed by a boolean match indicator.
+    RightMark,
+}
+
+impl JoinOutput {
+    /// Classifies a join by the name of its join type (such as `JOIN_TYPE_LEFT_SEMI`).
+    ///
+    /// The join type enumerations of the join relations number their values differently,
+    /// so the classification uses the names which they share.
+    fn from_name(name: &str) -> Option<Self> {
+        let output = match name.strip_prefix("JOIN_TYPE_")? {
+            "INNER" => Self::Both {
+                left_nullable: false,
+                right_nullable: false,
+            },
+            "OUTER" => Self::Both {
+                left_nullable: true,
+                right_nullable: true,
+            },
+            "LEFT" | "LEFT_SINGLE" => Self::Both {
+                left_nullable: false,
+                right_nullable: true,
+            },
+            "RIGHT" | "RIGHT_SINGLE" => Self::Both {
+                left_nullable: true,
+                right_nullable: false,
+            },
+            "LEFT_SEMI" | "LEFT_ANTI" => Self::Left,
+            "RIGHT_SEMI" | "RIGHT_ANTI" => Self::Right,
+            "LEFT_MARK" => Self::LeftMark,
+            "RIGHT_MARK" => Self::RightMark,
+            _ => return None,
+        };
+        Some(output)
+    }
+
+    fn apply(self, left: RelationSchema, right: RelationSchema) -> RelationSchema {
+        match self {
+            Self::Both {
+                left_nullable,
+                right_nullable,
+            } => {
+                let mut schema = if left_nullable { left.nullable() } else { left };
+                schema.extend(if right_nullable {
+                    right.nullable()
+                } else {
+                    right
+                });
+                schema
+            }
+            Self::Left => left,
+            Self::Right => right,
+            Self::LeftMark | Self::RightMark => {
+                let mut schema = if matches!(self, Self::LeftMark) {
+                    left
+                } else {
+                    right
+                };
+                schema
+                    .fields
+                    .push(SchemaField::new("mark", Some(boolean_type(true))));
+                schema
+            }
+        }
+    }
+}
+
+struct SchemaInference<'a> {
+    symbol_table: &'a SymbolTable,
+    // The relations whose schemas are being computed, used to detect cyclic pipelines.
+    in_progress: HashSet<*const SymbolInfo>,
+}
+
+impl<'a> SchemaInference<'a> {
+    fn new(symbol_table: &'a SymbolTable) -> Self {
+        Self {
+            symbol_table,
+            in_progress: HashSet::new(),
+        }
+    }
+
+    fn output_schema(&mut self, symbol: &SymbolInfo) -> Result<RelationSchema, TextPlanError> {
+        let pointer = symbol as *const SymbolInfo;
+        if !self.in_progress.insert(pointer) {
+            return Err(TextPlanError::SymbolTableError(format!(
+                "Relation '{}' is its own input",
+                symbol.name()
+            )));
+        }
+        let result = relation_data(symbol).and_then(|relation_data| {
+            let schema = self.unprojected_schema(symbol, &relation_data)?;
+            apply_emit(symbol, &relation_data, schema)
+        });
+        self.in_progress.remove(&pointer);
+        result
+    }
+
+    /// Returns the output schemas of the relation's inputs in input order.
+    fn input_schemas(
+        &mut self,
+        relation_data: &RelationData,
+    ) -> Result<Vec<RelationSchema>, TextPlanError> {
+        if let Some(input) = &relation_data.continuing_pipeline {
+            return Ok(vec![self.output_schema(input)?]);
+        }
+        relation_data
+            .new_pipelines
+            .iter()
+            .map(|input| self.output_schema(input))
+            .collect()
+    }
+
+    /// Computes a relation's schema before its emit is applied.
+    fn unprojected_schema(
+        &mut self,
+        symbol: &SymbolInfo,
+        relation_data: &RelationData,
+    ) -> Result<RelationSchema, TextPlanError> {
+        let rel = &relation_data.relation;
+        if let Some(RelType::Read(read_rel)) = &rel.rel_type {
+            return self.read_schema(symbol, relation_data, read_rel);
+        }
+
+        let mut inputs = self.input_schemas(relation_data)?;
+        let missing_input = || {
+            TextPlanError::SymbolTableError(format!("Relation '{}' has no input", symbol.name()))
+        };
+        let Some(rel_type) = &rel.rel_type else {
+            // The root marker passes its input through under the names it lists
+            let mut schema = inputs.into_iter().next().ok_or_else(missing_input)?;
+            schema.rename_depth_first(&relation_data.root_names);
+            return Ok(schema);
+        };
+
+        match rel_type {
+            RelType::Filter(_) | RelType::Sort(_) | RelType::Fetch(_) => {
+                inputs.into_iter().next().ok_or_else(missing_input)
+            }
+            RelType::Project(project_rel) => {
+                let mut schema = inputs.into_iter().next().ok_or_else(missing_input)?;
+                let names = generated_names(relation_data, project_rel.expressions.len());
+                let fields: Vec<SchemaField> = project_rel
+                    .expressions
+                    .iter()
+                    .zip(names)
+                    .map(|(expression, name)| {
+                        SchemaField::new(name, expression_type(expression, &schema))
+                    })
+                    .collect();
+                schema.fields.extend(fields);
+                Ok(schema)
+            }
+            RelType::Aggregate(aggregate_rel) => {
+                let input = inputs.into_iter().next().ok_or_else(missing_input)?;
+                #[allow(deprecated)]
+                let grouping_expressions = if !aggregate_rel.grouping_expressions.is_empty() {
+                    aggregate_rel.grouping_expressions.clone()
+                } else {
+                    let mut expressions: Vec<Expression> = Vec::new();
+                    for grouping in &aggregate_rel.groupings {
+                        for expression in &grouping.grouping_expressions {
+                            if !expressions.contains(expression) {
+                                expressions.push(expression.clone());
+                            }
+                        }
+                    }
+                    expressions
+                };
+                let multiple_groupings = aggregate_rel.groupings.len() > 1;
+                let mut names = generated_names(
+                    relation_data,
+                    grouping_expressions.len() + aggregate_rel.measures.len(),
+                )
+                .into_iter();
+
+                let mut schema = RelationSchema::default();
+                for expression in &grouping_expressions {
+                    let mut field_type = expression_type(expression, &input);
+                    // Keys missing from a grouping set are null in that set's rows
+                    if let (Some(field_type), true) = (&mut field_type, multiple_groupings) {
+                        type_utils::set_nullable(field_type, true);
+                    }
+                    schema.fields.push(SchemaField::new(
+                        names.next().unwrap_or_default(),
+                        field_type,
+                    ));
+                }
+                for measure in &aggregate_rel.measures {
+                    let field_type = measure
+                        .measure
+                        .as_ref()
+                        .and_then(|function| function.output_type.clone());
+                    schema.fields.push(SchemaField::new(
+                        names.next().unwrap_or_default(),
+                        field_type,
+                    ));
+                }
+                if multiple_groupings {
+                    schema
+                        .fields
+                        .push(SchemaField::new("grouping_set", Some(i32_type())));
+                }
+                Ok(schema)
+            }
+            RelType::Cross(_) => {
+                let mut schema = RelationSchema::default();
+                for input in inputs {
+                    schema.extend(input);
+                }
+                Ok(schema)
+            }
+            RelType::Join(join) => join_schema(
+                symbol,
+                inputs,
+                join_rel::JoinType::try_from(join.r#type).map(|t| t.as_str_name()),
+            ),
+            RelType::HashJoin(join) => join_schema(
+                symbol,
+                inputs,
+                hash_join_rel::JoinType::try_from(join.r#type).map(|t| t.as_str_name()),
+            ),
+            RelType::MergeJoin(join) => join_schema(
+                symbol,
+                inputs,
+                merge_join_rel::JoinType::try_from(join.r#type).map(|t| t.as_str_name()),
+            ),
+            RelType::NestedLoopJoin(join) => join_schema(
+                symbol,
+                inputs,
+                nested_loop_join_rel::JoinType::try_from(join.r#type).map(|t| t.as_str_name()),
+            ),
+            RelType::Set(_) => {
+                let mut inputs = inputs.into_iter();
+                let mut schema = inputs.next().ok_or_else(missing_input)?;
+                for input in inputs {
+                    if input.len() != schema.len() {
+                        return Err(TextPlanError::SemanticError(format!(
+                            "The inputs of set relation '{}' have {} and {} fields",
+                            symbol.name(),
+                            schema.len(),
+                            input.len()
+                        )));
+                    }
+                    for (field, other) in schema.fields.iter_mut().zip(&input.fields) {
+                        if other.is_nullable() {
+                            if let Some(field_type) = &mut field.r#type {
+                                type_utils::set_nullable(field_type, true);
+                            }
+                        }
+                    }
+                }
+                Ok(schema)
+            }
+            _ => Err(TextPlanError::SymbolTableError(format!(
+                "Cannot determine the output of relation '{}' of this type",
+                symbol.name()
+            ))),
+        }
+    }
+
+    /// Returns the columns of a read relation's base schema.
+    fn base_schema(
+        &self,
+        symbol: &SymbolInfo,
+        relation_data: &RelationData,
+        read_rel: &::substrait::proto::ReadRel,
+    ) -> Result<RelationSchema, TextPlanError> {
+        let schema_symbol = relation_data.schema.clone().or_else(|| {
+            relation_data
+                .schema_name
+                .as_ref()
+                .and_then(|name| self.symbol_table.lookup_symbol_by_name(name))
+        });
+
+        let schema = match &schema_symbol {
+            Some(schema_symbol) => schema_definition(self.symbol_table, schema_symbol),
+            None => match &read_rel.base_schema {
+                Some(named_struct) => named_struct_schema(named_struct),
+                None => {
+                    return Err(TextPlanError::SymbolTableError(format!(
+                        "Read relation '{}' has no schema",
+                        symbol.name()
+                    )))
+                }
+            },
+        };
+        Ok(schema)
+    }
+
+    /// Returns the columns a read relation produces after its projection.
+    fn read_schema(
+        &self,
+        symbol: &SymbolInfo,
+        relation_data: &RelationData,
+        read_rel: &::substrait::proto::ReadRel,
+    ) -> Result<RelationSchema, TextPlanError> {
+        let base_schema = self.base_schema(symbol, relation_data, read_rel)?;
+
+        if !relation_data.projected_columns.is_empty() {
+            let mut schema = RelationSchema::default();
+            for (projected_name, _) in &relation_data.projected_columns {
+                let column_name = projected_name.rsplit('.').next().unwrap_or(projected_name);
+                let field = base_schema
+                    .fields
+                    .iter()
+                    .find(|field| field.name == column_name)
+                    .ok_or_else(|| {
+                        TextPlanError::SemanticError(format!(
+                            "Projected column '{}' is not part of the base schema of '{}'",
+                            projected_name,
+                            symbol.name()
+                        ))
+                    })?;
+                schema.fields.push(field.clone());
+            }
+            return Ok(schema);
+        }
+
+        if let Some(select) = read_rel
+            .projection
+            .as_ref()
+            .and_then(|projection| projection.select.as_ref())
+        {
+            let fields = select
+                .struct_items
+                .iter()
+                .map(|item| {
+                    usize::try_from(item.field)
+                        .ok()
+                        .and_then(|index| base_schema.field(index))
+                        .cloned()
+                        .ok_or_else(|| {
+                            TextPlanError::SemanticError(format!(
+                                "Read relation '{}' projects field {} which is not in its base schema",
+                                symbol.name(),
+                                item.field
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(RelationSchema::new(fields));
+        }
+
+        Ok(base_schema)
+    }
+}
+
+/// Returns the number of fields output by a join of the named type (such as
+/// `JOIN_TYPE_LEFT_SEMI`) whose inputs have `left` and `right` fields.
+pub fn join_field_count(join_type: &str, left: usize, right: usize) -> Option<usize> {
+    let count = match JoinOutput::from_name(join_type)? {
+        JoinOutput::Both { .. } => left + right,
+        JoinOutput::Left => left,
+        JoinOutput::Right => right,
+        JoinOutput::LeftMark => left + 1,
+        JoinOutput::RightMark => right + 1,
+    };
+    Some(count)
+}
+
+/// Computes the output of a join from its left and right inputs.
+fn join_schema<E>(
+    symbol: &SymbolInfo,
+    inputs: Vec<RelationSchema>,
+    join_type: Result<&str, E>,
+) -> Result<RelationSchema, TextPlanError> {
+    let output = join_type
+        .ok()
+        .and_then(JoinOutput::from_name)
+        .ok_or_else(|| {
+            TextPlanError::SymbolTableError(format!(
+                "Join relation '{}' has no join type",
+                symbol.name()
+            ))
+        })?;
+    let mut inputs = inputs.into_iter();
+    let (Some(left), Some(right)) = (inputs.next(), inputs.next()) else {
+        return Err(TextPlanError::SymbolTableError(format!(
+            "Join relation '{}' needs a left and a right input",
+            symbol.name()
+        )));
+    };
+    Ok(output.apply(left, right))
+}
+
+/// Returns the names of the fields generated by a relation when there is one for each of
+/// the `count` generated values, otherwise positional names.
+fn generated_names(relation_data: &RelationData, count: usize) -> Vec<String> {
+    if relation_data.generated_field_references.len() == count {
+        relation_data
+            .generated_field_references
+            .iter()
+            .map(|field| field.name().to_string())
+            .collect()
+    } else {
+        let offset = relation_data.field_references.len();
+        (0..count)
+            .map(|index| format!("field{}", offset + index))
+            .collect()
+    }
+}
+
+/// Returns the columns defined by a schema symbol.
+///
+/// The names of nested struct fields are taken from the named struct kept with the schema
+/// (if any), so they are known for schemas given in text as well as for binary plans.
+pub fn schema_definition(
+    symbol_table: &SymbolTable,
+    schema_symbol: &Arc<SymbolInfo>,
+) -> RelationSchema {
+    let mut schema = RelationSchema::new(
+        symbol_table
+            .symbols()
+            .iter()
+            .filter(|column| column.symbol_type() == SymbolType::SchemaColumn)
+            .filter(|column| {
+                column
+                    .schema()
+                    .is_some_and(|schema| Arc::ptr_eq(&schema, schema_symbol))
+            })
+            .map(|column| {
+                SchemaField::new(
+                    column.name(),
+                    column.with_blob::<Type, _, _>(|type_val| type_val.clone()),
+                )
+            })
+            .collect(),
+    );
+    if let Some(names) =
+        schema_symbol.with_blob::<NamedStruct, _, _>(|named_struct| named_struct.names.clone())
+    {
+        schema.rename_depth_first(&names);
+    }
+    schema
+}
+
+/// Pairs each field of a struct with its name and the names nested within it, given the
+/// names of the struct's nested fields in depth first order.
+///
+/// Returns `None` if the names do not match the shape of the struct.
+pub fn struct_field_names(
+    struct_type: &r#type::Struct,
+    names: &[String],
+) -> Option<Vec<(String, Vec<String>)>> {
+    let mut position = 0;
+    let mut fields = Vec::new();
+    for field_type in &struct_type.types {
+        let nested_count = nested_name_count(field_type);
+        let name = names.get(position)?.clone();
+        let nested_names = names
+            .get(position + 1..position + 1 + nested_count)?
+            .to_vec();
+        position += 1 + nested_count;
+        fields.push((name, nested_names));
+    }
+    (position == names.len()).then_some(fields)
+}
+
+/// Returns the number of names a type's nested struct fields take up in a named struct.
+pub fn nested_name_count(type_val: &Type) -> usize {
+    match &type_val.kind {
+        Some(Kind::Struct(value)) => value
+            .types
+            .iter()
+            .map(|field| 1 + nested_name_count(field))
+            .sum(),
+        Some(Kind::List(value)) => value.r#type.as_deref().map_or(0, nested_name_count),
+        Some(Kind::Map(value)) => {
+            value.key.as_deref().map_or(0, nested_name_count)
+                + value.value.as_deref().map_or(0, nested_name_count)
+        }
+        _ => 0,
+    }
+}
+
+/// Builds a schema from the top level fields of a named struct.
+///
+/// The names of a named struct also include the names of nested struct fields in depth
+/// first order, which are kept with the field they belong to.
+fn named_struct_schema(named_struct: &NamedStruct) -> RelationSchema {
+    let types = named_struct
+        .r#struct
+        .as_ref()
+        .map(|value| value.types.as_slice())
+        .unwrap_or_default();
+    let mut position = 0;
+    let mut fields = Vec::new();
+    for (index, field_type) in types.iter().enumerate() {
+        let name = named_struct
+            .names
+            .get(position)
+            .cloned()
+            .unwrap_or_else(|| format!("field{}", index));
+        let nested_count = nested_name_count(field_type);
+        let mut field = SchemaField::new(name, Some(field_type.clone()));
+        field.nested_names = named_struct
+            .names
+            .get(position + 1..position + 1 + nested_count)
+            .map(<[String]>::to_vec)
+            .unwrap_or_default();
+        position += 1 + nested_count;
+        fields.push(field);
+    }
+    RelationSchema::new(fields)
+}
+
+/// Selects the fields listed by the relation's emit.
+fn apply_emit(
+    symbol: &SymbolInfo,
+    relation_data: &RelationData,
+    schema: RelationSchema,
+) -> Result<RelationSchema, TextPlanError> {
+    let Some(mapping) = emit_mapping(symbol, relation_data)? else {
+        return Ok(schema);
+    };
+    let fields = mapping
+        .iter()
+        .map(|index| {
+            usize::try_from(*index)
+                .ok()
+                .and_then(|index| schema.field(index))
+                .cloned()
+                .ok_or_else(|| {
+                    TextPlanError::SemanticError(format!(
+                        "Relation '{}' emits field {} but only has {} fields",
+                        symbol.name(),
+                        index,
+                        schema.len()
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(RelationSchema::new(fields))
+}
+
+/// Returns the emit mapping of a relation, if it has one.
+///
+/// Plans loaded from binary keep their emit in the relation's common message.  Parsed
+/// projects record the fields they emit as output field references, which are
+/// mapped to their position in the project's input fields followed by its expressions.
+pub fn emit_mapping(
+    symbol: &SymbolInfo,
+    relation_data: &RelationData,
+) -> Result<Option<Vec<i32>>, TextPlanError> {
+    if let Some(EmitKind::Emit(emit)) =
+        rel_common::rel_common(&relation_data.relation).and_then(|common| common.emit_kind.as_ref())
+    {
+        return Ok(Some(emit.output_mapping.clone()));
+    }
+    if relation_data.output_field_references.is_empty()
+        || !matches!(relation_data.relation.rel_type, Some(RelType::Project(_)))
+    {
+        return Ok(None);
+    }
+
+    let position_in = |fields: &[Arc<SymbolInfo>], field: &Arc<SymbolInfo>| {
+        fields.iter().position(|f| Arc::ptr_eq(f, field))
+    };
+    let mut mapping = Vec::new();
+    for field in &relation_data.output_field_references {
+        // Field selections are in both lists, and it is the generated entry that is emitted
+        let index = position_in(&relation_data.generated_field_references, field)
+            .map(|index| relation_data.field_references.len() + index)
+            .or_else(|| position_in(&relation_data.field_references, field))
+            .ok_or_else(|| {
+                TextPlanError::SemanticError(format!(
+                    "Emitted field '{}' is not a field of relation '{}'",
+                    field.name(),
+                    symbol.name()
+                ))
+            })?;
+        mapping.push(index as i32);
+    }
+    Ok(Some(mapping))
+}
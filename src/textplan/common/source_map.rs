@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversion between byte offsets in a textplan and the lines and columns shown to users.
+//!
+//! Text locations record byte offsets into the parsed text.  A [`SourceMap`] remembers
+//! where each line starts so that an offset can be turned into a line and column, counted
+//! either in characters (for people) or in UTF-16 code units (for editors using LSP).
+
+/// A 1-based line and column in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl LineColumn {
+    /// Creates a line and column from 1-based numbers.
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+/// The text of a textplan along with the offsets where each of its lines start.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    text: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceMap {
+    /// Creates a source map for the given text.
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            text.bytes()
+                .enumerate()
+                .filter(|(_, byte)| *byte == b'\n')
+                .map(|(offset, _)| offset + 1),
+        );
+        Self {
+            text: text.to_string(),
+            line_starts,
+        }
+    }
+
+    /// Returns the text the source map was built from.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the number of lines in the text.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the line and character column of a byte offset.
+    ///
+    /// Offsets past the end of the text are placed at its end, and offsets inside of a
+    /// multibyte character are placed at that character.
+    pub fn line_column(&self, offset: usize) -> LineColumn {
+        let (line, prefix) = self.line_prefix(offset);
+        LineColumn::new(line + 1, prefix.chars().count() + 1)
+    }
+
+    /// Returns the line and UTF-16 column of a byte offset.
+    pub fn line_column_utf16(&self, offset: usize) -> LineColumn {
+        let (line, prefix) = self.line_prefix(offset);
+        LineColumn::new(line + 1, prefix.encode_utf16().count() + 1)
+    }
+
+    /// Returns the byte offset of a line and character column.
+    ///
+    /// Columns past the end of the line are placed at the end of the line.
+    pub fn offset(&self, position: LineColumn) -> Option<usize> {
+        let line = position.line.checked_sub(1)?;
+        let start = *self.line_starts.get(line)?;
+        let text = self.line_text(position.line)?;
+        let column = position.column.saturating_sub(1);
+        Some(
+            start
+                + text
+                    .char_indices()
+                    .nth(column)
+                    .map(|(offset, _)| offset)
+                    .unwrap_or(text.len()),
+        )
+    }
+
+    /// Returns the text of a 1-based line without its line terminator.
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        let index = line.checked_sub(1)?;
+        let start = *self.line_starts.get(index)?;
+        let end = self
+            .line_starts
+            .get(index + 1)
+            .map(|next| next - 1)
+            .unwrap_or(self.text.len());
+        let text = &self.text[start..end];
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// Returns the 0-based line containing the offset and the text of that line before it.
+    fn line_prefix(&self, offset: usize) -> (usize, &str) {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        (line, &self.text[self.line_starts[line]..offset])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_column() {
+        let source = SourceMap::new("schema s {\r\n  é𝄞 i64;\n}");
+        assert_eq!(source.line_count(), 3);
+        assert_eq!(source.line_column(0), LineColumn::new(1, 1));
+        assert_eq!(source.line_column(14), LineColumn::new(2, 3));
+        // The é takes two bytes and the 𝄞 four bytes (and two UTF-16 code units)
+        assert_eq!(source.line_column(20), LineColumn::new(2, 5));
+        assert_eq!(source.line_column_utf16(20), LineColumn::new(2, 6));
+        assert_eq!(source.line_column(15), LineColumn::new(2, 3));
+        assert_eq!(source.line_column(100), LineColumn::new(3, 2));
+        assert_eq!(source.line_text(2), Some("  é𝄞 i64;"));
+        assert_eq!(source.offset(LineColumn::new(2, 5)), Some(20));
+        assert_eq!(source.offset(LineColumn::new(4, 1)), None);
+    }
+}
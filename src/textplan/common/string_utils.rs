@@ -67,6 +67,160 @@ pub fn unescape_string(s: &str) -> String {
     result
 }
 
+/// Escapes binary data as a quoted textplan string.
+///
+/// Printable ASCII is kept as is while any other byte is written as `\xNN`.
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() + 2);
+    result.push('"');
+
+    for (index, &b) in bytes.iter().enumerate() {
+        match b {
+            b'"' => result.push_str("\\\""),
+            // A trailing `\\` could be mistaken for an escaped closing quote
+            b'\\' if index == bytes.len() - 1 => result.push_str("\\x5c"),
+            b'\\' => result.push_str("\\\\"),
+            b'\n' => result.push_str("\\n"),
+            b'\r' => result.push_str("\\r"),
+            b'\t' => result.push_str("\\t"),
+            0x20..=0x7e => result.push(b as char),
+            _ => result.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+/// Unescapes a quoted textplan string produced by [`escape_bytes`] back into binary data.
+///
+/// Returns an error for escape sequences that [`escape_bytes`] does not produce.
+pub fn unescape_bytes(s: &str) -> Result<Vec<u8>, String> {
+    if s.len() < 2 || !s.starts_with('"') || !s.ends_with('"') {
+        return Ok(s.as_bytes().to_vec());
+    }
+
+    let s = &s[1..s.len() - 1];
+    let mut result = Vec::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('"') => result.push(b'"'),
+                Some('\\') => result.push(b'\\'),
+                Some('n') => result.push(b'\n'),
+                Some('r') => result.push(b'\r'),
+                Some('t') => result.push(b'\t'),
+                Some('x') => {
+                    let code: String = chars.by_ref().take(2).collect();
+                    if code.len() != 2 || !code.chars().all(|d| d.is_ascii_hexdigit()) {
+                        return Err(format!(
+                            "Invalid escape sequence '\\x{}', expected two hex digits",
+                            code
+                        ));
+                    }
+                    result.extend(decode_hex(&code).unwrap_or_default());
+                }
+                Some(c) => return Err(format!("Invalid escape sequence '\\{}'", c)),
+                None => return Err("Binary data ends with an incomplete escape".to_string()),
+            }
+        } else {
+            let mut buf = [0; 4];
+            result.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Encodes binary data as lowercase hexadecimal digits.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes hexadecimal digits into binary data, returning `None` if the text is not valid hex.
+pub fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Parses a canonical UUID (`8-4-4-4-12` hex digits) into its 16 bytes.
+pub fn parse_uuid(text: &str) -> Option<Vec<u8>> {
+    let groups: Vec<&str> = text.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    if lengths != [8, 4, 4, 4, 12] {
+        return None;
+    }
+    decode_hex(&groups.concat())
+}
+
+/// Formats 16 bytes as a canonical UUID, falling back to plain hex for other lengths.
+pub fn format_uuid(bytes: &[u8]) -> String {
+    let hex = encode_hex(bytes);
+    if bytes.len() != 16 {
+        return hex;
+    }
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Returns the number of single character insertions, deletions and substitutions needed
+/// to turn one string into the other (the Levenshtein distance).
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Finds the candidate a misspelled name was most likely meant to be.
+///
+/// Names are compared ignoring case, so a candidate differing only in case is always
+/// preferred.  Otherwise the candidate with the smallest edit distance is chosen as long
+/// as no more than a third of the name (and at least one character) differs.  Ties go to
+/// the earliest candidate.
+pub fn closest_match<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let lowercase_name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut best: Option<(usize, &'a str)> = None;
+    for candidate in candidates {
+        if candidate == name {
+            continue;
+        }
+        let distance = edit_distance(&lowercase_name, &candidate.to_lowercase());
+        if distance > max_distance {
+            continue;
+        }
+        if !matches!(best, Some((best_distance, _)) if best_distance <= distance) {
+            best = Some((distance, candidate));
+        }
+    }
+    best.map(|(_, candidate)| candidate)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,4 +240,52 @@ mod tests {
         assert_eq!(unescape_string(r#""hello\"world""#), "hello\"world");
         assert_eq!(unescape_string(r#""hello\\world""#), "hello\\world");
     }
+
+    #[test]
+    fn test_escape_bytes_roundtrip() {
+        let bytes = [b'a', b'"', 0x00, 0xff, b'\n'];
+        assert_eq!(escape_bytes(&bytes), r#""a\"\x00\xff\n""#);
+        assert_eq!(unescape_bytes(&escape_bytes(&bytes)), Ok(bytes.to_vec()));
+        assert_eq!(escape_bytes(b"a\\b\\"), r#""a\\b\x5c""#);
+        assert_eq!(
+            unescape_bytes(&escape_bytes(b"a\\b\\")),
+            Ok(b"a\\b\\".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_unescape_bytes_rejects_bad_escapes() {
+        assert!(unescape_bytes(r#""\q""#).is_err());
+        assert!(unescape_bytes(r#""\x4""#).is_err());
+        assert!(unescape_bytes(r#""\xzz""#).is_err());
+        assert!(unescape_bytes(r#""\x+1""#).is_err());
+    }
+
+    #[test]
+    fn test_hex_and_uuid() {
+        assert_eq!(decode_hex("DEADbeef"), Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(encode_hex(&[0xde, 0xad]), "dead");
+
+        let uuid = "123e4567-e89b-12d3-a456-426614174000";
+        let bytes = parse_uuid(uuid).unwrap();
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(format_uuid(&bytes), uuid);
+        assert_eq!(parse_uuid("123e4567e89b12d3a456426614174000"), None);
+    }
+
+    #[test]
+    fn test_closest_match() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+
+        let candidates = ["l_quantity", "l_extendedprice", "l_discount"];
+        assert_eq!(
+            closest_match("l_extendedprce", candidates),
+            Some("l_extendedprice")
+        );
+        assert_eq!(closest_match("L_DISCOUNT", candidates), Some("l_discount"));
+        assert_eq!(closest_match("l_quantity", candidates), None);
+        assert_eq!(closest_match("o_orderkey", candidates), None);
+    }
 }
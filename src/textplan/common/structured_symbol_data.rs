@@ -47,6 +47,13 @@ pub struct RelationData {
     pub seen_field_reference_names: HashMap<String, usize>,
     // Root output names (for root relations only).
     pub root_names: Vec<String>,
+    // Columns selected by a read relation's projection mask, each with an optional
+    // selection of its nested value. The names are resolved against the base schema
+    // when the plan is saved to binary.
+    pub projected_columns: Vec<(
+        String,
+        Option<::substrait::proto::expression::mask_expression::Select>,
+    )>,
 }
 
 impl RelationData {
@@ -68,6 +75,7 @@ impl RelationData {
             output_field_references: Vec::new(),
             seen_field_reference_names: HashMap::new(),
             root_names: Vec::new(),
+            projected_columns: Vec::new(),
         }
     }
 
@@ -89,6 +97,7 @@ impl RelationData {
             output_field_references: Vec::new(),
             seen_field_reference_names: HashMap::new(),
             root_names: Vec::new(),
+            projected_columns: Vec::new(),
         }
     }
 }
@@ -138,3 +147,22 @@ impl FunctionData {
         }
     }
 }
+
+// Used by Parameter symbols to keep track of the anchor, type and bound value of a
+// dynamic parameter.
+#[derive(Debug, Clone)]
+pub struct ParameterData {
+    pub anchor: u32,
+    pub parameter_type: Option<substrait::proto::Type>,
+    pub value: Option<substrait::proto::expression::Literal>,
+}
+
+impl ParameterData {
+    pub(crate) fn new(anchor: u32) -> Self {
+        ParameterData {
+            anchor,
+            parameter_type: None,
+            value: None,
+        }
+    }
+}
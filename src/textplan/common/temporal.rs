@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversions between human-readable temporal text and Substrait's epoch-based values.
+//!
+//! Dates are days since 1970-01-01, times are microseconds since midnight and
+//! timestamps are microseconds since 1970-01-01T00:00:00 (UTC for `timestamp_tz`).
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+
+/// Converts a proleptic Gregorian calendar date into days since the Unix epoch.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Converts days since the Unix epoch into a (year, month, day) triple.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// Parses `YYYY-MM-DD` into days since the Unix epoch.
+pub fn parse_date(text: &str) -> Option<i32> {
+    let mut parts = text.splitn(3, '-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?.parse::<u32>().ok()?;
+    let day = parts.next()?.parse::<u32>().ok()?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    i32::try_from(days_from_civil(year, month, day)).ok()
+}
+
+/// Formats a (year, month, day) triple as `YYYY-MM-DD`.
+///
+/// Returns None for years that [`parse_date`] would not read back.
+fn format_civil(year: i64, month: u32, day: u32) -> Option<String> {
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+/// Formats days since the Unix epoch as `YYYY-MM-DD`.
+///
+/// Returns None if the year is outside of 0000 to 9999.
+pub fn format_date(days: i32) -> Option<String> {
+    let (year, month, day) = civil_from_days(days as i64);
+    format_civil(year, month, day)
+}
+
+/// Parses `HH:MM:SS[.ffffff]` into microseconds since midnight.
+pub fn parse_time(text: &str) -> Option<i64> {
+    let (clock, fraction) = match text.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (text, None),
+    };
+    let mut parts = clock.splitn(3, ':');
+    let hours = parts.next()?.parse::<i64>().ok()?;
+    let minutes = parts.next()?.parse::<i64>().ok()?;
+    let seconds = parts.next()?.parse::<i64>().ok()?;
+    if !(0..24).contains(&hours) || !(0..60).contains(&minutes) || !(0..60).contains(&seconds) {
+        return None;
+    }
+    let micros = match fraction {
+        Some(fraction) => {
+            if fraction.is_empty()
+                || fraction.len() > 6
+                || !fraction.chars().all(|c| c.is_ascii_digit())
+            {
+                return None;
+            }
+            format!("{:0<6}", fraction).parse::<i64>().ok()?
+        }
+        None => 0,
+    };
+    Some(((hours * 60 + minutes) * 60 + seconds) * MICROS_PER_SECOND + micros)
+}
+
+/// Formats microseconds since midnight as `HH:MM:SS[.ffffff]`.
+///
+/// Trailing zeros of the fractional seconds are omitted.  Returns None if the value is
+/// not within a day.
+pub fn format_time(micros: i64) -> Option<String> {
+    if !(0..MICROS_PER_DAY).contains(&micros) {
+        return None;
+    }
+    let total_seconds = micros / MICROS_PER_SECOND;
+    let fraction = micros % MICROS_PER_SECOND;
+    let mut result = format!(
+        "{:02}:{:02}:{:02}",
+        total_seconds / 3600,
+        (total_seconds / 60) % 60,
+        total_seconds % 60
+    );
+    if fraction != 0 {
+        let digits = format!("{:06}", fraction);
+        result.push('.');
+        result.push_str(digits.trim_end_matches('0'));
+    }
+    Some(result)
+}
+
+/// Parses `YYYY-MM-DDTHH:MM:SS[.ffffff]` (a space may replace the `T`) into
+/// microseconds since the Unix epoch.
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let (date, time) = text.split_once(['T', ' '])?;
+    let days = parse_date(date)? as i64;
+    let micros = parse_time(time)?;
+    days.checked_mul(MICROS_PER_DAY)?.checked_add(micros)
+}
+
+/// Formats microseconds since the Unix epoch as `YYYY-MM-DDTHH:MM:SS[.ffffff]`.
+///
+/// Returns None if the year is outside of 0000 to 9999.
+pub fn format_timestamp(micros: i64) -> Option<String> {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    let time = micros.rem_euclid(MICROS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    Some(format!(
+        "{}T{}",
+        format_civil(year, month, day)?,
+        format_time(time)?
+    ))
+}
+
+/// Parses a timestamp with a `Z` or `+HH:MM`/`-HH:MM` suffix into microseconds
+/// since the Unix epoch in UTC.
+pub fn parse_timestamp_tz(text: &str) -> Option<i64> {
+    if let Some(local) = text.strip_suffix(['Z', 'z']) {
+        return parse_timestamp(local);
+    }
+    // The offset sign must come after the date's own dashes.
+    let sign_index = text.rfind(['+', '-'])?;
+    if sign_index <= 10 {
+        return None;
+    }
+    let (local, offset) = text.split_at(sign_index);
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let (hours, minutes) = offset[1..].split_once(':')?;
+    let hours = hours.parse::<i64>().ok()?;
+    let minutes = minutes.parse::<i64>().ok()?;
+    if !(0..24).contains(&hours) || !(0..60).contains(&minutes) {
+        return None;
+    }
+    let offset_micros = sign * (hours * 60 + minutes) * 60 * MICROS_PER_SECOND;
+    parse_timestamp(local)?.checked_sub(offset_micros)
+}
+
+/// Formats microseconds since the Unix epoch as a UTC timestamp ending in `Z`.
+///
+/// Returns None if the year is outside of 0000 to 9999.
+pub fn format_timestamp_tz(micros: i64) -> Option<String> {
+    Some(format!("{}Z", format_timestamp(micros)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_roundtrip() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-01-31"), Some(19753));
+        assert_eq!(parse_date("1969-12-31"), Some(-1));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(format_date(19753).as_deref(), Some("2024-01-31"));
+        assert_eq!(format_date(-1).as_deref(), Some("1969-12-31"));
+        assert_eq!(format_date(parse_date("0000-01-01").unwrap() - 1), None);
+        assert_eq!(format_date(i32::MAX), None);
+    }
+
+    #[test]
+    fn test_time_roundtrip() {
+        assert_eq!(parse_time("12:30:00.123"), Some(45_000_123_000));
+        assert_eq!(format_time(45_000_123_000).as_deref(), Some("12:30:00.123"));
+        assert_eq!(format_time(0).as_deref(), Some("00:00:00"));
+        assert_eq!(parse_time("24:00:00"), None);
+        assert_eq!(format_time(-1), None);
+        assert_eq!(format_time(MICROS_PER_DAY), None);
+    }
+
+    #[test]
+    fn test_timestamp_roundtrip() {
+        let micros = parse_timestamp("2024-01-31T12:30:00").unwrap();
+        assert_eq!(micros, 1_706_704_200_000_000);
+        assert_eq!(
+            format_timestamp(micros).as_deref(),
+            Some("2024-01-31T12:30:00")
+        );
+        assert_eq!(parse_timestamp_tz("2024-01-31T12:30:00Z"), Some(micros));
+        assert_eq!(
+            parse_timestamp_tz("2024-01-31T14:30:00+02:00"),
+            Some(micros)
+        );
+        assert_eq!(
+            format_timestamp_tz(micros).as_deref(),
+            Some("2024-01-31T12:30:00Z")
+        );
+        assert_eq!(format_timestamp(i64::MIN), None);
+        assert_eq!(format_timestamp_tz(i64::MAX), None);
+    }
+}
@@ -9,11 +9,13 @@ use std::fmt;
 /// Represents a position in the source text.
 ///
 /// This is used to provide context for errors and to reference entities in the source.
+/// Use a [`SourceMap`](crate::textplan::common::source_map::SourceMap) to find the line and
+/// column of a location.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TextLocation {
-    /// The position in the source text, 0-indexed.
+    /// The byte offset of the start of the text, 0-indexed.
     position: i32,
-    /// The length of the text at this location.
+    /// The length of the text at this location in bytes.
     length: i32,
 }
 
@@ -34,12 +36,12 @@ impl TextLocation {
         Self { position, length }
     }
 
-    /// Returns the position in the source text.
+    /// Returns the byte offset of the location in the source text.
     pub fn position(&self) -> i32 {
         self.position
     }
 
-    /// Returns the length of the text at this location.
+    /// Returns the length of the text at this location in bytes.
     pub fn length(&self) -> i32 {
         self.length
     }
@@ -0,0 +1,761 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Derivation of function return types from the type expressions of extension files.
+//!
+//! The parameter types of an implementation, such as `decimal<P1,S1>` or `any1`, bind
+//! their variables to the types of the call arguments.  The return type is then evaluated
+//! with those bindings, after running the assignments that may precede it:
+//!
+//! ```text
+//! init_scale = max(S1,S2)
+//! init_prec = init_scale + max(P1 - S1, P2 - S2) + 1
+//! prec = min(init_prec, 38)
+//! scale = init_prec > 38 ? max(init_scale - (init_prec - 38), min(init_scale, 6)) : init_scale
+//! DECIMAL<prec, scale>
+//! ```
+
+use std::collections::HashMap;
+
+use ::substrait::proto::r#type::{self, Kind, Nullability};
+use ::substrait::proto::Type;
+
+use crate::textplan::common::extension_registry::{
+    CallArgument, ExtensionArgument, FunctionImplementation,
+};
+use crate::textplan::common::type_utils;
+
+/// A value bound to a variable of a type expression.
+#[derive(Debug, Clone)]
+enum Binding {
+    Integer(i64),
+    Type(Type),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Integer(i64),
+    Symbol(&'static str),
+}
+
+/// A type such as `decimal?<P1, S1>` or `list<any1>`.
+#[derive(Debug, Clone)]
+struct TypeExpression {
+    name: String,
+    nullable: bool,
+    parameters: Vec<Parameter>,
+}
+
+#[derive(Debug, Clone)]
+enum Parameter {
+    Type(TypeExpression),
+    Integer(IntegerExpression),
+}
+
+#[derive(Debug, Clone)]
+enum IntegerExpression {
+    Literal(i64),
+    Variable(String),
+    Negate(Box<IntegerExpression>),
+    Not(Box<IntegerExpression>),
+    Binary(&'static str, Box<IntegerExpression>, Box<IntegerExpression>),
+    Call(String, Vec<IntegerExpression>),
+    Conditional(
+        Box<IntegerExpression>,
+        Box<IntegerExpression>,
+        Box<IntegerExpression>,
+    ),
+}
+
+/// Derives the return type of an implementation when called with the given arguments.
+///
+/// The nullability follows the implementation's nullability handling: `MIRROR` (the
+/// default) makes the result nullable if any argument is, while `DECLARED_OUTPUT` and
+/// `DISCRETE` use the nullability written in the return type.  Returns `None` if the type
+/// of any argument is unknown, as neither the implementation nor the nullability of the
+/// result can be relied upon then.
+pub fn derive_return_type(
+    implementation: &FunctionImplementation,
+    arguments: &[CallArgument],
+) -> Option<Type> {
+    if arguments
+        .iter()
+        .any(|argument| matches!(argument, CallArgument::Value(None)))
+    {
+        return None;
+    }
+
+    let mut bindings = HashMap::new();
+    let mut any_nullable = false;
+    for (index, argument) in arguments.iter().enumerate() {
+        let Some(parameter) = implementation
+            .args
+            .get(index.min(implementation.args.len().saturating_sub(1)))
+        else {
+            break;
+        };
+        if let (ExtensionArgument::Value(type_text), CallArgument::Value(Some(type_val))) =
+            (parameter, argument)
+        {
+            any_nullable |= type_utils::is_nullable(type_val);
+            if let Some(expression) = parse_type(type_text) {
+                bind(&expression, type_val, &mut bindings);
+            }
+        }
+    }
+
+    let lines: Vec<&str> = implementation
+        .return_type
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let (result_line, assignments) = lines.split_last()?;
+    for assignment in assignments {
+        let (name, expression) = assignment.split_once('=')?;
+        let mut parser = Parser::new(tokenize(expression)?);
+        let expression = parser.expression()?;
+        if !parser.at_end() {
+            return None;
+        }
+        let value = evaluate_integer(&expression, &bindings)?;
+        bindings.insert(name.trim().to_string(), Binding::Integer(value));
+    }
+
+    let result = parse_type(result_line)?;
+    let mut type_val = evaluate_type(&result, &bindings)?;
+    let nullable = match implementation
+        .nullability
+        .as_deref()
+        .map(str::to_uppercase)
+        .as_deref()
+    {
+        Some("DECLARED_OUTPUT") | Some("DISCRETE") => result.nullable,
+        _ => any_nullable,
+    };
+    type_utils::set_nullable(&mut type_val, nullable);
+    Some(type_val)
+}
+
+/// Parses a complete type expression.
+fn parse_type(text: &str) -> Option<TypeExpression> {
+    let mut parser = Parser::new(tokenize(text)?);
+    let expression = parser.type_expression()?;
+    parser.at_end().then_some(expression)
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    const SYMBOLS: [&str; 18] = [
+        "&&", "||", "==", "!=", "<=", ">=", "<", ">", ",", "(", ")", "?", ":", "+", "-", "*", "/",
+        "!",
+    ];
+
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Integer(rest[..end].parse().ok()?));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Identifier(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            let symbol = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol))?;
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        }
+        rest = rest.trim_start();
+    }
+    Some(tokens)
+}
+
+/// Returns true if the name is a type rather than an integer variable.
+fn is_type_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("any")
+        || matches!(
+            name.as_str(),
+            "bool"
+                | "boolean"
+                | "i8"
+                | "i16"
+                | "i32"
+                | "i64"
+                | "fp32"
+                | "fp64"
+                | "string"
+                | "binary"
+                | "timestamp"
+                | "timestamp_tz"
+                | "date"
+                | "time"
+                | "interval_year"
+                | "interval_day"
+                | "uuid"
+                | "fixedchar"
+                | "varchar"
+                | "fixedbinary"
+                | "decimal"
+                | "list"
+                | "map"
+                | "struct"
+        )
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    fn peek(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.position + offset)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(0), Some(Token::Symbol(s)) if *s == symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_any(&mut self, symbols: &[&'static str]) -> Option<&'static str> {
+        let symbol = symbols
+            .iter()
+            .find(|symbol| matches!(self.peek(0), Some(Token::Symbol(s)) if s == *symbol))?;
+        self.position += 1;
+        Some(*symbol)
+    }
+
+    fn type_expression(&mut self) -> Option<TypeExpression> {
+        let Some(Token::Identifier(name)) = self.advance() else {
+            return None;
+        };
+        let mut nullable = self.eat("?");
+        let mut parameters = Vec::new();
+        if self.eat("<") {
+            loop {
+                parameters.push(self.parameter()?);
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            if !self.eat(">") {
+                return None;
+            }
+            nullable |= self.eat("?");
+        }
+        Some(TypeExpression {
+            name,
+            nullable,
+            parameters,
+        })
+    }
+
+    fn parameter(&mut self) -> Option<Parameter> {
+        if let Some(Token::Identifier(name)) = self.peek(0) {
+            let is_type = is_type_name(name)
+                || matches!(
+                    self.peek(1),
+                    Some(Token::Symbol("<")) | Some(Token::Symbol("?"))
+                );
+            if is_type {
+                return self.type_expression().map(Parameter::Type);
+            }
+        }
+        // Comparisons would be ambiguous with the closing bracket, so they need parentheses
+        self.additive().map(Parameter::Integer)
+    }
+
+    fn expression(&mut self) -> Option<IntegerExpression> {
+        let condition = self.or()?;
+        if !self.eat("?") {
+            return Some(condition);
+        }
+        let if_true = self.expression()?;
+        if !self.eat(":") {
+            return None;
+        }
+        let if_false = self.expression()?;
+        Some(IntegerExpression::Conditional(
+            Box::new(condition),
+            Box::new(if_true),
+            Box::new(if_false),
+        ))
+    }
+
+    fn or(&mut self) -> Option<IntegerExpression> {
+        let mut left = self.and()?;
+        while let Some(operator) = self.eat_any(&["||"]) {
+            left = IntegerExpression::Binary(operator, Box::new(left), Box::new(self.and()?));
+        }
+        Some(left)
+    }
+
+    fn and(&mut self) -> Option<IntegerExpression> {
+        let mut left = self.comparison()?;
+        while let Some(operator) = self.eat_any(&["&&"]) {
+            left =
+                IntegerExpression::Binary(operator, Box::new(left), Box::new(self.comparison()?));
+        }
+        Some(left)
+    }
+
+    fn comparison(&mut self) -> Option<IntegerExpression> {
+        let left = self.additive()?;
+        match self.eat_any(&["==", "!=", "<=", ">=", "<", ">"]) {
+            Some(operator) => Some(IntegerExpression::Binary(
+                operator,
+                Box::new(left),
+                Box::new(self.additive()?),
+            )),
+            None => Some(left),
+        }
+    }
+
+    fn additive(&mut self) -> Option<IntegerExpression> {
+        let mut left = self.multiplicative()?;
+        while let Some(operator) = self.eat_any(&["+", "-"]) {
+            left = IntegerExpression::Binary(
+                operator,
+                Box::new(left),
+                Box::new(self.multiplicative()?),
+            );
+        }
+        Some(left)
+    }
+
+    fn multiplicative(&mut self) -> Option<IntegerExpression> {
+        let mut left = self.unary()?;
+        while let Some(operator) = self.eat_any(&["*", "/"]) {
+            left = IntegerExpression::Binary(operator, Box::new(left), Box::new(self.unary()?));
+        }
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Option<IntegerExpression> {
+        if self.eat("-") {
+            return Some(IntegerExpression::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat("!") {
+            return Some(IntegerExpression::Not(Box::new(self.unary()?)));
+        }
+        match self.advance()? {
+            Token::Integer(value) => Some(IntegerExpression::Literal(value)),
+            Token::Identifier(name) => {
+                if !self.eat("(") {
+                    return Some(IntegerExpression::Variable(name));
+                }
+                let mut arguments = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        arguments.push(self.expression()?);
+                        if !self.eat(",") {
+                            break;
+                        }
+                    }
+                    if !self.eat(")") {
+                        return None;
+                    }
+                }
+                Some(IntegerExpression::Call(name, arguments))
+            }
+            Token::Symbol("(") => {
+                let expression = self.expression()?;
+                self.eat(")").then_some(expression)
+            }
+            Token::Symbol(_) => None,
+        }
+    }
+}
+
+/// Binds the variables of a parameter type to the matching parts of an argument type.
+/// Variables that are already bound keep their first value.
+fn bind(expression: &TypeExpression, type_val: &Type, bindings: &mut HashMap<String, Binding>) {
+    let name = expression.name.to_lowercase();
+    if name.starts_with("any") {
+        bindings
+            .entry(name)
+            .or_insert_with(|| Binding::Type(type_val.clone()));
+        return;
+    }
+
+    let mut bind_integer = |index: usize, value: i32| {
+        if let Some(Parameter::Integer(IntegerExpression::Variable(variable))) =
+            expression.parameters.get(index)
+        {
+            bindings
+                .entry(variable.clone())
+                .or_insert(Binding::Integer(value.into()));
+        }
+    };
+    match &type_val.kind {
+        Some(Kind::Decimal(decimal)) => {
+            bind_integer(0, decimal.precision);
+            bind_integer(1, decimal.scale);
+        }
+        Some(Kind::Varchar(varchar)) => bind_integer(0, varchar.length),
+        Some(Kind::FixedChar(fixed_char)) => bind_integer(0, fixed_char.length),
+        Some(Kind::FixedBinary(fixed_binary)) => bind_integer(0, fixed_binary.length),
+        Some(Kind::List(list)) => {
+            if let (Some(Parameter::Type(element)), Some(element_type)) =
+                (expression.parameters.first(), &list.r#type)
+            {
+                bind(element, element_type, bindings);
+            }
+        }
+        Some(Kind::Map(map)) => {
+            if let (Some(Parameter::Type(key)), Some(key_type)) =
+                (expression.parameters.first(), &map.key)
+            {
+                bind(key, key_type, bindings);
+            }
+            if let (Some(Parameter::Type(value)), Some(value_type)) =
+                (expression.parameters.get(1), &map.value)
+            {
+                bind(value, value_type, bindings);
+            }
+        }
+        Some(Kind::Struct(struct_type)) => {
+            for (parameter, field_type) in expression.parameters.iter().zip(&struct_type.types) {
+                if let Parameter::Type(field) = parameter {
+                    bind(field, field_type, bindings);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn evaluate_integer(
+    expression: &IntegerExpression,
+    bindings: &HashMap<String, Binding>,
+) -> Option<i64> {
+    let value = match expression {
+        IntegerExpression::Literal(value) => *value,
+        IntegerExpression::Variable(name) => match bindings.get(name)? {
+            Binding::Integer(value) => *value,
+            Binding::Type(_) => return None,
+        },
+        IntegerExpression::Negate(operand) => evaluate_integer(operand, bindings)?.checked_neg()?,
+        IntegerExpression::Not(operand) => (evaluate_integer(operand, bindings)? == 0).into(),
+        IntegerExpression::Binary(operator, left, right) => {
+            let left = evaluate_integer(left, bindings)?;
+            let right = evaluate_integer(right, bindings)?;
+            match *operator {
+                "+" => left.checked_add(right)?,
+                "-" => left.checked_sub(right)?,
+                "*" => left.checked_mul(right)?,
+                "/" => left.checked_div(right)?,
+                "==" => (left == right).into(),
+                "!=" => (left != right).into(),
+                "<" => (left < right).into(),
+                "<=" => (left <= right).into(),
+                ">" => (left > right).into(),
+                ">=" => (left >= right).into(),
+                "&&" => (left != 0 && right != 0).into(),
+                "||" => (left != 0 || right != 0).into(),
+                _ => return None,
+            }
+        }
+        IntegerExpression::Call(name, arguments) => {
+            let arguments = arguments
+                .iter()
+                .map(|argument| evaluate_integer(argument, bindings))
+                .collect::<Option<Vec<_>>>()?;
+            match (name.to_lowercase().as_str(), arguments.as_slice()) {
+                ("max", [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.max(*b)),
+                ("min", [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.min(*b)),
+                ("abs", [value]) => value.checked_abs()?,
+                _ => return None,
+            }
+        }
+        IntegerExpression::Conditional(condition, if_true, if_false) => {
+            if evaluate_integer(condition, bindings)? != 0 {
+                evaluate_integer(if_true, bindings)?
+            } else {
+                evaluate_integer(if_false, bindings)?
+            }
+        }
+    };
+    Some(value)
+}
+
+fn evaluate_type(expression: &TypeExpression, bindings: &HashMap<String, Binding>) -> Option<Type> {
+    let name = expression.name.to_lowercase();
+    if name.starts_with("any") {
+        return match bindings.get(&name)? {
+            Binding::Type(type_val) => Some(type_val.clone()),
+            Binding::Integer(_) => None,
+        };
+    }
+
+    let nullability = if expression.nullable {
+        Nullability::Nullable
+    } else {
+        Nullability::Required
+    } as i32;
+    let integer = |index: usize| -> Option<i32> {
+        match expression.parameters.get(index)? {
+            Parameter::Integer(parameter) => evaluate_integer(parameter, bindings)?.try_into().ok(),
+            Parameter::Type(_) => None,
+        }
+    };
+    let nested = |index: usize| -> Option<Box<Type>> {
+        match expression.parameters.get(index)? {
+            Parameter::Type(parameter) => evaluate_type(parameter, bindings).map(Box::new),
+            Parameter::Integer(_) => None,
+        }
+    };
+
+    let kind = match name.as_str() {
+        "bool" | "boolean" => Kind::Bool(r#type::Boolean {
+            nullability,
+            ..Default::default()
+        }),
+        "i8" => Kind::I8(r#type::I8 {
+            nullability,
+            ..Default::default()
+        }),
+        "i16" => Kind::I16(r#type::I16 {
+            nullability,
+            ..Default::default()
+        }),
+        "i32" => Kind::I32(r#type::I32 {
+            nullability,
+            ..Default::default()
+        }),
+        "i64" => Kind::I64(r#type::I64 {
+            nullability,
+            ..Default::default()
+        }),
+        "fp32" => Kind::Fp32(r#type::Fp32 {
+            nullability,
+            ..Default::default()
+        }),
+        "fp64" => Kind::Fp64(r#type::Fp64 {
+            nullability,
+            ..Default::default()
+        }),
+        "string" => Kind::String(r#type::String {
+            nullability,
+            ..Default::default()
+        }),
+        "binary" => Kind::Binary(r#type::Binary {
+            nullability,
+            ..Default::default()
+        }),
+        "timestamp" => Kind::Timestamp(r#type::Timestamp {
+            nullability,
+            ..Default::default()
+        }),
+        "timestamp_tz" => Kind::TimestampTz(r#type::TimestampTz {
+            nullability,
+            ..Default::default()
+        }),
+        "date" => Kind::Date(r#type::Date {
+            nullability,
+            ..Default::default()
+        }),
+        "time" => Kind::Time(r#type::Time {
+            nullability,
+            ..Default::default()
+        }),
+        "interval_year" => Kind::IntervalYear(r#type::IntervalYear {
+            nullability,
+            ..Default::default()
+        }),
+        "interval_day" => Kind::IntervalDay(r#type::IntervalDay {
+            nullability,
+            ..Default::default()
+        }),
+        "uuid" => Kind::Uuid(r#type::Uuid {
+            nullability,
+            ..Default::default()
+        }),
+        "fixedchar" => Kind::FixedChar(r#type::FixedChar {
+            length: integer(0)?,
+            nullability,
+            ..Default::default()
+        }),
+        "varchar" => Kind::Varchar(r#type::VarChar {
+            length: integer(0)?,
+            nullability,
+            ..Default::default()
+        }),
+        "fixedbinary" => Kind::FixedBinary(r#type::FixedBinary {
+            length: integer(0)?,
+            nullability,
+            ..Default::default()
+        }),
+        "decimal" => Kind::Decimal(r#type::Decimal {
+            precision: integer(0)?,
+            scale: integer(1)?,
+            nullability,
+            ..Default::default()
+        }),
+        "list" => Kind::List(Box::new(r#type::List {
+            r#type: Some(nested(0)?),
+            nullability,
+            ..Default::default()
+        })),
+        "map" => Kind::Map(Box::new(r#type::Map {
+            key: Some(nested(0)?),
+            value: Some(nested(1)?),
+            nullability,
+            ..Default::default()
+        })),
+        "struct" => Kind::Struct(r#type::Struct {
+            types: (0..expression.parameters.len())
+                .map(|index| nested(index).map(|field| *field))
+                .collect::<Option<Vec<_>>>()?,
+            nullability,
+            ..Default::default()
+        }),
+        _ => return None,
+    };
+    Some(Type { kind: Some(kind) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(precision: i32, scale: i32, nullable: bool) -> Type {
+        let nullability = if nullable {
+            Nullability::Nullable
+        } else {
+            Nullability::Required
+        } as i32;
+        Type {
+            kind: Some(Kind::Decimal(r#type::Decimal {
+                precision,
+                scale,
+                nullability,
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn implementation(
+        args: &[&str],
+        nullability: Option<&str>,
+        return_type: &str,
+    ) -> FunctionImplementation {
+        FunctionImplementation {
+            args: args
+                .iter()
+                .map(|arg| ExtensionArgument::Value(arg.to_string()))
+                .collect(),
+            variadic_min: None,
+            nullability: nullability.map(str::to_string),
+            return_type: return_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_decimal_arithmetic() {
+        let add = implementation(
+            &["decimal<P1,S1>", "decimal<P2,S2>"],
+            None,
+            "init_scale = max(S1,S2)
+             init_prec = init_scale + max(P1 - S1, P2 - S2) + 1
+             min_scale = min(init_scale, 6)
+             delta = init_prec - 38
+             prec = min(init_prec, 38)
+             scale_after_borrow = max(init_scale - delta, min_scale)
+             scale = init_prec > 38 ? scale_after_borrow : init_scale
+             DECIMAL<prec, scale>",
+        );
+
+        let result = derive_return_type(
+            &add,
+            &[
+                CallArgument::Value(Some(decimal(10, 2, false))),
+                CallArgument::Value(Some(decimal(12, 4, true))),
+            ],
+        );
+        assert_eq!(result, Some(decimal(13, 4, true)));
+
+        let result = derive_return_type(
+            &add,
+            &[
+                CallArgument::Value(Some(decimal(38, 10, false))),
+                CallArgument::Value(Some(decimal(38, 10, false))),
+            ],
+        );
+        assert_eq!(result, Some(decimal(38, 9, false)));
+
+        // The precision depends on the arguments, which are unknown here
+        assert_eq!(
+            derive_return_type(
+                &add,
+                &[CallArgument::Value(None), CallArgument::Value(None)]
+            ),
+            None
+        );
+
+        // Arithmetic that overflows leaves the type underived
+        let argument = CallArgument::Value(Some(decimal(10, 2, false)));
+        for return_type in [
+            "DECIMAL<abs(P1 - 9223372036854775807 - P1 - 1), S1>",
+            "DECIMAL<-(P1 - 9223372036854775807 - P1 - 1), S1>",
+        ] {
+            let overflow = implementation(&["decimal<P1,S1>"], None, return_type);
+            assert_eq!(
+                derive_return_type(&overflow, std::slice::from_ref(&argument)),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn test_nullability_handling() {
+        let argument = CallArgument::Value(Some(decimal(5, 1, true)));
+
+        let declared = implementation(&["any1"], Some("DECLARED_OUTPUT"), "i64");
+        let result = derive_return_type(&declared, std::slice::from_ref(&argument)).unwrap();
+        assert_eq!(type_utils::type_kind_name(&result), "i64");
+        assert!(!type_utils::is_nullable(&result));
+
+        let mirror = implementation(&["any1"], None, "LIST<any1>");
+        let result = derive_return_type(&mirror, std::slice::from_ref(&argument)).unwrap();
+        assert_eq!(type_utils::type_kind_name(&result), "list");
+        assert!(type_utils::is_nullable(&result));
+
+        let discrete = implementation(&["any1"], Some("DISCRETE"), "any1?");
+        let result = derive_return_type(&discrete, &[argument]).unwrap();
+        assert_eq!(result, decimal(5, 1, true));
+
+        // Even a fixed return type is not derived for arguments of unknown type
+        assert_eq!(
+            derive_return_type(&declared, &[CallArgument::Value(None)]),
+            None
+        );
+    }
+}
@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for inspecting Substrait types and deriving the types of literals.
+
+use ::substrait::proto::expression::literal::LiteralType;
+use ::substrait::proto::expression::Literal;
+use ::substrait::proto::r#type::{self, Kind, Nullability};
+use ::substrait::proto::Type;
+
+/// Returns the textplan name of a type's kind (e.g. `i32`, `struct`), without parameters.
+///
+/// The names match the literal suffixes accepted by the parser.
+pub fn type_kind_name(type_val: &Type) -> &'static str {
+    match &type_val.kind {
+        Some(Kind::Bool(_)) => "bool",
+        Some(Kind::I8(_)) => "i8",
+        Some(Kind::I16(_)) => "i16",
+        Some(Kind::I32(_)) => "i32",
+        Some(Kind::I64(_)) => "i64",
+        Some(Kind::Fp32(_)) => "fp32",
+        Some(Kind::Fp64(_)) => "fp64",
+        Some(Kind::String(_)) => "string",
+        Some(Kind::Binary(_)) => "binary",
+        Some(Kind::Timestamp(_)) => "timestamp",
+        Some(Kind::Date(_)) => "date",
+        Some(Kind::Time(_)) => "time",
+        Some(Kind::IntervalYear(_)) => "interval_year",
+        Some(Kind::IntervalDay(_)) => "interval_day",
+        Some(Kind::TimestampTz(_)) => "timestamp_tz",
+        Some(Kind::Uuid(_)) => "uuid",
+        Some(Kind::FixedChar(_)) => "fixedchar",
+        Some(Kind::Varchar(_)) => "varchar",
+        Some(Kind::FixedBinary(_)) => "fixedbinary",
+        Some(Kind::Decimal(_)) => "decimal",
+        Some(Kind::Struct(_)) => "struct",
+        Some(Kind::List(_)) => "list",
+        Some(Kind::Map(_)) => "map",
+        Some(Kind::UserDefined(_)) => "user_defined",
+        Some(_) => "unsupported",
+        None => "unknown",
+    }
+}
+
+/// Returns true if both types have the same kind, ignoring nullability and parameters.
+pub fn same_type_kind(a: &Type, b: &Type) -> bool {
+    match (&a.kind, &b.kind) {
+        (Some(a), Some(b)) => std::mem::discriminant(a) == std::mem::discriminant(b),
+        _ => false,
+    }
+}
+
+/// Returns true if the type is marked as nullable.
+pub fn is_nullable(type_val: &Type) -> bool {
+    let nullability = match &type_val.kind {
+        Some(Kind::Bool(t)) => t.nullability,
+        Some(Kind::I8(t)) => t.nullability,
+        Some(Kind::I16(t)) => t.nullability,
+        Some(Kind::I32(t)) => t.nullability,
+        Some(Kind::I64(t)) => t.nullability,
+        Some(Kind::Fp32(t)) => t.nullability,
+        Some(Kind::Fp64(t)) => t.nullability,
+        Some(Kind::String(t)) => t.nullability,
+        Some(Kind::Binary(t)) => t.nullability,
+        Some(Kind::Timestamp(t)) => t.nullability,
+        Some(Kind::Date(t)) => t.nullability,
+        Some(Kind::Time(t)) => t.nullability,
+        Some(Kind::IntervalYear(t)) => t.nullability,
+        Some(Kind::IntervalDay(t)) => t.nullability,
+        Some(Kind::TimestampTz(t)) => t.nullability,
+        Some(Kind::Uuid(t)) => t.nullability,
+        Some(Kind::FixedChar(t)) => t.nullability,
+        Some(Kind::Varchar(t)) => t.nullability,
+        Some(Kind::FixedBinary(t)) => t.nullability,
+        Some(Kind::Decimal(t)) => t.nullability,
+        Some(Kind::Struct(t)) => t.nullability,
+        Some(Kind::List(t)) => t.nullability,
+        Some(Kind::Map(t)) => t.nullability,
+        Some(Kind::UserDefined(t)) => t.nullability,
+        _ => Nullability::Unspecified as i32,
+    };
+    nullability == Nullability::Nullable as i32
+}
+
+/// Marks the type as nullable or required.
+pub fn set_nullable(type_val: &mut Type, nullable: bool) {
+    let nullability = if nullable {
+        Nullability::Nullable
+    } else {
+        Nullability::Required
+    } as i32;
+    match &mut type_val.kind {
+        Some(Kind::Bool(t)) => t.nullability = nullability,
+        Some(Kind::I8(t)) => t.nullability = nullability,
+        Some(Kind::I16(t)) => t.nullability = nullability,
+        Some(Kind::I32(t)) => t.nullability = nullability,
+        Some(Kind::I64(t)) => t.nullability = nullability,
+        Some(Kind::Fp32(t)) => t.nullability = nullability,
+        Some(Kind::Fp64(t)) => t.nullability = nullability,
+        Some(Kind::String(t)) => t.nullability = nullability,
+        Some(Kind::Binary(t)) => t.nullability = nullability,
+        Some(Kind::Timestamp(t)) => t.nullability = nullability,
+        Some(Kind::Date(t)) => t.nullability = nullability,
+        Some(Kind::Time(t)) => t.nullability = nullability,
+        Some(Kind::IntervalYear(t)) => t.nullability = nullability,
+        Some(Kind::IntervalDay(t)) => t.nullability = nullability,
+        Some(Kind::TimestampTz(t)) => t.nullability = nullability,
+        Some(Kind::Uuid(t)) => t.nullability = nullability,
+        Some(Kind::FixedChar(t)) => t.nullability = nullability,
+        Some(Kind::Varchar(t)) => t.nullability = nullability,
+        Some(Kind::FixedBinary(t)) => t.nullability = nullability,
+        Some(Kind::Decimal(t)) => t.nullability = nullability,
+        Some(Kind::Struct(t)) => t.nullability = nullability,
+        Some(Kind::List(t)) => t.nullability = nullability,
+        Some(Kind::Map(t)) => t.nullability = nullability,
+        Some(Kind::UserDefined(t)) => t.nullability = nullability,
+        _ => {}
+    }
+}
+
+/// Returns true if both types are the same apart from their top level nullability.
+pub fn same_type_ignoring_nullability(a: &Type, b: &Type) -> bool {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    set_nullable(&mut a, false);
+    set_nullable(&mut b, false);
+    a == b
+}
+
+/// Groups of type kinds whose values may be cast between each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CastCategory {
+    Boolean,
+    Numeric,
+    String,
+    Binary,
+    Temporal,
+    Interval,
+    Nested,
+    Other,
+}
+
+fn cast_category(type_val: &Type) -> CastCategory {
+    match type_kind_name(type_val) {
+        "bool" => CastCategory::Boolean,
+        "i8" | "i16" | "i32" | "i64" | "fp32" | "fp64" | "decimal" => CastCategory::Numeric,
+        "string" | "varchar" | "fixedchar" => CastCategory::String,
+        "binary" | "fixedbinary" | "uuid" => CastCategory::Binary,
+        "date" | "time" | "timestamp" | "timestamp_tz" => CastCategory::Temporal,
+        "interval_year" | "interval_day" => CastCategory::Interval,
+        "struct" | "list" | "map" => CastCategory::Nested,
+        _ => CastCategory::Other,
+    }
+}
+
+/// Returns true if a value of type `from` may be cast to type `to`.
+///
+/// Strings may be cast to and from any type that is not nested, booleans and numbers may
+/// be cast between each other, and the kinds of each of the binary, temporal and interval
+/// groups may be cast within their group.  Nested types may only be cast to the same kind
+/// of nested type.  Casts involving user defined types are assumed to be valid.
+pub fn can_cast(from: &Type, to: &Type) -> bool {
+    use CastCategory::*;
+
+    if same_type_kind(from, to) {
+        return true;
+    }
+    match (cast_category(from), cast_category(to)) {
+        (Other, _) | (_, Other) => true,
+        (Nested, _) | (_, Nested) => false,
+        (String, _) | (_, String) => true,
+        (Boolean | Numeric, Boolean | Numeric) => true,
+        (from, to) => from == to,
+    }
+}
+
+/// Derives the type of a literal.
+///
+/// Returns `None` for literals whose type cannot be determined from the literal alone.
+pub fn literal_to_type(literal: &Literal) -> Option<Type> {
+    let nullability = if literal.nullable {
+        Nullability::Nullable
+    } else {
+        Nullability::Required
+    } as i32;
+
+    let kind = match literal.literal_type.as_ref()? {
+        LiteralType::Boolean(_) => Kind::Bool(r#type::Boolean {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::I8(_) => Kind::I8(r#type::I8 {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::I16(_) => Kind::I16(r#type::I16 {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::I32(_) => Kind::I32(r#type::I32 {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::I64(_) => Kind::I64(r#type::I64 {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Fp32(_) => Kind::Fp32(r#type::Fp32 {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Fp64(_) => Kind::Fp64(r#type::Fp64 {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::String(_) => Kind::String(r#type::String {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Binary(_) => Kind::Binary(r#type::Binary {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Timestamp(_) => Kind::Timestamp(r#type::Timestamp {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Date(_) => Kind::Date(r#type::Date {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Time(_) => Kind::Time(r#type::Time {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::IntervalYearToMonth(_) => Kind::IntervalYear(r#type::IntervalYear {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::IntervalDayToSecond(_) => Kind::IntervalDay(r#type::IntervalDay {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::TimestampTz(_) => Kind::TimestampTz(r#type::TimestampTz {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Uuid(_) => Kind::Uuid(r#type::Uuid {
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::FixedChar(value) => Kind::FixedChar(r#type::FixedChar {
+            length: value.len() as i32,
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::VarChar(value) => Kind::Varchar(r#type::VarChar {
+            length: value.length as i32,
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::FixedBinary(value) => Kind::FixedBinary(r#type::FixedBinary {
+            length: value.len() as i32,
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Decimal(value) => Kind::Decimal(r#type::Decimal {
+            precision: value.precision,
+            scale: value.scale,
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::Struct(value) => Kind::Struct(r#type::Struct {
+            types: value
+                .fields
+                .iter()
+                .map(literal_to_type)
+                .collect::<Option<Vec<_>>>()?,
+            nullability,
+            ..Default::default()
+        }),
+        LiteralType::List(value) => Kind::List(Box::new(r#type::List {
+            r#type: literal_to_type(value.values.first()?).map(Box::new),
+            nullability,
+            ..Default::default()
+        })),
+        LiteralType::Map(value) => {
+            let first = value.key_values.first()?;
+            Kind::Map(Box::new(r#type::Map {
+                key: literal_to_type(first.key.as_ref()?).map(Box::new),
+                value: literal_to_type(first.value.as_ref()?).map(Box::new),
+                nullability,
+                ..Default::default()
+            }))
+        }
+        LiteralType::EmptyList(list_type) => Kind::List(Box::new(r#type::List {
+            nullability,
+            ..list_type.clone()
+        })),
+        LiteralType::EmptyMap(map_type) => Kind::Map(Box::new(r#type::Map {
+            nullability,
+            ..map_type.clone()
+        })),
+        LiteralType::Null(null_type) => return Some(null_type.clone()),
+        _ => return None,
+    };
+
+    Some(Type { kind: Some(kind) })
+}
@@ -6,15 +6,17 @@
 //! It builds on the generated BasePlanProtoVisitor trait to provide specialized
 //! visitors for different stages of plan processing.
 
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
 use crate::textplan::common::error::TextPlanError;
 use crate::textplan::common::location::Location;
 use crate::textplan::common::structured_symbol_data::ExtensionSpaceData;
 use crate::textplan::common::structured_symbol_data::FunctionData;
+use crate::textplan::common::structured_symbol_data::ParameterData;
 use crate::textplan::common::structured_symbol_data::RelationData;
 use crate::textplan::converter::generated::PlanProtoVisitor;
 use crate::textplan::converter::generated::Traversable;
 use crate::textplan::symbol_table::SourceType;
-use crate::textplan::{ProtoLocation, SymbolInfo, SymbolType};
+use crate::textplan::{ProtoLocation, SymbolInfo, SymbolType, TextLocation};
 use ::substrait::proto as substrait;
 use std::any::Any;
 use std::collections::HashMap;
@@ -44,6 +46,9 @@ pub struct InitialPlanVisitor {
 
     /// Track the next subquery index for each parent relation (by location hash)
     subquery_indices: HashMap<u64, usize>,
+
+    /// Problems that prevent the plan from being converted
+    diagnostics: Vec<Diagnostic>,
 }
 
 fn short_name(s: &str) -> &str {
@@ -106,9 +111,28 @@ impl InitialPlanVisitor {
             read_relation_sources: HashMap::new(),
             read_relation_schemas: HashMap::new(),
             subquery_indices: HashMap::new(),
+            diagnostics: Vec::new(),
         }
     }
 
+    /// Get the problems found that prevent the plan from being converted
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.clone()
+    }
+
+    /// Record a problem with the part of the plan at the given location
+    fn add_error(&mut self, location: &ProtoLocation, message: &str) {
+        let path = location.path_string();
+        self.diagnostics.push(
+            Diagnostic::error(
+                DiagnosticCode::InvalidPlan,
+                message,
+                TextLocation::unknown(),
+            )
+            .with_proto_path(path.trim_start_matches('.')),
+        );
+    }
+
     /// Get the symbol table built by this visitor
     pub fn symbol_table(&self) -> &crate::textplan::symbol_table::SymbolTable {
         &self.symbol_table
@@ -124,6 +148,32 @@ impl InitialPlanVisitor {
     }
 
     pub fn visit_plan(&mut self, obj: &substrait::Plan) {
+        if let Some(advanced_extension) = &obj.advanced_extensions {
+            self.symbol_table.define_symbol(
+                "advanced_extension".to_string(),
+                self.current_location().field("advanced_extensions"),
+                SymbolType::AdvancedExtension,
+                /* subtype */ None,
+                Some(Arc::new(Mutex::new(advanced_extension.clone()))
+                    as Arc<Mutex<dyn Any + Send + Sync>>),
+            );
+        }
+        for (i, binding) in obj.parameter_bindings.iter().enumerate() {
+            let mut data = ParameterData::new(binding.parameter_anchor);
+            data.parameter_type = binding
+                .value
+                .as_ref()
+                .and_then(crate::textplan::common::type_utils::literal_to_type);
+            data.value = binding.value.clone();
+            self.symbol_table.define_symbol(
+                format!("${}", binding.parameter_anchor),
+                self.current_location()
+                    .indexed_field("parameter_bindings", i),
+                SymbolType::Parameter,
+                /* subtype */ None,
+                Some(Arc::new(Mutex::new(data)) as Arc<Mutex<dyn Any + Send + Sync>>),
+            );
+        }
         obj.traverse(self);
     }
 
@@ -140,41 +190,32 @@ impl InitialPlanVisitor {
 
     /// Get the output mapping from a relation's common emit field.
     fn get_output_mapping(relation: &substrait::Rel) -> Vec<i32> {
-        if let Some(rel_type) = &relation.rel_type {
-            let common_opt = match rel_type {
-                substrait::rel::RelType::Read(r) => r.common.as_ref(),
-                substrait::rel::RelType::Filter(f) => f.common.as_ref(),
-                substrait::rel::RelType::Fetch(f) => f.common.as_ref(),
-                substrait::rel::RelType::Aggregate(a) => a.common.as_ref(),
-                substrait::rel::RelType::Sort(s) => s.common.as_ref(),
-                substrait::rel::RelType::Join(j) => j.common.as_ref(),
-                substrait::rel::RelType::Project(p) => p.common.as_ref(),
-                substrait::rel::RelType::Set(s) => s.common.as_ref(),
-                substrait::rel::RelType::ExtensionSingle(e) => e.common.as_ref(),
-                substrait::rel::RelType::ExtensionMulti(e) => e.common.as_ref(),
-                substrait::rel::RelType::ExtensionLeaf(e) => e.common.as_ref(),
-                substrait::rel::RelType::Cross(c) => c.common.as_ref(),
-                substrait::rel::RelType::Reference(_) => None, // No common in ReferenceRel
-                substrait::rel::RelType::Write(w) => w.common.as_ref(),
-                substrait::rel::RelType::Ddl(d) => d.common.as_ref(),
-                substrait::rel::RelType::HashJoin(h) => h.common.as_ref(),
-                substrait::rel::RelType::MergeJoin(m) => m.common.as_ref(),
-                substrait::rel::RelType::NestedLoopJoin(n) => n.common.as_ref(),
-                substrait::rel::RelType::Window(w) => w.common.as_ref(),
-                substrait::rel::RelType::Exchange(e) => e.common.as_ref(),
-                substrait::rel::RelType::Expand(e) => e.common.as_ref(),
-                substrait::rel::RelType::Update(_) => None, // UpdateRel has no common field
-            };
-
-            if let Some(common) = common_opt {
-                if let Some(substrait::rel_common::EmitKind::Emit(emit)) = &common.emit_kind {
-                    return emit.output_mapping.clone();
-                }
+        if let Some(common) = crate::textplan::common::rel_common::rel_common(relation) {
+            if let Some(substrait::rel_common::EmitKind::Emit(emit)) = &common.emit_kind {
+                return emit.output_mapping.clone();
             }
         }
         Vec::new()
     }
 
+    /// Returns true if the selection drills into a nested column (e.g. `addr.1` or `tags[0]`)
+    /// or masks parts of the input, rather than passing a field through.
+    fn is_derived_selection(selection: &substrait::expression::FieldReference) -> bool {
+        use substrait::expression::field_reference::ReferenceType;
+        use substrait::expression::reference_segment;
+
+        match &selection.reference_type {
+            Some(ReferenceType::DirectReference(segment)) => match &segment.reference_type {
+                Some(reference_segment::ReferenceType::StructField(struct_field)) => {
+                    struct_field.child.is_some()
+                }
+                _ => false,
+            },
+            Some(ReferenceType::MaskedReference(_)) => true,
+            None => false,
+        }
+    }
+
     /// Get the schema name from a field's schema reference.
     fn get_schema_name(field: &Arc<crate::textplan::SymbolInfo>) -> String {
         if let Some(schema) = field.schema() {
@@ -335,6 +376,21 @@ impl InitialPlanVisitor {
                             relation_data.field_references.push(symbol);
                         }
                     }
+
+                    // A projection mask narrows what the read produces for downstream relations.
+                    if let Some(select) = read_rel
+                        .projection
+                        .as_ref()
+                        .and_then(|mask| mask.select.as_ref())
+                    {
+                        for item in &select.struct_items {
+                            if let Some(field) =
+                                relation_data.field_references.get(item.field as usize)
+                            {
+                                relation_data.output_field_references.push(field.clone());
+                            }
+                        }
+                    }
                 }
                 substrait::rel::RelType::Filter(filter_rel) => {
                     if let Some(input) = &filter_rel.input {
@@ -409,68 +465,71 @@ impl InitialPlanVisitor {
                     }
 
                     for expr in &project_rel.expressions {
-                        // TODO -- Add support for other kinds of direct references.
-                        if let Some(substrait::expression::RexType::Selection(selection)) =
-                            &expr.rex_type
-                        {
-                            // TODO(REVIEW): Verify FieldReference.reference_type vs root_type usage.
-                            // The protobuf has both reference_type (DirectReference/MaskedReference) and
-                            // root_type (Expression/RootReference/OuterReference) as separate oneofs.
-                            if let Some(substrait::expression::field_reference::ReferenceType::DirectReference(ref_seg)) = &selection.reference_type {
-                                if let Some(substrait::expression::reference_segment::ReferenceType::StructField(struct_field)) = &ref_seg.reference_type {
-                                    let mapping = struct_field.field as usize;
-                                    if mapping < relation_data.field_references.len() {
-                                        let field = relation_data.field_references[mapping].clone();
-                                        relation_data.generated_field_references.push(field.clone());
-
-                                        // Handle duplicate field names needing schema qualification
-                                        let prev_instance = relation_data.seen_field_reference_names.get(field.name());
-                                        if field.alias().is_none() && prev_instance.is_some() {
-                                            // Add a version with the schema supplied.
-                                            let schema_name = Self::get_schema_name(&field);
-                                            if !schema_name.is_empty() {
-                                                relation_data.generated_field_reference_alternative_expression.insert(
-                                                    relation_data.generated_field_references.len() - 1,
-                                                    format!("{}.{}", schema_name, field.name())
-                                                );
-                                            }
-                                            // Now update the first occurrence if it hasn't already.
-                                            if let Some(&prev_idx) = prev_instance {
-                                                let schema_name_prev = Self::get_schema_name(&relation_data.generated_field_references[prev_idx]);
-                                                if !schema_name_prev.is_empty() {
+                        match &expr.rex_type {
+                            // Nested and masked references select new values
+                            Some(substrait::expression::RexType::Selection(selection))
+                                if !Self::is_derived_selection(selection) =>
+                            {
+                                // TODO(REVIEW): Verify FieldReference.reference_type vs root_type usage.
+                                // The protobuf has both reference_type (DirectReference/MaskedReference) and
+                                // root_type (Expression/RootReference/OuterReference) as separate oneofs.
+                                if let Some(substrait::expression::field_reference::ReferenceType::DirectReference(ref_seg)) = &selection.reference_type {
+                                    if let Some(substrait::expression::reference_segment::ReferenceType::StructField(struct_field)) = &ref_seg.reference_type {
+                                        let mapping = struct_field.field as usize;
+                                        if mapping < relation_data.field_references.len() {
+                                            let field = relation_data.field_references[mapping].clone();
+                                            relation_data.generated_field_references.push(field.clone());
+
+                                            // Handle duplicate field names needing schema qualification
+                                            let prev_instance = relation_data.seen_field_reference_names.get(field.name());
+                                            if field.alias().is_none() && prev_instance.is_some() {
+                                                // Add a version with the schema supplied.
+                                                let schema_name = Self::get_schema_name(&field);
+                                                if !schema_name.is_empty() {
                                                     relation_data.generated_field_reference_alternative_expression.insert(
-                                                        prev_idx,
-                                                        format!("{}.{}", schema_name_prev, field.name())
+                                                        relation_data.generated_field_references.len() - 1,
+                                                        format!("{}.{}", schema_name, field.name())
                                                     );
                                                 }
+                                                // Now update the first occurrence if it hasn't already.
+                                                if let Some(&prev_idx) = prev_instance {
+                                                    let schema_name_prev = Self::get_schema_name(&relation_data.generated_field_references[prev_idx]);
+                                                    if !schema_name_prev.is_empty() {
+                                                        relation_data.generated_field_reference_alternative_expression.insert(
+                                                            prev_idx,
+                                                            format!("{}.{}", schema_name_prev, field.name())
+                                                        );
+                                                    }
+                                                }
                                             }
+                                            if field.alias().is_none() {
+                                                relation_data.seen_field_reference_names.insert(
+                                                    field.name().to_string(),
+                                                    relation_data.generated_field_references.len() - 1
+                                                );
+                                            }
+                                        } else {
+                                            // TODO -- Add error handling
+                                            // errorListener_->addError("Asked to project a field that isn't available");
                                         }
-                                        if field.alias().is_none() {
-                                            relation_data.seen_field_reference_names.insert(
-                                                field.name().to_string(),
-                                                relation_data.generated_field_references.len() - 1
-                                            );
-                                        }
-                                    } else {
-                                        // TODO -- Add error handling
-                                        // errorListener_->addError("Asked to project a field that isn't available");
                                     }
                                 }
                             }
-                        } else {
-                            // Non-selection expression - create intermediate node
-                            let unique_name = self.symbol_table.get_unique_name("intermediate");
-                            let new_symbol = self.symbol_table.define_symbol(
-                                unique_name.clone(),
-                                self.current_location().field("project"),
-                                SymbolType::Unknown,
-                                None,
-                                None,
-                            );
-                            relation_data
-                                .generated_field_references
-                                .push(new_symbol.clone());
-                            self.symbol_table.add_alias(unique_name, &new_symbol);
+                            _ => {
+                                // Non-selection expression - create intermediate node
+                                let unique_name = self.symbol_table.get_unique_name("intermediate");
+                                let new_symbol = self.symbol_table.define_symbol(
+                                    unique_name.clone(),
+                                    self.current_location().field("project"),
+                                    SymbolType::Unknown,
+                                    None,
+                                    None,
+                                );
+                                relation_data
+                                    .generated_field_references
+                                    .push(new_symbol.clone());
+                                self.symbol_table.add_alias(unique_name, &new_symbol);
+                            }
                         }
                     }
                 }
@@ -718,8 +777,9 @@ impl PlanProtoVisitor for InitialPlanVisitor {
                     );
                 }
                 _ => {
-                    panic!("Unknown mapping type case {:#?} encountered.",
-                           &obj.mapping_type);
+                    let location = self.current_location().clone();
+                    self.add_error(&location,
+                                   "Only function extensions are supported in textplans");
                 }
             }
         }
@@ -794,8 +854,10 @@ impl PlanProtoVisitor for InitialPlanVisitor {
                         self.symbol_table
                             .set_parent_query_location(&symbol, parent_rel_location.box_clone());
 
-                        // Get the next subquery index for this parent relation
-                        let current_index = *self.subquery_indices.entry(parent_hash).or_insert(0);
+                        // Take the next subquery index for this parent relation
+                        let next_index = self.subquery_indices.entry(parent_hash).or_insert(0);
+                        let current_index = *next_index;
+                        *next_index += 1;
                         println!(
                             "DEBUG INIT: Setting parent_query_index for '{}' to {}",
                             symbol.name(),
@@ -803,9 +865,6 @@ impl PlanProtoVisitor for InitialPlanVisitor {
                         );
                         self.symbol_table
                             .set_parent_query_index(&symbol, current_index as i32);
-
-                        // Increment for next subquery in same parent
-                        *self.subquery_indices.get_mut(&parent_hash).unwrap() += 1;
                     }
                 } else {
                     println!(
@@ -884,10 +943,9 @@ impl PlanProtoVisitor for InitialPlanVisitor {
                 None,
                 Some(Arc::new(Mutex::new(base_schema.clone()))),
             );
-            self.read_relation_schemas.insert(
-                self.current_relation_scope.last().unwrap().to_string(),
-                symbol,
-            );
+            if let Some(scope) = self.current_relation_scope.last() {
+                self.read_relation_schemas.insert(scope.to_string(), symbol);
+            }
             // Traverse the named struct to process its contents
             base_schema.traverse(self);
         }
@@ -6,12 +6,14 @@ pub mod generated;
 mod initial_plan_visitor;
 pub mod load_json;
 mod pipeline_visitor;
+mod plan_validator;
 pub mod save_binary;
 pub mod save_json;
 pub mod save_text;
 
 // Re-export the main conversion functions
 pub use load_json::load_from_json_file;
+pub use plan_validator::validate_plan;
 pub use save_binary::save_to_binary;
 pub use save_json::save_to_json;
 pub use save_json::save_to_json_file;
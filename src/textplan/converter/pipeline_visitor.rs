@@ -6,12 +6,14 @@
 //! It builds on the generated BasePlanProtoVisitor trait to provide specialized
 //! visitors for different stages of plan processing.
 
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
 use crate::textplan::common::structured_symbol_data::RelationData;
-use crate::textplan::common::ProtoLocation;
+use crate::textplan::common::{ProtoLocation, TextLocation};
 use crate::textplan::converter::generated::base_plan_visitor::Traversable;
 use crate::textplan::converter::generated::PlanProtoVisitor;
 use crate::textplan::SymbolType;
 use ::substrait::proto as substrait;
+use std::cell::RefCell;
 use std::sync::Arc;
 
 /// Pipeline visitor implementation that processes a Substrait plan in multiple stages.
@@ -30,6 +32,8 @@ pub struct PipelineVisitor {
     current_location: ProtoLocation,
     /// Flag to prevent infinite recursion when traversing subquery relations
     in_subquery_traversal: bool,
+    /// Problems that prevent the plan from being converted
+    diagnostics: RefCell<Vec<Diagnostic>>,
 }
 
 impl PipelineVisitor {
@@ -41,9 +45,40 @@ impl PipelineVisitor {
             previous_relation_scope: None,
             current_location: ProtoLocation::default(),
             in_subquery_traversal: false,
+            diagnostics: RefCell::new(Vec::new()),
         }
     }
 
+    /// Get the problems found that prevent the plan from being converted
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.borrow().clone()
+    }
+
+    /// Record a problem with the part of the plan at the given location
+    fn add_error(&self, location: &ProtoLocation, message: &str) {
+        let path = location.path_string();
+        self.diagnostics.borrow_mut().push(
+            Diagnostic::error(
+                DiagnosticCode::InvalidPlan,
+                message,
+                TextLocation::unknown(),
+            )
+            .with_proto_path(path.trim_start_matches('.')),
+        );
+    }
+
+    /// Look up the relation at the location of a required input, recording an error if
+    /// there is none
+    fn required_input(&self, location: ProtoLocation) -> Option<Arc<crate::textplan::SymbolInfo>> {
+        let symbol = self
+            .symbol_table
+            .lookup_symbol_by_location_and_type(&location, SymbolType::Relation);
+        if symbol.is_none() {
+            self.add_error(&location, "No relation was found for this input");
+        }
+        symbol
+    }
+
     /// Get the symbol table built by this visitor
     pub fn symbol_table(&self) -> &crate::textplan::symbol_table::SymbolTable {
         &self.symbol_table
@@ -316,16 +351,12 @@ impl PlanProtoVisitor for PipelineVisitor {
                     relation_data.continuing_pipeline = rel_symbol;
                 }
                 Some(substrait::rel::RelType::Join(_)) => {
-                    let left_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self.current_location().field("join").field("left"),
-                        SymbolType::Relation,
-                    );
-                    let right_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self.current_location().field("join").field("right"),
-                        SymbolType::Relation,
-                    );
-                    relation_data.new_pipelines.push(left_symbol.unwrap());
-                    relation_data.new_pipelines.push(right_symbol.unwrap());
+                    for side in ["left", "right"] {
+                        let location = self.current_location().field("join").field(side);
+                        relation_data
+                            .new_pipelines
+                            .extend(self.required_input(location));
+                    }
                 }
                 Some(substrait::rel::RelType::Project(_)) => {
                     let rel_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
@@ -336,14 +367,13 @@ impl PlanProtoVisitor for PipelineVisitor {
                 }
                 Some(substrait::rel::RelType::Set(set)) => {
                     for i in 0..set.inputs.len() {
-                        let input_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                            &self
-                                .current_location()
-                                .field("set")
-                                .indexed_field("inputs", i),
-                            SymbolType::Relation,
-                        );
-                        relation_data.new_pipelines.push(input_symbol.unwrap());
+                        let location = self
+                            .current_location()
+                            .field("set")
+                            .indexed_field("inputs", i);
+                        relation_data
+                            .new_pipelines
+                            .extend(self.required_input(location));
                     }
                 }
                 Some(substrait::rel::RelType::ExtensionSingle(_)) => {
@@ -358,34 +388,32 @@ impl PlanProtoVisitor for PipelineVisitor {
                 }
                 Some(substrait::rel::RelType::ExtensionMulti(multi)) => {
                     for i in 0..multi.inputs.len() {
-                        let input_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                            &self
-                                .current_location()
-                                .field("extension_multi")
-                                .indexed_field("inputs", i),
-                            SymbolType::Relation,
-                        );
-                        relation_data.new_pipelines.push(input_symbol.unwrap());
+                        let location = self
+                            .current_location()
+                            .field("extension_multi")
+                            .indexed_field("inputs", i);
+                        relation_data
+                            .new_pipelines
+                            .extend(self.required_input(location));
                     }
                 }
                 Some(substrait::rel::RelType::ExtensionLeaf(_)) => {
                     // No children.
                 }
                 Some(substrait::rel::RelType::Cross(_)) => {
-                    let left_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self.current_location().field("cross").field("left"),
-                        SymbolType::Relation,
-                    );
-                    let right_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self.current_location().field("cross").field("right"),
-                        SymbolType::Relation,
-                    );
-                    relation_data.new_pipelines.push(left_symbol.unwrap());
-                    relation_data.new_pipelines.push(right_symbol.unwrap());
+                    for side in ["left", "right"] {
+                        let location = self.current_location().field("cross").field(side);
+                        relation_data
+                            .new_pipelines
+                            .extend(self.required_input(location));
+                    }
                 }
                 Some(substrait::rel::RelType::Reference(_)) => {
                     // TODO -- Add support for references in text plans.
-                    todo!()
+                    self.add_error(
+                        self.current_location(),
+                        "Reference relations are not supported in textplans",
+                    );
                 }
                 Some(substrait::rel::RelType::Write(_)) => {
                     let rel_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
@@ -396,53 +424,44 @@ impl PlanProtoVisitor for PipelineVisitor {
                 }
                 Some(substrait::rel::RelType::Ddl(_)) => {
                     // TODO -- Add support for DDL in text plans.
-                    todo!()
+                    self.add_error(
+                        self.current_location(),
+                        "DDL relations are not supported in textplans",
+                    );
                 }
                 Some(substrait::rel::RelType::Update(_)) => {
                     // TODO -- Add support for update in text plans.
-                    todo!()
+                    self.add_error(
+                        self.current_location(),
+                        "Update relations are not supported in textplans",
+                    );
                 }
                 Some(substrait::rel::RelType::HashJoin(_)) => {
-                    let left_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self.current_location().field("hash_join").field("left"),
-                        SymbolType::Relation,
-                    );
-                    let right_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self.current_location().field("hash_join").field("right"),
-                        SymbolType::Relation,
-                    );
-                    relation_data.new_pipelines.push(left_symbol.unwrap());
-                    relation_data.new_pipelines.push(right_symbol.unwrap());
+                    for side in ["left", "right"] {
+                        let location = self.current_location().field("hash_join").field(side);
+                        relation_data
+                            .new_pipelines
+                            .extend(self.required_input(location));
+                    }
                 }
                 Some(substrait::rel::RelType::MergeJoin(_)) => {
-                    let left_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self.current_location().field("merge_join").field("left"),
-                        SymbolType::Relation,
-                    );
-                    let right_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self.current_location().field("merge_join").field("right"),
-                        SymbolType::Relation,
-                    );
-                    relation_data.new_pipelines.push(left_symbol.unwrap());
-                    relation_data.new_pipelines.push(right_symbol.unwrap());
+                    for side in ["left", "right"] {
+                        let location = self.current_location().field("merge_join").field(side);
+                        relation_data
+                            .new_pipelines
+                            .extend(self.required_input(location));
+                    }
                 }
                 Some(substrait::rel::RelType::NestedLoopJoin(_)) => {
-                    let left_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self
-                            .current_location()
-                            .field("nested_loop_join")
-                            .field("left"),
-                        SymbolType::Relation,
-                    );
-                    let right_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                        &self
+                    for side in ["left", "right"] {
+                        let location = self
                             .current_location()
                             .field("nested_loop_join")
-                            .field("right"),
-                        SymbolType::Relation,
-                    );
-                    relation_data.new_pipelines.push(left_symbol.unwrap());
-                    relation_data.new_pipelines.push(right_symbol.unwrap());
+                            .field(side);
+                        relation_data
+                            .new_pipelines
+                            .extend(self.required_input(location));
+                    }
                 }
                 Some(substrait::rel::RelType::Window(_)) => {
                     let rel_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
@@ -477,21 +496,26 @@ impl PlanProtoVisitor for PipelineVisitor {
         let symbols = self
             .symbol_table()
             .lookup_symbols_by_location(self.current_location());
-        // A symbol is guaranteed as we previously visited the parse tree.
-        symbols[0].with_blob::<RelationData, _, _>(|relation_data| match relation.rel_type {
+        // The InitialPlanVisitor defines a symbol for every plan relation.
+        let Some(plan_symbol) = symbols.first() else {
+            self.add_error(
+                self.current_location(),
+                "No symbol was defined for this plan relation",
+            );
+            return;
+        };
+        plan_symbol.with_blob::<RelationData, _, _>(|relation_data| match relation.rel_type {
             Some(substrait::plan_rel::RelType::Rel(_)) => {
-                let rel_symbol = self.symbol_table.lookup_symbol_by_location_and_type(
-                    &self.current_location().field("rel"),
-                    SymbolType::Relation,
-                );
-                relation_data.new_pipelines.push(rel_symbol.unwrap());
+                let location = self.current_location().field("rel");
+                relation_data
+                    .new_pipelines
+                    .extend(self.required_input(location));
             }
             Some(substrait::plan_rel::RelType::Root(_)) => {
-                let lookup_location = self.current_location().field("root").field("input");
-                let input_symbol = self
-                    .symbol_table
-                    .lookup_symbol_by_location_and_type(&lookup_location, SymbolType::Relation);
-                relation_data.new_pipelines.push(input_symbol.unwrap());
+                let location = self.current_location().field("root").field("input");
+                relation_data
+                    .new_pipelines
+                    .extend(self.required_input(location));
             }
             None => {}
         });
@@ -0,0 +1,426 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Validation of binary Substrait plans before they are converted.
+//!
+//! The converters assume that the plans they are given are well formed.  The validator
+//! walks a plan without converting it and reports the problems that would otherwise make
+//! the conversion fail or produce placeholder text, identifying each by the path of the
+//! offending message (such as `relations[0].root.input.filter.condition`).
+
+use std::collections::HashSet;
+
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::textplan::common::rel_common::rel_common;
+use crate::textplan::common::relation_schema::join_field_count;
+use crate::textplan::common::{ProtoLocation, TextLocation};
+use crate::textplan::converter::generated::base_plan_visitor::Traversable;
+use crate::textplan::converter::generated::PlanProtoVisitor;
+use ::substrait::proto as substrait;
+use ::substrait::proto::expression::field_reference::{ReferenceType, RootType};
+use ::substrait::proto::expression::reference_segment;
+use ::substrait::proto::extensions::simple_extension_declaration::MappingType;
+use ::substrait::proto::rel::RelType;
+use ::substrait::proto::rel_common::EmitKind;
+
+/// Checks a binary plan for problems that prevent it from being converted.
+///
+/// Reports functions used with anchors that are not declared, field references and emits
+/// beyond the fields of a relation's input, and relations missing their type or inputs.
+/// The diagnostics have no text location; their path within the plan is given by
+/// [`Diagnostic::proto_path`].
+pub fn validate_plan(plan: &substrait::Plan) -> Vec<Diagnostic> {
+    let mut validator = PlanValidator::new(plan);
+    plan.traverse(&mut validator);
+    validator.diagnostics
+}
+
+/// A visitor collecting the problems of a plan.
+struct PlanValidator {
+    current_location: ProtoLocation,
+    /// The anchors of the functions declared by the plan's extensions.
+    function_anchors: HashSet<u32>,
+    /// The number of fields available to the expressions of each relation being visited,
+    /// innermost last, if it is known.
+    input_field_counts: Vec<Option<usize>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl PlanValidator {
+    fn new(plan: &substrait::Plan) -> Self {
+        let function_anchors = plan
+            .extensions
+            .iter()
+            .filter_map(|extension| match &extension.mapping_type {
+                Some(MappingType::ExtensionFunction(function)) => Some(function.function_anchor),
+                _ => None,
+            })
+            .collect();
+        Self {
+            current_location: ProtoLocation::unknown(),
+            function_anchors,
+            input_field_counts: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn add_error(&mut self, location: &ProtoLocation, message: String) {
+        self.add_error_with_code(DiagnosticCode::InvalidPlan, location, message);
+    }
+
+    fn add_error_with_code(
+        &mut self,
+        code: DiagnosticCode,
+        location: &ProtoLocation,
+        message: String,
+    ) {
+        let path = location.path_string();
+        self.diagnostics.push(
+            Diagnostic::error(code, message, TextLocation::unknown())
+                .with_proto_path(path.trim_start_matches('.')),
+        );
+    }
+
+    fn check_function_reference(&mut self, function_reference: u32) {
+        if !self.function_anchors.contains(&function_reference) {
+            let location = self.current_location.field("function_reference");
+            self.add_error_with_code(
+                DiagnosticCode::UnknownFunction,
+                &location,
+                format!(
+                    "Function anchor {} is not declared by any extension",
+                    function_reference
+                ),
+            );
+        }
+    }
+
+    /// Reports the inputs and other parts a relation cannot do without.
+    fn check_relation(&mut self, rel: &substrait::Rel) {
+        let Some(rel_type) = &rel.rel_type else {
+            let location = self.current_location.clone();
+            self.add_error(&location, "The relation has no type".to_string());
+            return;
+        };
+        let type_name = rel_type_field_name(rel_type);
+        let type_location = self.current_location.field(type_name);
+
+        if let RelType::Read(read) = rel_type {
+            if read.base_schema.is_none() {
+                self.add_error(
+                    &type_location.field("base_schema"),
+                    "The read relation has no base schema".to_string(),
+                );
+            }
+            if read.read_type.is_none() {
+                self.add_error(
+                    &type_location,
+                    "The read relation has no source".to_string(),
+                );
+            }
+        }
+        for input in missing_inputs(rel_type) {
+            self.add_error_with_code(
+                DiagnosticCode::ArityMismatch,
+                &type_location.field(input),
+                format!("The {} relation has no {}", type_name, input),
+            );
+        }
+
+        if let (Some(EmitKind::Emit(emit)), Some(field_count)) = (
+            rel_common(rel).and_then(|common| common.emit_kind.as_ref()),
+            direct_field_count(rel),
+        ) {
+            let emit_location = type_location.field("common").field("emit");
+            for (index, field) in emit.output_mapping.iter().enumerate() {
+                if !usize::try_from(*field).is_ok_and(|field| field < field_count) {
+                    self.add_error(
+                        &emit_location.indexed_field("output_mapping", index),
+                        format!(
+                            "The {} relation emits field {} but only has {} fields",
+                            type_name, field, field_count
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl PlanProtoVisitor for PlanValidator {
+    fn current_location(&self) -> &ProtoLocation {
+        &self.current_location
+    }
+
+    fn set_location(&mut self, location: ProtoLocation) {
+        self.current_location = location;
+    }
+
+    // The generated visitor doesn't traverse FunctionArgument.arg_type
+    fn post_process_function_argument(&mut self, arg: &substrait::FunctionArgument) {
+        if let Some(substrait::function_argument::ArgType::Value(expr)) = &arg.arg_type {
+            let prev_location = self.current_location().clone();
+            self.set_location(self.current_location().field("value"));
+            expr.traverse(self);
+            self.set_location(prev_location);
+        }
+    }
+
+    fn pre_process_plan_rel(&mut self, obj: &substrait::PlanRel) {
+        if obj.rel_type.is_none() {
+            let location = self.current_location.clone();
+            self.add_error(
+                &location,
+                "The plan relation has neither a relation nor a root".to_string(),
+            );
+        }
+    }
+
+    fn pre_process_rel_root(&mut self, obj: &substrait::RelRoot) {
+        if obj.input.is_none() {
+            let location = self.current_location.field("input");
+            self.add_error(&location, "The root has no input".to_string());
+        }
+    }
+
+    fn pre_process_rel(&mut self, obj: &substrait::Rel) {
+        self.check_relation(obj);
+        self.input_field_counts.push(input_field_count(obj));
+    }
+
+    fn post_process_rel(&mut self, _obj: &substrait::Rel) {
+        self.input_field_counts.pop();
+    }
+
+    fn pre_process_field_reference(&mut self, obj: &substrait::expression::FieldReference) {
+        // Outer references and references into expressions are not checked
+        if !matches!(obj.root_type, None | Some(RootType::RootReference(_))) {
+            return;
+        }
+        let Some(ReferenceType::DirectReference(segment)) = &obj.reference_type else {
+            return;
+        };
+        let Some(reference_segment::ReferenceType::StructField(struct_field)) =
+            &segment.reference_type
+        else {
+            return;
+        };
+        let Some(Some(field_count)) = self.input_field_counts.last().copied() else {
+            return;
+        };
+        if !usize::try_from(struct_field.field).is_ok_and(|field| field < field_count) {
+            let location = self
+                .current_location
+                .field("direct_reference")
+                .field("struct_field")
+                .field("field");
+            self.add_error_with_code(
+                DiagnosticCode::UnknownColumn,
+                &location,
+                format!(
+                    "Field {} is out of range as the input has {} fields",
+                    struct_field.field, field_count
+                ),
+            );
+        }
+    }
+
+    fn pre_process_scalar_function(&mut self, obj: &substrait::expression::ScalarFunction) {
+        self.check_function_reference(obj.function_reference);
+    }
+
+    fn pre_process_aggregate_function(&mut self, obj: &substrait::AggregateFunction) {
+        self.check_function_reference(obj.function_reference);
+    }
+
+    fn pre_process_window_function(&mut self, obj: &substrait::expression::WindowFunction) {
+        self.check_function_reference(obj.function_reference);
+    }
+
+    fn pre_process_window_rel_function(
+        &mut self,
+        obj: &substrait::consistent_partition_window_rel::WindowRelFunction,
+    ) {
+        self.check_function_reference(obj.function_reference);
+    }
+}
+
+/// Returns the name of the field holding a type of relation in the `Rel` message.
+fn rel_type_field_name(rel_type: &RelType) -> &'static str {
+    match rel_type {
+        RelType::Read(_) => "read",
+        RelType::Filter(_) => "filter",
+        RelType::Fetch(_) => "fetch",
+        RelType::Aggregate(_) => "aggregate",
+        RelType::Sort(_) => "sort",
+        RelType::Join(_) => "join",
+        RelType::Project(_) => "project",
+        RelType::Set(_) => "set",
+        RelType::ExtensionSingle(_) => "extension_single",
+        RelType::ExtensionMulti(_) => "extension_multi",
+        RelType::ExtensionLeaf(_) => "extension_leaf",
+        RelType::Cross(_) => "cross",
+        RelType::Reference(_) => "reference",
+        RelType::Write(_) => "write",
+        RelType::Ddl(_) => "ddl",
+        RelType::Update(_) => "update",
+        RelType::HashJoin(_) => "hash_join",
+        RelType::MergeJoin(_) => "merge_join",
+        RelType::NestedLoopJoin(_) => "nested_loop_join",
+        RelType::Window(_) => "window",
+        RelType::Exchange(_) => "exchange",
+        RelType::Expand(_) => "expand",
+    }
+}
+
+/// Returns the names of the input fields that a relation requires but does not have.
+fn missing_inputs(rel_type: &RelType) -> Vec<&'static str> {
+    let single = |input: &Option<Box<substrait::Rel>>| {
+        if input.is_none() {
+            vec!["input"]
+        } else {
+            vec![]
+        }
+    };
+    let pair = |left: &Option<Box<substrait::Rel>>, right: &Option<Box<substrait::Rel>>| {
+        let mut missing = Vec::new();
+        if left.is_none() {
+            missing.push("left");
+        }
+        if right.is_none() {
+            missing.push("right");
+        }
+        missing
+    };
+    match rel_type {
+        RelType::Filter(rel) => single(&rel.input),
+        RelType::Fetch(rel) => single(&rel.input),
+        RelType::Aggregate(rel) => single(&rel.input),
+        RelType::Sort(rel) => single(&rel.input),
+        RelType::Project(rel) => single(&rel.input),
+        RelType::ExtensionSingle(rel) => single(&rel.input),
+        RelType::Window(rel) => single(&rel.input),
+        RelType::Exchange(rel) => single(&rel.input),
+        RelType::Expand(rel) => single(&rel.input),
+        RelType::Join(rel) => pair(&rel.left, &rel.right),
+        RelType::Cross(rel) => pair(&rel.left, &rel.right),
+        RelType::HashJoin(rel) => pair(&rel.left, &rel.right),
+        RelType::MergeJoin(rel) => pair(&rel.left, &rel.right),
+        RelType::NestedLoopJoin(rel) => pair(&rel.left, &rel.right),
+        RelType::Set(rel) if rel.inputs.is_empty() => vec!["inputs"],
+        _ => vec![],
+    }
+}
+
+/// Returns the number of fields a relation outputs, if it can be determined.
+fn output_field_count(rel: &substrait::Rel) -> Option<usize> {
+    if let Some(EmitKind::Emit(emit)) = rel_common(rel).and_then(|common| common.emit_kind.as_ref())
+    {
+        return Some(emit.output_mapping.len());
+    }
+    direct_field_count(rel)
+}
+
+/// Returns the number of fields a relation outputs before its emit is applied.
+fn direct_field_count(rel: &substrait::Rel) -> Option<usize> {
+    let count = |input: &Option<Box<substrait::Rel>>| input.as_deref().and_then(output_field_count);
+    match rel.rel_type.as_ref()? {
+        RelType::Read(read) => match read
+            .projection
+            .as_ref()
+            .and_then(|projection| projection.select.as_ref())
+        {
+            Some(select) => Some(select.struct_items.len()),
+            None => read
+                .base_schema
+                .as_ref()?
+                .r#struct
+                .as_ref()
+                .map(|value| value.types.len()),
+        },
+        RelType::Filter(rel) => count(&rel.input),
+        RelType::Fetch(rel) => count(&rel.input),
+        RelType::Sort(rel) => count(&rel.input),
+        RelType::Project(rel) => Some(count(&rel.input)? + rel.expressions.len()),
+        RelType::Aggregate(rel) => {
+            #[allow(deprecated)]
+            let grouping_count = if !rel.grouping_expressions.is_empty() {
+                rel.grouping_expressions.len()
+            } else {
+                let mut expressions: Vec<&substrait::Expression> = Vec::new();
+                for grouping in &rel.groupings {
+                    for expression in &grouping.grouping_expressions {
+                        if !expressions.contains(&expression) {
+                            expressions.push(expression);
+                        }
+                    }
+                }
+                expressions.len()
+            };
+            let grouping_set = usize::from(rel.groupings.len() > 1);
+            Some(grouping_count + rel.measures.len() + grouping_set)
+        }
+        RelType::Cross(rel) => Some(count(&rel.left)? + count(&rel.right)?),
+        RelType::Join(rel) => join_field_count(
+            substrait::join_rel::JoinType::try_from(rel.r#type)
+                .ok()?
+                .as_str_name(),
+            count(&rel.left)?,
+            count(&rel.right)?,
+        ),
+        RelType::HashJoin(rel) => join_field_count(
+            substrait::hash_join_rel::JoinType::try_from(rel.r#type)
+                .ok()?
+                .as_str_name(),
+            count(&rel.left)?,
+            count(&rel.right)?,
+        ),
+        RelType::MergeJoin(rel) => join_field_count(
+            substrait::merge_join_rel::JoinType::try_from(rel.r#type)
+                .ok()?
+                .as_str_name(),
+            count(&rel.left)?,
+            count(&rel.right)?,
+        ),
+        RelType::NestedLoopJoin(rel) => join_field_count(
+            substrait::nested_loop_join_rel::JoinType::try_from(rel.r#type)
+                .ok()?
+                .as_str_name(),
+            count(&rel.left)?,
+            count(&rel.right)?,
+        ),
+        RelType::Set(rel) => rel.inputs.first().and_then(output_field_count),
+        _ => None,
+    }
+}
+
+/// Returns the number of fields available to the expressions of a relation, if known.
+fn input_field_count(rel: &substrait::Rel) -> Option<usize> {
+    let count = |input: &Option<Box<substrait::Rel>>| input.as_deref().and_then(output_field_count);
+    let joined = |left: &Option<Box<substrait::Rel>>, right: &Option<Box<substrait::Rel>>| {
+        // Post join filters see the join's output, which may add a mark field
+        let inputs = count(left)? + count(right)?;
+        Some(direct_field_count(rel).map_or(inputs, |output| inputs.max(output)))
+    };
+    match rel.rel_type.as_ref()? {
+        // The filters of a read apply to its base schema
+        RelType::Read(read) => read
+            .base_schema
+            .as_ref()?
+            .r#struct
+            .as_ref()
+            .map(|value| value.types.len()),
+        RelType::Filter(rel) => count(&rel.input),
+        RelType::Fetch(rel) => count(&rel.input),
+        RelType::Sort(rel) => count(&rel.input),
+        RelType::Project(rel) => count(&rel.input),
+        RelType::Aggregate(rel) => count(&rel.input),
+        RelType::Window(rel) => count(&rel.input),
+        RelType::Exchange(rel) => count(&rel.input),
+        RelType::Expand(rel) => count(&rel.input),
+        RelType::Join(join) => joined(&join.left, &join.right),
+        RelType::HashJoin(join) => joined(&join.left, &join.right),
+        RelType::MergeJoin(join) => joined(&join.left, &join.right),
+        RelType::NestedLoopJoin(join) => joined(&join.left, &join.right),
+        _ => None,
+    }
+}
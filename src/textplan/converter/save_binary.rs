@@ -4,6 +4,7 @@
 
 use crate::proto::{save_plan_to_binary, Plan, PlanRel};
 use crate::textplan::common::error::TextPlanError;
+use crate::textplan::common::relation_schema;
 use crate::textplan::common::structured_symbol_data::RelationData;
 use crate::textplan::symbol_table::{SymbolInfo, SymbolTable, SymbolType};
 use ::substrait::proto::{plan_rel, rel, Rel, RelRoot};
@@ -20,7 +21,9 @@ use std::sync::Arc;
 ///
 /// The Plan protobuf representation.
 pub fn create_plan_from_symbol_table(symbol_table: &SymbolTable) -> Result<Plan, TextPlanError> {
-    use crate::textplan::common::structured_symbol_data::{ExtensionSpaceData, FunctionData};
+    use crate::textplan::common::structured_symbol_data::{
+        ExtensionSpaceData, FunctionData, ParameterData,
+    };
     use std::collections::HashMap;
 
     // Create a plan with the appropriate version
@@ -74,9 +77,31 @@ pub fn create_plan_from_symbol_table(symbol_table: &SymbolTable) -> Result<Plan,
                     }
                 }
             }
+            SymbolType::AdvancedExtension => {
+                // The plan level advanced extension
+                plan.advanced_extensions = symbol
+                    .with_blob::<::substrait::proto::extensions::AdvancedExtension, _, _>(
+                        |extension| extension.clone(),
+                    );
+            }
+            SymbolType::Parameter => {
+                // Parameters with a value from the parameters block become bindings
+                if let Some(Some(binding)) = symbol.with_blob::<ParameterData, _, _>(|data| {
+                    data.value
+                        .clone()
+                        .map(|value| ::substrait::proto::DynamicParameterBinding {
+                            parameter_anchor: data.anchor,
+                            value: Some(value),
+                        })
+                }) {
+                    plan.parameter_bindings.push(binding);
+                }
+            }
             _ => {}
         }
     }
+    plan.parameter_bindings
+        .sort_by_key(|binding| binding.parameter_anchor);
 
     // Build extension_uris vector from collected extension spaces
     for (anchor, uri) in extension_spaces.iter() {
@@ -166,9 +191,18 @@ pub fn create_plan_from_symbol_table(symbol_table: &SymbolTable) -> Result<Plan,
                                 if !relation_data.new_pipelines.is_empty() {
                                     // Clone input_symbol and root_names to avoid borrow issues
                                     let input_symbol = relation_data.new_pipelines[0].clone();
-                                    let root_names = relation_data.root_names.clone();
+                                    let mut root_names = relation_data.root_names.clone();
                                     drop(blob_data); // Drop the lock early
 
+                                    // Without explicit names the root uses its input's field names
+                                    if root_names.is_empty() {
+                                        if let Ok(schema) =
+                                            symbol_table.relation_output_schema(&input_symbol)
+                                        {
+                                            root_names = schema.depth_first_names();
+                                        }
+                                    }
+
                                     // Get the input's Rel and build the tree
                                     if let Some(input_blob) = &input_symbol.blob {
                                         if let Ok(input_data) = input_blob.lock() {
@@ -247,15 +281,17 @@ fn populate_read_rel(
     source_symbol: &Option<Arc<SymbolInfo>>,
     schema_symbol: &Option<Arc<SymbolInfo>>,
     schema_name: &Option<String>,
+    projected_columns: &[(
+        String,
+        Option<::substrait::proto::expression::mask_expression::Select>,
+    )],
     read_rel: &mut ::substrait::proto::ReadRel,
 ) -> Result<(), TextPlanError> {
     // Try to resolve schema symbol by name if not already resolved
-    let resolved_schema = if schema_symbol.is_none() && schema_name.is_some() {
-        let name = schema_name.as_ref().unwrap();
+    let resolved_schema = match (schema_symbol, schema_name) {
         // Attempt late binding of schema name to symbol
-        symbol_table.lookup_symbol_by_name(name)
-    } else {
-        schema_symbol.clone()
+        (None, Some(name)) => symbol_table.lookup_symbol_by_name(name),
+        _ => schema_symbol.clone(),
     };
 
     // Populate base_schema from schema symbol
@@ -265,7 +301,6 @@ fn populate_read_rel(
         // Keep the existing baseSchema
     } else if let Some(schema_sym) = &resolved_schema {
         // Find all SchemaColumn symbols that belong to this schema
-        let mut field_names = Vec::new();
         let mut field_types = Vec::new();
 
         for symbol in symbol_table.symbols() {
@@ -273,9 +308,6 @@ fn populate_read_rel(
                 // Check if this column belongs to our schema
                 if let Some(column_schema) = symbol.schema() {
                     if Arc::ptr_eq(&column_schema, schema_sym) {
-                        // Add field name
-                        field_names.push(symbol.name().to_string());
-
                         // Add field type from the symbol's blob
                         let field_type = if let Some(blob_lock) = &symbol.blob {
                             if let Ok(blob_data) = blob_lock.lock() {
@@ -325,6 +357,10 @@ fn populate_read_rel(
             }
         }
 
+        // The names of a named struct also list the nested struct fields, depth first
+        let field_names =
+            relation_schema::schema_definition(symbol_table, schema_sym).depth_first_names();
+
         // Build NamedStruct
         if !field_names.is_empty() {
             read_rel.base_schema = Some(::substrait::proto::NamedStruct {
@@ -344,8 +380,18 @@ fn populate_read_rel(
         }
     }
 
-    // Populate namedTable from source symbol
-    if let Some(source_sym) = source_symbol {
+    // Local files are kept whole in the source's blob
+    let local_files = source_symbol.as_ref().and_then(|source_sym| {
+        source_sym.with_blob::<::substrait::proto::read_rel::LocalFiles, _, _>(|local_files| {
+            local_files.clone()
+        })
+    });
+    if let Some(local_files) = local_files {
+        read_rel.read_type = Some(::substrait::proto::read_rel::ReadType::LocalFiles(
+            local_files,
+        ));
+    } else if let Some(source_sym) = source_symbol {
+        // Populate namedTable from source symbol
         // Find SourceDetail symbols that belong to this source
         let mut table_names = Vec::new();
         for symbol in symbol_table.symbols() {
@@ -372,10 +418,17 @@ fn populate_read_rel(
             table_names.push(source_sym.name().to_string());
         }
 
+        // The source's blob carries the named table's advanced extension (if any)
+        let advanced_extension = source_sym
+            .with_blob::<::substrait::proto::read_rel::NamedTable, _, _>(|named_table| {
+                named_table.advanced_extension.clone()
+            })
+            .flatten();
+
         read_rel.read_type = Some(::substrait::proto::read_rel::ReadType::NamedTable(
             ::substrait::proto::read_rel::NamedTable {
                 names: table_names.clone(),
-                advanced_extension: None,
+                advanced_extension,
             },
         ));
         println!(
@@ -385,186 +438,104 @@ fn populate_read_rel(
         );
     }
 
-    // Set common to direct emission (no projection)
-    read_rel.common = Some(::substrait::proto::RelCommon {
-        emit_kind: Some(::substrait::proto::rel_common::EmitKind::Direct(
-            ::substrait::proto::rel_common::Direct {},
-        )),
-        ..Default::default()
-    });
+    // Populate the projection mask by resolving the projected column names
+    // against the (now complete) base schema
+    if !projected_columns.is_empty() {
+        let schema_names = read_rel
+            .base_schema
+            .as_ref()
+            .map(|schema| schema.names.clone())
+            .unwrap_or_default();
+
+        let mut struct_items = Vec::new();
+        for (projected_name, child) in projected_columns {
+            // Allow the column to be qualified with its schema name
+            let column_name = projected_name.rsplit('.').next().unwrap_or(projected_name);
+            let field = schema_names
+                .iter()
+                .position(|name| name == column_name)
+                .ok_or_else(|| {
+                    TextPlanError::SymbolTableError(format!(
+                        "Projected column '{}' is not part of the read relation's base schema",
+                        projected_name
+                    ))
+                })?;
+            struct_items.push(
+                ::substrait::proto::expression::mask_expression::StructItem {
+                    field: field as i32,
+                    child: child.clone(),
+                },
+            );
+        }
+
+        read_rel.projection = Some(::substrait::proto::expression::MaskExpression {
+            select: Some(
+                ::substrait::proto::expression::mask_expression::StructSelect { struct_items },
+            ),
+            maintain_singular_struct: false,
+        });
+    }
+
+    // Set common to direct emission (no emit mapping), keeping any hints from the text
+    let common = read_rel.common.get_or_insert_with(Default::default);
+    common.emit_kind = Some(::substrait::proto::rel_common::EmitKind::Direct(
+        ::substrait::proto::rel_common::Direct {},
+    ));
 
     Ok(())
 }
 
 /// Populates a ProjectRel's emit output mappings from symbol table references.
 fn populate_project_emit(
+    symbol_table: &SymbolTable,
     symbol: &Arc<SymbolInfo>,
     project_rel: &mut ::substrait::proto::ProjectRel,
 ) -> Result<(), TextPlanError> {
     // Get relation data to check for output field references (emits)
-    if let Some(blob_lock) = &symbol.blob {
-        if let Ok(blob_data) = blob_lock.lock() {
-            if let Some(relation_data) = blob_data.downcast_ref::<RelationData>() {
-                let emit_count = relation_data.output_field_references.len();
-
-                if emit_count > 0 {
-                    // Build output mapping by finding where each output_field_reference appears
-                    // IMPORTANT: Must search generated_field_references FIRST (like substrait-cpp),
-                    // because field selections create entries in BOTH field_references AND
-                    // generated_field_references (same Arc pointer), and we need the generated index.
-                    let field_refs_len = relation_data.field_references.len();
-                    let mut output_mapping = Vec::new();
-
-                    for output_field in &relation_data.output_field_references {
-                        // First search in generated_field_references
-                        if let Some(gen_index) = relation_data
-                            .generated_field_references
-                            .iter()
-                            .position(|f| Arc::ptr_eq(f, output_field))
-                        {
-                            // Found in generated fields - use offset index
-                            output_mapping.push((field_refs_len + gen_index) as i32);
-                        } else if let Some(field_index) = relation_data
-                            .field_references
-                            .iter()
-                            .position(|f| Arc::ptr_eq(f, output_field))
-                        {
-                            // Found in field references
-                            output_mapping.push(field_index as i32);
-                        } else {
-                            // Symbol not found - this shouldn't happen, but use a fallback
-                            println!(
-                                "  WARNING: Output field '{}' not found in field space for '{}'",
-                                output_field.name(),
-                                symbol.name()
-                            );
-                            output_mapping.push(0);
-                        }
-                    }
-
-                    println!(
-                        "  Building emit for project '{}': field_refs={}, generated={}, emits={}, mapping={:?}",
-                        symbol.name(),
-                        relation_data.field_references.len(),
-                        relation_data.generated_field_references.len(),
-                        emit_count,
-                        output_mapping
-                    );
+    let Some(output_mapping) = symbol
+        .with_blob::<RelationData, _, _>(|relation_data| {
+            relation_schema::emit_mapping(symbol, relation_data)
+        })
+        .transpose()?
+        .flatten()
+    else {
+        // No emits, use direct emission
+        let common = project_rel.common.get_or_insert_with(Default::default);
+        common.emit_kind = Some(::substrait::proto::rel_common::EmitKind::Direct(
+            ::substrait::proto::rel_common::Direct {},
+        ));
+        return Ok(());
+    };
 
-                    // Set the RelCommon with emit mapping
-                    project_rel.common = Some(::substrait::proto::RelCommon {
-                        emit_kind: Some(::substrait::proto::rel_common::EmitKind::Emit(
-                            ::substrait::proto::rel_common::Emit { output_mapping },
-                        )),
-                        ..Default::default()
-                    });
-                } else {
-                    // No emits, use direct emission
-                    project_rel.common = Some(::substrait::proto::RelCommon {
-                        emit_kind: Some(::substrait::proto::rel_common::EmitKind::Direct(
-                            ::substrait::proto::rel_common::Direct {},
-                        )),
-                        ..Default::default()
-                    });
-                }
-            }
+    // Every emitted field must be one of the project's input fields or expressions
+    if let Ok(input_schema) = relation_schema::relation_input_schema(symbol_table, symbol) {
+        let field_count = input_schema.len() + project_rel.expressions.len();
+        if let Some(index) = output_mapping
+            .iter()
+            .find(|index| **index < 0 || **index as usize >= field_count)
+        {
+            return Err(TextPlanError::SemanticError(format!(
+                "Project '{}' emits field {} but only has {} fields",
+                symbol.name(),
+                index,
+                field_count
+            )));
         }
     }
 
-    Ok(())
-}
+    println!(
+        "  Building emit for project '{}': mapping={:?}",
+        symbol.name(),
+        output_mapping
+    );
 
-/// Counts the output fields from a relation.
-/// This is a simplified version that needs proper implementation.
-fn count_relation_output_fields(rel: &::substrait::proto::Rel) -> usize {
-    // TODO: Implement proper field counting based on relation type
-    // For now, return a placeholder
-    if let Some(rel_type) = &rel.rel_type {
-        match rel_type {
-            ::substrait::proto::rel::RelType::Read(read_rel) => {
-                // Count fields from base_schema
-                if let Some(base_schema) = &read_rel.base_schema {
-                    base_schema.names.len()
-                } else {
-                    0
-                }
-            }
-            ::substrait::proto::rel::RelType::Cross(cross_rel) => {
-                // Cross product combines left and right fields
-                let left_count = if let Some(left) = &cross_rel.left {
-                    count_relation_output_fields(left)
-                } else {
-                    0
-                };
-                let right_count = if let Some(right) = &cross_rel.right {
-                    count_relation_output_fields(right)
-                } else {
-                    0
-                };
-                left_count + right_count
-            }
-            ::substrait::proto::rel::RelType::Filter(filter_rel) => {
-                // Filter passes through all input fields from its input
-                if let Some(input) = &filter_rel.input {
-                    count_relation_output_fields(input)
-                } else {
-                    0
-                }
-            }
-            ::substrait::proto::rel::RelType::Project(proj) => {
-                // Project outputs based on emit mapping or expressions
-                if let Some(common) = &proj.common {
-                    if let Some(emit_kind) = &common.emit_kind {
-                        match emit_kind {
-                            ::substrait::proto::rel_common::EmitKind::Emit(emit) => {
-                                // Emit specifies exactly which fields to output
-                                emit.output_mapping.len()
-                            }
-                            ::substrait::proto::rel_common::EmitKind::Direct(_) => {
-                                // Direct emission outputs all input fields plus all expressions
-                                let input_count = if let Some(input) = &proj.input {
-                                    count_relation_output_fields(input)
-                                } else {
-                                    0
-                                };
-                                input_count + proj.expressions.len()
-                            }
-                        }
-                    } else {
-                        // No emit specified - output all input fields plus expressions
-                        let input_count = if let Some(input) = &proj.input {
-                            count_relation_output_fields(input)
-                        } else {
-                            0
-                        };
-                        input_count + proj.expressions.len()
-                    }
-                } else {
-                    // No common - output all input fields plus expressions
-                    let input_count = if let Some(input) = &proj.input {
-                        count_relation_output_fields(input)
-                    } else {
-                        0
-                    };
-                    input_count + proj.expressions.len()
-                }
-            }
-            ::substrait::proto::rel::RelType::Aggregate(agg) => {
-                // Aggregate outputs grouping keys + measures
-                #[allow(deprecated)]
-                let grouping_count = agg
-                    .groupings
-                    .first()
-                    .map(|g| g.grouping_expressions.len())
-                    .unwrap_or(0);
-                let measure_count = agg.measures.len();
-                grouping_count + measure_count
-            }
-            _ => 0, // Other relation types
-        }
-    } else {
-        0
-    }
+    // Set the RelCommon with emit mapping (hints from the text are kept)
+    let common = project_rel.common.get_or_insert_with(Default::default);
+    common.emit_kind = Some(::substrait::proto::rel_common::EmitKind::Emit(
+        ::substrait::proto::rel_common::Emit { output_mapping },
+    ));
+
+    Ok(())
 }
 
 /// Helper to get sub_query_pipelines from a symbol.
@@ -659,6 +630,7 @@ fn add_inputs_to_relation(
         let source_symbol = relation_data.source.clone();
         let schema_symbol = relation_data.schema.clone();
         let schema_name = relation_data.schema_name.clone();
+        let projected_columns = relation_data.projected_columns.clone();
 
         println!(
             "  '{}' has continuing_pipeline={:?}, new_pipelines.len={}",
@@ -674,6 +646,7 @@ fn add_inputs_to_relation(
             source_symbol,
             schema_symbol,
             schema_name,
+            projected_columns,
         )
     };
     // All locks are dropped here
@@ -685,6 +658,7 @@ fn add_inputs_to_relation(
         source_symbol,
         schema_symbol,
         schema_name,
+        projected_columns,
     ) = result;
     println!("  Lock dropped for '{}'", symbol.name());
 
@@ -701,6 +675,7 @@ fn add_inputs_to_relation(
                     &source_symbol,
                     &schema_symbol,
                     &schema_name,
+                    &projected_columns,
                     read_rel,
                 )?;
                 // Read has no inputs
@@ -786,7 +761,7 @@ fn add_inputs_to_relation(
                 }
 
                 // Populate emit output mappings if this project has generated field references
-                populate_project_emit(symbol, project_rel)?;
+                populate_project_emit(symbol_table, symbol, project_rel)?;
             }
             rel::RelType::Aggregate(agg_rel) => {
                 println!(
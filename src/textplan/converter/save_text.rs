@@ -3,6 +3,7 @@
 //! Save a binary Substrait plan to textplan format.
 
 use crate::proto;
+use crate::textplan::common::diagnostic::Diagnostic;
 use crate::textplan::common::error::TextPlanError;
 use crate::textplan::common::structured_symbol_data::RelationData;
 use crate::textplan::converter::initial_plan_visitor::InitialPlanVisitor;
@@ -119,7 +120,7 @@ pub fn process_plan_with_visitor(plan: &substrait::proto::Plan) -> Result<String
 
     let mut visitor1 = InitialPlanVisitor::new(symbol_table);
     visitor1.visit_plan(plan);
-    // MEGAHACK -- Check for errors.
+    check_visitor_errors(visitor1.diagnostics())?;
 
     println!(
         "DEBUG: After InitialPlanVisitor, symbol table has {} symbols",
@@ -153,8 +154,7 @@ pub fn process_plan_with_visitor(plan: &substrait::proto::Plan) -> Result<String
 
     // Visit the plan to build the symbol table
     visitor.visit_plan(plan);
-
-    // MEGAHACK -- Check for errors.
+    check_visitor_errors(visitor.diagnostics())?;
 
     println!(
         "DEBUG: After PipelineVisitor, symbol table has {} symbols",
@@ -177,6 +177,24 @@ pub fn process_plan_with_visitor(plan: &substrait::proto::Plan) -> Result<String
     Ok(plan_text)
 }
 
+/// Fails the conversion with the problems a visitor found, each prefixed by its location
+/// in the plan.
+fn check_visitor_errors(diagnostics: Vec<Diagnostic>) -> Result<(), TextPlanError> {
+    let errors: Vec<String> = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+        .map(|diagnostic| match diagnostic.proto_path() {
+            Some(path) if !path.is_empty() => format!("{}: {}", path, diagnostic.message()),
+            _ => diagnostic.message().to_string(),
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(TextPlanError::BinaryConversionError(errors.join("; ")))
+    }
+}
+
 /// Populates pipeline_start for all relations in subquery pipelines.
 ///
 /// This function finds subquery terminus relations (those with parent_query_index >= 0)
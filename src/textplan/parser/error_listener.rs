@@ -7,9 +7,12 @@ use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
 
 // Import everything we need from antlr_rust
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::textplan::common::source_map::{LineColumn, SourceMap};
 use crate::textplan::common::text_location::TextLocation;
 use antlr_rust::atn_config_set::ATNConfigSet;
 use antlr_rust::dfa::DFA;
+use antlr_rust::token::Token;
 use antlr_rust::token_factory::{TokenAware, TokenFactory};
 
 /// Represents an error encountered during parsing.
@@ -44,10 +47,18 @@ impl fmt::Display for ParseError {
     }
 }
 
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        Diagnostic::error(DiagnosticCode::InvalidPlan, error.message, error.location)
+    }
+}
+
 /// Listens for errors during parsing.
 pub struct ErrorListener {
-    /// The errors encountered during parsing.
-    errors: Arc<Mutex<Vec<ParseError>>>,
+    /// The diagnostics reported during parsing.
+    errors: Arc<Mutex<Vec<Diagnostic>>>,
+    /// The text being parsed, used to locate errors reported by line and column.
+    source_map: SourceMap,
 }
 
 impl Default for ErrorListener {
@@ -59,27 +70,53 @@ impl Default for ErrorListener {
 impl ErrorListener {
     /// Creates a new error listener.
     pub fn new() -> Self {
+        Self::with_source(SourceMap::default())
+    }
+
+    /// Creates a new error listener for the given text.
+    pub fn with_source(source_map: SourceMap) -> Self {
         Self {
             errors: Arc::new(Mutex::new(Vec::new())),
+            source_map,
         }
     }
 
-    /// Adds an error to the listener.
+    /// Returns the source map of the text being parsed.
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    /// Adds an error about the meaning of the plan to the listener.
     pub fn add_error(&self, message: String, location: TextLocation) {
+        self.add_diagnostic(ParseError::new(message, location).into());
+    }
+
+    /// Adds a diagnostic to the listener.
+    pub fn add_diagnostic(&self, diagnostic: Diagnostic) {
         let mut errors = self.errors.lock().unwrap();
-        errors.push(ParseError::new(message, location));
+        errors.push(diagnostic);
     }
 
     /// Returns true if any errors were encountered during parsing.
     pub fn has_errors(&self) -> bool {
         let errors = self.errors.lock().unwrap();
-        !errors.is_empty()
+        errors.iter().any(Diagnostic::is_error)
+    }
+
+    /// Returns all of the diagnostics reported so far.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let errors = self.errors.lock().unwrap();
+        errors.clone()
     }
 
     /// Formats all errors as strings.
     pub fn format_errors(&self) -> Vec<String> {
         let errors = self.errors.lock().unwrap();
-        errors.iter().map(|e| e.to_string()).collect()
+        errors
+            .iter()
+            .filter(|e| e.is_error())
+            .map(|e| e.format_with(&self.source_map))
+            .collect()
     }
 }
 
@@ -95,11 +132,24 @@ impl AntlrErrorListener {
         Self { error_listener }
     }
 
-    /// Reports a syntax error.
+    /// Reports a syntax error at a 1-based line and 0-based column.
     pub fn syntax_error(&self, line: isize, column: isize, msg: &str) {
         // Convert line/column to our Location type and add the error
-        let location = TextLocation::new(line as i32, column as i32);
-        self.error_listener.add_error(msg.to_string(), location);
+        let position = LineColumn::new(line.max(1) as usize, column.max(0) as usize + 1);
+        let location = match self.error_listener.source_map().offset(position) {
+            Some(offset) => TextLocation::new(offset as i32, 1),
+            None => TextLocation::unknown(),
+        };
+        self.report_syntax_error(location, msg);
+    }
+
+    /// Reports a syntax error at the given location.
+    fn report_syntax_error(&self, location: TextLocation, msg: &str) {
+        self.error_listener.add_diagnostic(Diagnostic::error(
+            DiagnosticCode::SyntaxError,
+            msg,
+            location,
+        ));
     }
 
     /// Gets the underlying ErrorListener
@@ -117,7 +167,7 @@ where
     fn syntax_error(
         &self,
         _recognizer: &T,
-        _offending_symbol: std::option::Option<
+        offending_symbol: std::option::Option<
             &<<T as TokenAware<'input>>::TF as TokenFactory<'input>>::Inner,
         >,
         line: isize,
@@ -125,8 +175,16 @@ where
         msg: &str,
         _e: std::option::Option<&antlr_rust::errors::ANTLRError>,
     ) {
-        // Forward to our internal method
-        self.syntax_error(line, column, msg);
+        // Underline the offending token (the end of file token has no text).  The lexer
+        // does not have a token yet so it reports the error by line and column instead.
+        match offending_symbol.filter(|token| token.get_start() >= 0) {
+            Some(token) => {
+                let length = (token.get_stop() - token.get_start() + 1).max(0);
+                let location = TextLocation::new(token.get_start() as i32, length as i32);
+                self.report_syntax_error(location, msg);
+            }
+            None => self.syntax_error(line, column, msg),
+        }
     }
 
     fn report_ambiguity(
@@ -171,13 +229,15 @@ where
         _configs: &ATNConfigSet,
     ) {
         // Log context sensitivity
-        self.error_listener.add_error(
+        self.error_listener.add_diagnostic(Diagnostic::error(
+            DiagnosticCode::SyntaxError,
             format!(
                 "Context sensitivity detected at indices {}-{} with prediction {}",
                 start_index, stop_index, prediction
             ),
-            TextLocation::new(start_index as i32, stop_index as i32),
-        );
+            // The indices are into the token stream so they can't be placed in the text
+            TextLocation::unknown(),
+        ));
     }
 }
 
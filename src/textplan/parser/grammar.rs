@@ -2,16 +2,32 @@
 
 //! Grammar module for the ANTLR4 parser.
 
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::textplan::common::extension_registry::ExtensionRegistry;
+use crate::textplan::common::source_map::SourceMap;
+use crate::textplan::parser::antlr::substraitplanlexer::{LEFTBRACE, RIGHTBRACE};
 use crate::textplan::parser::antlr::substraitplanparser::SubstraitPlanParserContextType;
+use crate::textplan::parser::antlr::substraitplanparservisitor::SubstraitPlanParserVisitor;
 use crate::textplan::parser::antlr::{SubstraitPlanLexer, SubstraitPlanParser};
 use crate::textplan::parser::error_listener::ErrorListener;
+use crate::textplan::symbol_table::SymbolTable;
 use crate::textplan::TextLocation;
 use antlr_rust::{
-    common_token_stream::CommonTokenStream, input_stream::InputStream, parser::Parser,
-    token_factory::CommonTokenFactory, DefaultErrorStrategy,
+    common_token_stream::CommonTokenStream,
+    input_stream::InputStream,
+    int_stream::{IntStream, EOF},
+    lexer::Lexer,
+    parser::Parser,
+    token::{Token, TOKEN_DEFAULT_CHANNEL},
+    token_factory::CommonTokenFactory,
+    token_stream::TokenStream,
+    DefaultErrorStrategy,
 };
+use std::collections::HashSet;
 use std::fs;
+use std::ops::Range;
 use std::path::Path;
+use std::rc::Rc;
 use std::sync::Arc;
 
 /// Options for the ANTLR parser.
@@ -55,69 +71,215 @@ pub struct ParseResult {
 ///
 /// A result containing either a processed symbol table or an error message.
 pub fn parse_string(text: &str) -> Result<ParseResult, String> {
-    use crate::textplan::parser::visitors::PlanVisitor as PlanVisitorTrait;
-    use crate::textplan::symbol_table::SymbolTable;
+    parse_string_with_registry(text, ExtensionRegistry::standard())
+}
 
+/// Parses a string using ANTLR, checking function declarations against the given
+/// extension registry.
+///
+/// # Arguments
+///
+/// * `text` - The text to parse.
+/// * `extension_registry` - The extension files that function declarations must match.
+///
+/// # Returns
+///
+/// A result containing either a processed symbol table or an error message.
+pub fn parse_string_with_registry(
+    text: &str,
+    extension_registry: Arc<ExtensionRegistry>,
+) -> Result<ParseResult, String> {
     // Create an error listener
-    let error_listener = Arc::new(ErrorListener::new());
+    let error_listener = Arc::new(ErrorListener::with_source(SourceMap::new(text)));
 
     // Create a token factory - this needs to outlive the lexer
     let tf = CommonTokenFactory;
 
-    // Create a lexer
-    let lexer_result = create_lexer(text, error_listener.clone(), &tf);
-
-    // If lexer creation fails, return the error
-    let lexer = match lexer_result {
-        Ok(mut lexer) => {
-            // Create an ANTLR error listener
-            let boxed_error_listener = create_boxed_error_listener(error_listener.clone());
+    // Most plans have no syntax errors, so first parse the text as a whole.  Its errors
+    // are collected separately as they are reported again below if there are any.
+    let whole_text_listener = Arc::new(ErrorListener::with_source(SourceMap::new(text)));
+    let (whole_plan, blocks) = parse_plan(text, 0..text.len(), whole_text_listener.clone(), &tf)?;
+    if let (Some(plan), false) = (whole_plan, whole_text_listener.has_errors()) {
+        let symbol_table = build_symbol_table(&[plan], &error_listener, extension_registry);
+        return Ok(ParseResult {
+            symbol_table,
+            error_listener,
+        });
+    }
 
-            // Add the error listener to the lexer
-            lexer.remove_error_listeners();
-            lexer.add_error_listener(boxed_error_listener);
+    // A syntax error makes ANTLR report errors for the rest of the text, so parse each
+    // top level block (relation, schema, source, ...) on its own instead.  Each block is
+    // lexed starting from its offset in the text so that locations remain correct.  The
+    // visitors cannot make sense of a tree with syntax errors, so only the blocks without
+    // errors contribute to the symbol table.
+    let mut plans = Vec::new();
+    let mut dropped_names = HashSet::new();
+    for block in &blocks {
+        let error_count = error_listener.diagnostics().len();
+        let (plan, _) = parse_plan(text, block.range.clone(), error_listener.clone(), &tf)?;
+        match plan {
+            Some(plan) if error_listener.diagnostics().len() == error_count => plans.push(plan),
+            _ => dropped_names.extend(block.name.clone()),
+        }
+    }
 
-            lexer
+    // The dropped blocks are missing from the symbol table, so leave out the errors within
+    // the relations that depend on them and those pointing at references to their names.
+    let semantic_listener = Arc::new(ErrorListener::with_source(SourceMap::new(text)));
+    let symbol_table = build_symbol_table(&plans, &semantic_listener, extension_registry);
+    let dropped_names = with_dependent_relations(&symbol_table, dropped_names);
+    let dropped_ranges: Vec<&Range<usize>> = blocks
+        .iter()
+        .filter(|block| {
+            block
+                .name
+                .as_ref()
+                .is_some_and(|name| dropped_names.contains(name))
+        })
+        .map(|block| &block.range)
+        .collect();
+    for diagnostic in semantic_listener.diagnostics() {
+        let start = diagnostic.location().position() as usize;
+        let end = start + diagnostic.location().length() as usize;
+        let in_dropped_block = dropped_ranges.iter().any(|range| range.contains(&start));
+        let at_dropped_name = text
+            .get(start..end)
+            .is_some_and(|located| dropped_names.contains(located));
+        if !in_dropped_block && !at_dropped_name {
+            error_listener.add_diagnostic(diagnostic);
         }
-        Err(err) => return Err(err),
-    };
+    }
 
-    // Create a token stream from the lexer
-    let token_stream = CommonTokenStream::new(lexer);
+    Ok(ParseResult {
+        symbol_table,
+        error_listener,
+    })
+}
 
-    // Create a parser with the token stream and default error strategy
-    let error_strategy = DefaultErrorStrategy::new();
-    let mut parser = SubstraitPlanParser::with_strategy(token_stream, error_strategy);
+/// A top level block (`plan_detail`) of a plan, such as a relation, schema or source.
+struct PlanBlock {
+    /// The bytes of the block, including any text since the end of the previous block.
+    range: Range<usize>,
+    /// The name the block defines, such as `myread` for `read relation myread { ... }`.
+    name: Option<String>,
+}
 
-    // Create an ANTLR error listener for the parser
-    let boxed_error_listener = create_boxed_error_listener(error_listener.clone());
+/// Parses text into a plan parse tree, reporting any syntax errors to the error listener.
+///
+/// Only the given byte range of the text is parsed.  Returns `None` for the tree if the
+/// parser could not produce one at all, along with the top level blocks of the text.
+fn parse_plan<'input>(
+    text: &'input str,
+    range: Range<usize>,
+    error_listener: Arc<ErrorListener>,
+    tf: &'input CommonTokenFactory,
+) -> Result<(Option<Rc<PlanContext<'input>>>, Vec<PlanBlock>), String> {
+    let mut input = InputStream::new(&text[..range.end]);
+    input.seek(range.start as isize);
+    let mut lexer = SubstraitPlanLexer::new_with_token_factory(input, tf);
+    let start = error_listener.source_map().line_column(range.start);
+    lexer.set_line(start.line as isize);
+    lexer.set_char_position_in_line(start.column as isize - 1);
+    lexer.remove_error_listeners();
+    lexer.add_error_listener(create_boxed_error_listener(error_listener.clone()));
 
-    // Add the error listener to the parser
-    parser.remove_error_listeners();
-    parser.add_error_listener(boxed_error_listener);
+    let token_stream = CommonTokenStream::new(lexer);
+    let mut parser = create_parser(token_stream, error_listener.clone())?;
 
-    // Call the parser's plan() method to get the parse tree
-    let plan_result = match parser.plan() {
-        Ok(plan_ctx) => plan_ctx,
+    let plan = match parser.plan() {
+        Ok(plan_ctx) => Some(plan_ctx),
         Err(e) => {
-            // If we get a parsing error, add it to our error listener
-            error_listener.add_error(
+            error_listener.add_diagnostic(Diagnostic::error(
+                DiagnosticCode::ParserFailure,
                 format!("Parser error: {}", e),
                 TextLocation::new(0, 0), // We don't have specific location for this error
-            );
-            // Return early with the error
-            return Err(format!("Parser error: {}", e));
+            ));
+            None
         }
     };
+    let blocks = plan_blocks(parser.get_input_stream_mut(), range);
+    Ok((plan, blocks))
+}
+
+/// Splits the tokens of a parsed text into its top level blocks.
+///
+/// A block ends with the closing brace that brings the nesting depth back to zero, any
+/// text between two blocks is part of the following block.  The parser may have given up
+/// before reaching the end of the text, in which case the remaining tokens are read first.
+fn plan_blocks<'input>(
+    tokens: &mut dyn TokenStream<'input, TF = CommonTokenFactory>,
+    range: Range<usize>,
+) -> Vec<PlanBlock> {
+    while tokens.la(1) != EOF {
+        tokens.consume();
+    }
+
+    let mut blocks = Vec::new();
+    let mut block_start = range.start;
+    let mut name = None;
+    let mut previous_text = None;
+    let mut depth = 0usize;
+    let mut pending = false;
+    for index in 0..tokens.size() {
+        let token = tokens.get(index);
+        let token_type = token.get_token_type();
+        if token_type == EOF || token.get_channel() != TOKEN_DEFAULT_CHANNEL {
+            continue;
+        }
+        pending = true;
+        match token_type {
+            LEFTBRACE => {
+                if depth == 0 {
+                    name = previous_text.take();
+                }
+                depth += 1;
+            }
+            RIGHTBRACE if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let block_end = token.get_stop() as usize + 1;
+                    blocks.push(PlanBlock {
+                        range: block_start..block_end,
+                        name: name.take(),
+                    });
+                    block_start = block_end;
+                    pending = false;
+                }
+            }
+            _ => {}
+        }
+        previous_text = Some(token.get_text().to_string());
+    }
+    if pending {
+        blocks.push(PlanBlock {
+            range: block_start..range.end,
+            name,
+        });
+    }
+    blocks
+}
 
-    // Check for errors collected by our custom error listener
-    if error_listener.has_errors() {
-        let error_messages = error_listener.format_errors();
-        return Err(format!("Parsing errors: {}", error_messages.join(", ")));
+/// Applies a visitor to each of the parse trees in turn.
+fn visit_plans<'input, V>(visitor: &mut V, plans: &[Rc<PlanContext<'input>>])
+where
+    V: SubstraitPlanParserVisitor<'input>,
+{
+    for plan in plans {
+        crate::textplan::parser::visitors::visit_plan(visitor, plan.as_ref());
     }
+}
+
+/// Builds the symbol table for the given parse trees.
+///
+/// The trees are processed together, one phase at a time, so the trees of the separately
+/// parsed blocks of a plan may refer to each other as if they had been parsed as one.
+fn build_symbol_table<'input>(
+    plans: &[Rc<PlanContext<'input>>],
+    error_listener: &Arc<ErrorListener>,
+    extension_registry: Arc<ExtensionRegistry>,
+) -> SymbolTable {
+    use crate::textplan::parser::visitors::PlanVisitor as PlanVisitorTrait;
 
-    // Process the parse tree with visitors while the lexer/parser are still alive.
-    // This is critical because the parse tree has references to the token stream.
     let mut symbol_table = SymbolTable::new();
 
     // Process the parse tree in multiple phases using our visitors
@@ -125,7 +287,7 @@ pub fn parse_string(text: &str) -> Result<ParseResult, String> {
     println!("Applying TypeVisitor");
     let mut type_visitor =
         crate::textplan::parser::visitors::TypeVisitor::new(symbol_table, error_listener.clone());
-    crate::textplan::parser::visitors::visit_plan(&mut type_visitor, plan_result.as_ref());
+    visit_plans(&mut type_visitor, plans);
     symbol_table = type_visitor.symbol_table();
 
     // Phase 2: Main plan visitor
@@ -134,7 +296,8 @@ pub fn parse_string(text: &str) -> Result<ParseResult, String> {
         symbol_table,
         error_listener.clone(),
     );
-    crate::textplan::parser::visitors::visit_plan(&mut plan_visitor, plan_result.as_ref());
+    plan_visitor.set_extension_registry(extension_registry.clone());
+    visit_plans(&mut plan_visitor, plans);
     symbol_table = plan_visitor.symbol_table();
 
     // Phase 3: Pipeline visitor
@@ -143,7 +306,7 @@ pub fn parse_string(text: &str) -> Result<ParseResult, String> {
         symbol_table,
         error_listener.clone(),
     );
-    crate::textplan::parser::visitors::visit_plan(&mut pipeline_visitor, plan_result.as_ref());
+    visit_plans(&mut pipeline_visitor, plans);
     symbol_table = pipeline_visitor.symbol_table();
 
     // Phase 4: Relation visitor
@@ -152,8 +315,9 @@ pub fn parse_string(text: &str) -> Result<ParseResult, String> {
         symbol_table,
         error_listener.clone(),
     );
+    relation_visitor.set_extension_registry(extension_registry);
 
-    crate::textplan::parser::visitors::visit_plan(&mut relation_visitor, plan_result.as_ref());
+    visit_plans(&mut relation_visitor, plans);
     symbol_table = relation_visitor.symbol_table();
 
     // Phase 5: Subquery relation visitor
@@ -162,21 +326,74 @@ pub fn parse_string(text: &str) -> Result<ParseResult, String> {
         symbol_table,
         error_listener.clone(),
     );
-    crate::textplan::parser::visitors::visit_plan(&mut subquery_visitor, plan_result.as_ref());
+    visit_plans(&mut subquery_visitor, plans);
 
     // Populate sub_query_pipelines now that parent_query_index has been set during the visit
     subquery_visitor.finalize();
 
+    // Pipelines of subqueries end without a root, which is only known now
+    pipeline_visitor.validate_pipelines();
+
+    // Columns of subqueries may come from the enclosing query, which is only known now
+    relation_visitor.report_unresolved_columns();
+
     symbol_table = subquery_visitor.symbol_table();
 
+    // Phase 6: Type check visitor
+    println!("Applying TypeCheckVisitor");
+    let mut type_check_visitor = crate::textplan::parser::visitors::TypeCheckVisitor::new(
+        symbol_table,
+        error_listener.clone(),
+    );
+    visit_plans(&mut type_check_visitor, plans);
+    symbol_table = type_check_visitor.symbol_table();
+
     // Outer references were already fixed by SubqueryRelationVisitor during its visit pass.
     // No need for additional fixing here.
 
-    // Return the parse result with the processed symbol table
-    Ok(ParseResult {
-        symbol_table,
-        error_listener,
-    })
+    symbol_table
+}
+
+/// Adds the relations that take their input, schema or source from one of the given
+/// names, directly or through other relations, to the names.
+fn with_dependent_relations(
+    symbol_table: &SymbolTable,
+    mut names: HashSet<String>,
+) -> HashSet<String> {
+    use crate::textplan::common::structured_symbol_data::RelationData;
+    use crate::textplan::symbol_table::SymbolType;
+
+    if names.is_empty() {
+        return names;
+    }
+    loop {
+        let mut added = false;
+        for symbol in symbol_table.symbols() {
+            if symbol.symbol_type() != SymbolType::Relation || names.contains(symbol.name()) {
+                continue;
+            }
+            let depends = symbol
+                .with_blob::<RelationData, _, _>(|data| {
+                    let inputs = data
+                        .new_pipelines
+                        .iter()
+                        .chain(&data.schema)
+                        .chain(&data.source);
+                    inputs
+                        .map(|input| input.name())
+                        .chain(data.schema_name.as_deref())
+                        .any(|name| names.contains(name))
+                })
+                .unwrap_or(false);
+            if depends {
+                names.insert(symbol.name().to_string());
+                added = true;
+            }
+        }
+        if !added {
+            return names;
+        }
+    }
 }
 
 /// Fixes field references in subquery relations to use outerReference instead of rootReference
@@ -98,10 +98,15 @@ pub fn load_from_text(text: &str) -> Result<Vec<u8>, TextPlanError> {
 
     let parse_result = parse_stream(text);
 
-    if !parse_result.successful() {
-        let errors = parse_result.all_errors();
-        let error_msg = errors.join("; ");
-        return Err(TextPlanError::ParseError(error_msg));
+    let syntax_errors = parse_result.syntax_errors();
+    if !syntax_errors.is_empty() {
+        return Err(TextPlanError::ParseError(syntax_errors.join("; ")));
+    }
+
+    // A plan with unknown columns or functions would be emitted with bogus references
+    let semantic_errors = parse_result.semantic_errors();
+    if !semantic_errors.is_empty() {
+        return Err(TextPlanError::SemanticError(semantic_errors.join("; ")));
     }
 
     // Convert the symbol table to a protobuf plan
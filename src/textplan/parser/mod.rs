@@ -14,4 +14,4 @@ pub mod antlr;
 
 // Re-export the main functions
 pub use load_text::load_from_text;
-pub use parse_text::{load_text_file, load_text_string, parse_stream};
+pub use parse_text::{load_text_file, load_text_string, parse_stream, parse_stream_with_registry};
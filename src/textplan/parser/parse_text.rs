@@ -7,8 +7,12 @@ use std::io::Read;
 use std::path::Path;
 use std::sync::Arc;
 
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
 use crate::textplan::common::error::TextPlanError;
+use crate::textplan::common::extension_registry::ExtensionRegistry;
 use crate::textplan::common::parse_result::ParseResult;
+use crate::textplan::common::source_map::SourceMap;
+use crate::textplan::common::text_location::TextLocation;
 use crate::textplan::parser::antlr::substraitplanparser::PlanContext;
 use crate::textplan::parser::error_listener::ErrorListener;
 use crate::textplan::parser::grammar;
@@ -63,33 +67,44 @@ pub fn load_text_string(text: &str) -> String {
 ///
 /// The parse result.
 pub fn parse_stream(text: &str) -> ParseResult {
+    parse_stream_with_registry(text, ExtensionRegistry::standard())
+}
+
+/// Parses a textplan, checking its function declarations against the given extensions.
+///
+/// # Arguments
+///
+/// * `text` - The text to parse.
+/// * `extension_registry` - The extension files that function declarations must match.
+///
+/// # Returns
+///
+/// The parse result.
+pub fn parse_stream_with_registry(
+    text: &str,
+    extension_registry: Arc<ExtensionRegistry>,
+) -> ParseResult {
     // If the text is empty, return an empty result
     if text.trim().is_empty() {
-        return ParseResult::new(SymbolTable::new(), Vec::new(), Vec::new());
+        return ParseResult::new(SymbolTable::new(), Vec::new(), SourceMap::new(text));
     }
 
     // Try to parse the text using ANTLR
     // The parse_string function now handles the visitor processing internally
-    match grammar::parse_string(text) {
+    match grammar::parse_string_with_registry(text, extension_registry) {
         Ok(grammar_result) => {
-            // Get any errors from the error listener
-            let error_messages = if grammar_result.error_listener.has_errors() {
-                grammar_result
-                    .error_listener
-                    .format_errors()
-                    .into_iter()
-                    .map(|msg| format!("ANTLR parsing error: {}", msg))
-                    .collect()
-            } else {
-                Vec::new()
-            };
-
-            // Return the parse result with the symbol table and any errors
-            ParseResult::new(grammar_result.symbol_table, error_messages, Vec::new())
+            // Return the parse result with the symbol table and any diagnostics
+            ParseResult::new(
+                grammar_result.symbol_table,
+                grammar_result.error_listener.diagnostics(),
+                grammar_result.error_listener.source_map().clone(),
+            )
         }
         Err(err) => {
             // If parsing fails, return an error result
-            ParseResult::new(SymbolTable::new(), vec![err], Vec::new())
+            let diagnostic =
+                Diagnostic::error(DiagnosticCode::ParserFailure, err, TextLocation::unknown());
+            ParseResult::new(SymbolTable::new(), vec![diagnostic], SourceMap::new(text))
         }
     }
 }
@@ -11,14 +11,21 @@ use antlr_rust::token::{GenericToken, Token};
 use antlr_rust::tree::{ParseTree, ParseTreeVisitor};
 use antlr_rust::TidExt;
 
-use crate::textplan::common::structured_symbol_data::RelationData;
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::textplan::common::extension_registry::ExtensionRegistry;
+use crate::textplan::common::structured_symbol_data::{ParameterData, RelationData};
 use crate::textplan::parser::antlr::substraitplanparser::*;
 use crate::textplan::parser::antlr::substraitplanparservisitor::SubstraitPlanParserVisitor;
 use crate::textplan::parser::error_listener::ErrorListener;
-use crate::textplan::symbol_table::{RelationType, SymbolInfo, SymbolTable, SymbolType};
+use crate::textplan::symbol_table::{
+    RelationType, SourceType, SymbolInfo, SymbolTable, SymbolType,
+};
 use ::substrait::proto::{rel::RelType, Rel};
 
-use super::{extract_from_string, token_to_location, PlanVisitor, TypeVisitor};
+use super::{
+    add_advanced_extension_details, extract_from_string, lookup_or_define_parameter,
+    token_to_location, PlanVisitor, TypeVisitor,
+};
 
 /// The PlanVisitor processes the top-level plan structure.
 ///
@@ -31,6 +38,7 @@ pub struct MainPlanVisitor<'input> {
     current_extension_space: Option<Arc<SymbolInfo>>, // Track current extension space
     num_spaces_seen: i32,
     num_functions_seen: i32,
+    extension_registry: Arc<ExtensionRegistry>, // Extension files to check function declarations against
 }
 
 impl<'input> MainPlanVisitor<'input> {
@@ -43,9 +51,15 @@ impl<'input> MainPlanVisitor<'input> {
             current_extension_space: None,
             num_spaces_seen: 0,
             num_functions_seen: 0,
+            extension_registry: Arc::new(ExtensionRegistry::new()),
         }
     }
 
+    /// Sets the extension files that function declarations are checked against.
+    pub fn set_extension_registry(&mut self, registry: Arc<ExtensionRegistry>) {
+        self.extension_registry = registry;
+    }
+
     /// Gets the current relation scope, if any.
     pub fn current_relation_scope(&self) -> Option<&Arc<SymbolInfo>> {
         self.current_relation_scope.as_ref()
@@ -85,6 +99,20 @@ impl<'input> MainPlanVisitor<'input> {
         self.type_visitor.add_error(token, message);
     }
 
+    /// Adds an error with a specific diagnostic code to the error listener.
+    pub fn add_error_with_code<'a>(
+        &self,
+        token: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+        code: DiagnosticCode,
+        message: &str,
+    ) {
+        self.get_error_listener().add_diagnostic(Diagnostic::error(
+            code,
+            message,
+            token_to_location(token),
+        ));
+    }
+
     /// Process an extension space and add it to the symbol table.
     fn process_extension_space(
         &mut self,
@@ -152,6 +180,16 @@ impl<'input> MainPlanVisitor<'input> {
         let token = ctx.start();
         let location = token_to_location(&token);
 
+        // Check the declaration against the extension file it comes from
+        if let Some(ext_space) = &self.current_extension_space {
+            if let Err(message) = self
+                .extension_registry
+                .validate_declaration(ext_space.name(), &full_name)
+            {
+                self.add_error_with_code(&token, DiagnosticCode::ExtensionMismatch, &message);
+            }
+        }
+
         // Assign an anchor for this function (incrementing counter)
         let anchor = self.num_functions_seen as u32;
 
@@ -220,13 +258,36 @@ impl<'input> MainPlanVisitor<'input> {
                 let token = ctx.start();
                 let location = token_to_location(&token);
 
-                // Define the source in the symbol table
+                // Define the source in the symbol table. The blob holds the parts of the
+                // named table that aren't symbols themselves (its advanced extension).
+                let blob = Some(Arc::new(Mutex::new(
+                    ::substrait::proto::read_rel::NamedTable::default(),
+                )) as Arc<Mutex<dyn Any + Send + Sync>>);
                 let symbol = self.type_visitor.symbol_table_mut().define_symbol(
                     name,
                     location,
                     SymbolType::Source,
                     None,
-                    None,
+                    blob,
+                );
+
+                Some(symbol)
+            }
+            Read_typeContextAll::LocalFilesContext(local_files_ctx) => {
+                let name = local_files_ctx.id()?.get_text();
+                let token = ctx.start();
+                let location = token_to_location(&token);
+
+                // As with named tables the blob holds the source's advanced extension.
+                let blob = Some(Arc::new(Mutex::new(
+                    ::substrait::proto::read_rel::LocalFiles::default(),
+                )) as Arc<Mutex<dyn Any + Send + Sync>>);
+                let symbol = self.type_visitor.symbol_table_mut().define_symbol(
+                    name,
+                    location,
+                    SymbolType::Source,
+                    Some(Box::new(SourceType::LocalFiles)),
+                    blob,
                 );
 
                 Some(symbol)
@@ -238,6 +299,94 @@ impl<'input> MainPlanVisitor<'input> {
         }
     }
 
+    /// Process a plan level advanced extension, merging repeated blocks into one symbol.
+    fn process_plan_advanced_extension(&mut self, ctx: &Advanced_extensionContext<'input>) {
+        if ctx.advanced_extension_detail_all().is_empty() {
+            return;
+        }
+        let existing = self
+            .type_visitor
+            .symbol_table()
+            .symbols()
+            .iter()
+            .find(|symbol| symbol.symbol_type() == SymbolType::AdvancedExtension)
+            .cloned();
+        let symbol = match existing {
+            Some(symbol) => symbol,
+            None => {
+                let token = ctx.start();
+                let location = token_to_location(&token);
+                let name = self
+                    .type_visitor
+                    .symbol_table()
+                    .get_unique_name("advanced_extension");
+                let blob = Some(Arc::new(Mutex::new(
+                    ::substrait::proto::extensions::AdvancedExtension::default(),
+                )) as Arc<Mutex<dyn Any + Send + Sync>>);
+                self.type_visitor.symbol_table_mut().define_symbol(
+                    name,
+                    location,
+                    SymbolType::AdvancedExtension,
+                    None,
+                    blob,
+                )
+            }
+        };
+
+        let errors = symbol
+            .with_blob::<::substrait::proto::extensions::AdvancedExtension, _, _>(|extension| {
+                add_advanced_extension_details(ctx, extension)
+            })
+            .unwrap_or_default();
+        for message in errors {
+            self.add_error(&ctx.start(), &message);
+        }
+    }
+
+    /// Process a parameters block, defining each parameter with its declared type.
+    ///
+    /// The bound values are built later by the RelationVisitor.  Without an explicit
+    /// declaration the type is taken from the value's suffix (as in `42_i64`).
+    fn process_parameters(&mut self, ctx: &ParametersContext<'input>) {
+        for binding_ctx in ctx.parameter_binding_all() {
+            let name = binding_ctx
+                .PARAMETER()
+                .map(|p| p.get_text())
+                .unwrap_or_default();
+            let location = token_to_location(&binding_ctx.start());
+            let symbol = match lookup_or_define_parameter(
+                self.type_visitor.symbol_table_mut(),
+                &name,
+                location,
+            ) {
+                Ok(symbol) => symbol,
+                Err(message) => {
+                    self.add_error(&binding_ctx.start(), &message);
+                    continue;
+                }
+            };
+
+            let type_text = binding_ctx
+                .literal_complex_type()
+                .map(|type_ctx| type_ctx.get_text())
+                .or_else(|| {
+                    let constant_ctx = binding_ctx.constant()?;
+                    constant_ctx
+                        .literal_basic_type()
+                        .map(|type_ctx| type_ctx.get_text())
+                        .or_else(|| constant_ctx.literal_complex_type().map(|t| t.get_text()))
+                });
+            if let Some(type_text) = type_text {
+                let parameter_type = self
+                    .type_visitor
+                    .text_to_type_proto(&*binding_ctx, &type_text);
+                symbol.with_blob::<ParameterData, _, _>(|data| {
+                    data.parameter_type = Some(parameter_type);
+                });
+            }
+        }
+    }
+
     /// Process named table details and add string symbols to the symbol table.
     fn process_named_table_detail(
         &mut self,
@@ -694,14 +843,18 @@ impl<'input> MainPlanVisitor<'input> {
         let token = ctx.start();
         let location = token_to_location(&token);
 
-        // Define the schema in the symbol table
-        // Symbol table accessed directly via base
+        // Define the schema in the symbol table.  As for plans loaded from binary the blob
+        // holds the schema as a named struct, which also records the names of nested fields.
+        let blob = Some(
+            Arc::new(Mutex::new(::substrait::proto::NamedStruct::default()))
+                as Arc<Mutex<dyn Any + Send + Sync>>,
+        );
         let symbol = self.type_visitor.symbol_table_mut().define_symbol(
             schema_name,
             location,
             SymbolType::Schema,
             None,
-            None,
+            blob,
         );
 
         // Process schema items
@@ -732,6 +885,18 @@ impl<'input> MainPlanVisitor<'input> {
         // Convert the type text to a Substrait Type protobuf
         let proto_type = self.type_visitor.text_to_type_proto(ctx, &type_text);
 
+        parent_schema.with_blob::<::substrait::proto::NamedStruct, _, _>(|named_struct| {
+            named_struct.names.push(name.clone());
+            named_struct
+                .names
+                .extend(self.type_visitor.nested_field_names(&type_text));
+            named_struct
+                .r#struct
+                .get_or_insert_with(Default::default)
+                .types
+                .push(proto_type.clone());
+        });
+
         // Store the Type protobuf in the blob
         let blob = Some(Arc::new(std::sync::Mutex::new(proto_type))
             as Arc<std::sync::Mutex<dyn std::any::Any + Send + Sync>>);
@@ -783,6 +948,17 @@ impl<'input> SubstraitPlanParserVisitor<'input> for MainPlanVisitor<'input> {
         // Process plan details
         println!("Visiting plan detail: {}", ctx.get_text());
 
+        // Advanced extensions directly under the plan belong to the plan itself
+        if let Some(extension_ctx) = ctx.advanced_extension() {
+            self.process_plan_advanced_extension(&extension_ctx);
+            return;
+        }
+
+        if let Some(parameters_ctx) = ctx.parameters() {
+            self.process_parameters(&parameters_ctx);
+            return;
+        }
+
         // Visit children to process nested elements
         self.visit_children(ctx);
     }
@@ -917,6 +1093,37 @@ impl<'input> SubstraitPlanParserVisitor<'input> for MainPlanVisitor<'input> {
         self.visit_children(ctx);
     }
 
+    fn visit_advanced_extension(&mut self, ctx: &Advanced_extensionContext<'input>) {
+        // Source level advanced extensions are kept on the source's named table or local
+        // files.  Plan level ones are handled in visit_plan_detail and relation level ones
+        // by the RelationVisitor.
+        let has_details = !ctx.advanced_extension_detail_all().is_empty();
+        if let Some(source_symbol) = self.current_source_scope().cloned().filter(|_| has_details) {
+            let errors = source_symbol
+                .with_blob::<::substrait::proto::read_rel::NamedTable, _, _>(|named_table| {
+                    let extension = named_table
+                        .advanced_extension
+                        .get_or_insert_with(Default::default);
+                    add_advanced_extension_details(ctx, extension)
+                })
+                .or_else(|| {
+                    source_symbol.with_blob::<::substrait::proto::read_rel::LocalFiles, _, _>(
+                        |local_files| {
+                            let extension = local_files
+                                .advanced_extension
+                                .get_or_insert_with(Default::default);
+                            add_advanced_extension_details(ctx, extension)
+                        },
+                    )
+                })
+                .unwrap_or_default();
+            for message in errors {
+                self.add_error(&ctx.start(), &message);
+            }
+        }
+        self.visit_children(ctx);
+    }
+
     // We delegate to the TypeVisitor for type-related nodes
     fn visit_literal_basic_type(&mut self, ctx: &Literal_basic_typeContext<'input>) {
         self.type_visitor.visit_literal_basic_type(ctx);
@@ -13,34 +13,45 @@ use antlr_rust::token::{GenericToken, Token};
 use crate::textplan::common::text_location::TextLocation;
 use crate::textplan::parser::antlr::substraitplanparservisitor::SubstraitPlanParserVisitor;
 use crate::textplan::parser::error_listener::ErrorListener;
-use crate::textplan::symbol_table::SymbolTable;
+use crate::textplan::symbol_table::{SymbolInfo, SymbolTable, SymbolType};
 
 // Module declarations
 mod base;
 mod main_visitor;
+mod pipeline_graph;
 mod pipeline_visitor;
 mod relation_visitor;
 mod subquery_visitor;
+mod type_check_visitor;
 mod type_visitor;
 
 // Re-exports
 pub use base::BasePlanVisitor;
 pub use main_visitor::MainPlanVisitor;
+pub use pipeline_graph::{PipelineEdge, PipelineGraph};
 pub use pipeline_visitor::PipelineVisitor;
 pub use relation_visitor::RelationVisitor;
 pub use subquery_visitor::SubqueryRelationVisitor;
+pub use type_check_visitor::TypeCheckVisitor;
 pub use type_visitor::TypeVisitor;
 
 /// Helper function to convert ANTLR token to TextLocation
 pub fn token_to_location<'a>(
     token: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
 ) -> TextLocation {
-    // Convert token position to an absolute position
-    // Use both line and column to create a unique position
-    // Position = (line * 10000) + column to ensure different lines have different positions
-    let position = (token.line as i32 * 10000) + (token.column as i32);
+    // The token's start is a byte offset into the parsed text
     let length = token.get_text().len() as i32;
-    TextLocation::new(position, length)
+    TextLocation::new(token.start as i32, length)
+}
+
+/// Helper function to convert the tokens spanning a parse tree node to a TextLocation
+pub fn tokens_to_location<'a>(
+    start: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+    stop: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+) -> TextLocation {
+    // The stop offset is inclusive
+    let length = (stop.stop + 1 - start.start).max(0) as i32;
+    TextLocation::new(start.start as i32, length)
 }
 
 /// Helper function to extract string content by removing quotes.
@@ -65,6 +76,100 @@ pub fn extract_from_string(s: &str) -> String {
     result
 }
 
+/// Helper function to add the details of an ADVANCED_EXTENSION block to an extension.
+///
+/// Each detail is written as `optimization "type_url" "payload";` or
+/// `enhancement "type_url" "payload";`, the payload being in the form read by
+/// [`parse_payload`](crate::textplan::common::advanced_extension::parse_payload).
+/// Returns the messages for any details that could not be added.
+pub fn add_advanced_extension_details(
+    ctx: &crate::textplan::parser::antlr::substraitplanparser::Advanced_extensionContext<'_>,
+    extension: &mut ::substrait::proto::extensions::AdvancedExtension,
+) -> Vec<String> {
+    use crate::textplan::common::advanced_extension::{
+        add_advanced_extension_detail, parse_payload,
+    };
+    use crate::textplan::common::string_utils::unescape_string;
+    use crate::textplan::parser::antlr::substraitplanparser::*;
+    use antlr_rust::tree::ParseTree;
+
+    let mut errors = Vec::new();
+    for detail_ctx in ctx.advanced_extension_detail_all() {
+        let kind = detail_ctx.id().map(|id| id.get_text()).unwrap_or_default();
+        let strings = detail_ctx.STRING_all();
+        let type_url = strings
+            .first()
+            .map(|url| unescape_string(&url.get_text()))
+            .unwrap_or_default();
+        let value = match strings.get(1) {
+            Some(payload) => match parse_payload(&type_url, &payload.get_text()) {
+                Ok(value) => value,
+                Err(message) => {
+                    errors.push(message);
+                    continue;
+                }
+            },
+            None => Vec::new(),
+        };
+        if let Err(message) = add_advanced_extension_detail(extension, &kind, type_url, value) {
+            errors.push(message);
+        }
+    }
+    errors
+}
+
+/// Helper function to find or define the symbol for a dynamic parameter such as `$1` or `$region`.
+///
+/// Numbered parameters use their number as the anchor while named parameters take the lowest
+/// anchor not yet in use.  The TypeVisitor defines every numbered parameter of a plan first so
+/// that named parameters never take their anchors. Returns an error if a numbered parameter's
+/// anchor already belongs to a named parameter.
+pub fn lookup_or_define_parameter(
+    symbol_table: &mut SymbolTable,
+    name: &str,
+    location: TextLocation,
+) -> Result<Arc<SymbolInfo>, String> {
+    use crate::textplan::common::structured_symbol_data::ParameterData;
+    use std::any::Any;
+    use std::sync::Mutex;
+
+    if let Some(symbol) = symbol_table.lookup_symbol_by_name(name) {
+        if symbol.symbol_type() == SymbolType::Parameter {
+            return Ok(symbol);
+        }
+    }
+
+    let used_anchors: Vec<u32> = symbol_table
+        .symbols()
+        .iter()
+        .filter(|symbol| symbol.symbol_type() == SymbolType::Parameter)
+        .filter_map(|symbol| symbol.with_blob::<ParameterData, _, _>(|data| data.anchor))
+        .collect();
+
+    let anchor = match name.trim_start_matches('$').parse::<u32>() {
+        Ok(anchor) if used_anchors.contains(&anchor) => {
+            return Err(format!(
+                "Parameter {} uses the same anchor as a named parameter",
+                name
+            ));
+        }
+        Ok(anchor) => anchor,
+        Err(_) => (1..)
+            .find(|anchor| !used_anchors.contains(anchor))
+            .unwrap_or_default(),
+    };
+
+    let blob =
+        Some(Arc::new(Mutex::new(ParameterData::new(anchor))) as Arc<Mutex<dyn Any + Send + Sync>>);
+    Ok(symbol_table.define_symbol(
+        name.to_string(),
+        location,
+        SymbolType::Parameter,
+        None,
+        blob,
+    ))
+}
+
 /// Helper function to safely apply a visitor to a parse tree node.
 ///
 /// This function handles the common pattern of applying a visitor to a parse tree node,
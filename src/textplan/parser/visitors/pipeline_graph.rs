@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Validation of the graph the pipelines of a plan connect relations into.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::textplan::common::text_location::TextLocation;
+use crate::textplan::symbol_table::{RelationType, SymbolInfo, SymbolTable, SymbolType};
+
+/// A link made by a pipeline arrow from a relation to the relation consuming it.
+#[derive(Debug, Clone)]
+pub struct PipelineEdge {
+    pub input: Arc<SymbolInfo>,
+    pub consumer: Arc<SymbolInfo>,
+    /// The location of the arrow.
+    pub location: TextLocation,
+}
+
+/// The relations connected by the pipelines of a plan.
+///
+/// The edges are collected as the pipelines are visited and validated once the whole plan
+/// (including which relations are subqueries) is known.
+#[derive(Debug, Default)]
+pub struct PipelineGraph {
+    edges: Vec<PipelineEdge>,
+}
+
+impl PipelineGraph {
+    /// Creates an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the link made by a pipeline arrow.
+    pub fn add_edge(
+        &mut self,
+        input: Arc<SymbolInfo>,
+        consumer: Arc<SymbolInfo>,
+        location: TextLocation,
+    ) {
+        self.edges.push(PipelineEdge {
+            input,
+            consumer,
+            location,
+        });
+    }
+
+    /// Checks that the relations and pipelines form a valid plan.
+    ///
+    /// Reports relations defined more than once, links repeated in several pipelines,
+    /// cycles, relations whose output is consumed more than once, relations with the wrong
+    /// number of inputs for their type and pipelines that end neither in a root nor in a
+    /// subquery.  Apart from duplicate definitions each problem is reported at the pipeline
+    /// arrow responsible for it.  Relations outside of every pipeline are warned about
+    /// where they are defined.
+    pub fn validate(&self, symbol_table: &SymbolTable) -> Vec<Diagnostic> {
+        let mut diagnostics = duplicate_definitions(symbol_table);
+
+        // Repeated links are reported once and otherwise ignored
+        let mut seen = HashSet::new();
+        let mut edges = Vec::new();
+        for edge in &self.edges {
+            if seen.insert((edge.input.name(), edge.consumer.name())) {
+                edges.push(edge);
+            } else {
+                diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::InvalidPlan,
+                    format!(
+                        "'{} -> {}' is already part of a pipeline",
+                        edge.input.name(),
+                        edge.consumer.name()
+                    ),
+                    edge.location,
+                ));
+            }
+        }
+
+        let mut inputs: HashMap<&str, Vec<&PipelineEdge>> = HashMap::new();
+        let mut consumers: HashMap<&str, Vec<&PipelineEdge>> = HashMap::new();
+        let mut relations: Vec<&Arc<SymbolInfo>> = Vec::new();
+        for &edge in &edges {
+            inputs.entry(edge.consumer.name()).or_default().push(edge);
+            consumers.entry(edge.input.name()).or_default().push(edge);
+            for relation in [&edge.input, &edge.consumer] {
+                if !relations.iter().any(|r| r.name() == relation.name()) {
+                    relations.push(relation);
+                }
+            }
+        }
+
+        diagnostics.extend(cycles(&relations, &consumers));
+
+        for relation in relations {
+            let name = relation.name();
+            let relation_inputs = inputs.get(name).map(Vec::as_slice).unwrap_or_default();
+            let relation_consumers = consumers.get(name).map(Vec::as_slice).unwrap_or_default();
+
+            if let Some(extra) = relation_consumers.get(1) {
+                diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::InvalidPlan,
+                    format!(
+                        "Relation '{}' already feeds '{}' and cannot also feed '{}'",
+                        name,
+                        relation_consumers[0].consumer.name(),
+                        extra.consumer.name()
+                    ),
+                    extra.location,
+                ));
+            }
+
+            if let Some(diagnostic) =
+                check_input_count(relation, relation_inputs, relation_consumers)
+            {
+                diagnostics.push(diagnostic);
+            }
+
+            // Reported for the end of each pipeline so the relations before it are covered too
+            if relation_consumers.is_empty() && !is_pipeline_end(relation) {
+                if let Some(last) = relation_inputs.last() {
+                    diagnostics.push(Diagnostic::error(
+                        DiagnosticCode::InvalidPlan,
+                        format!("Pipeline ends at '{}' instead of at a root", name),
+                        last.location,
+                    ));
+                }
+            }
+        }
+
+        diagnostics.extend(orphans(symbol_table, &inputs, &consumers));
+
+        diagnostics
+    }
+}
+
+/// Returns the type of a relation, if it was defined.
+fn relation_type(relation: &SymbolInfo) -> Option<RelationType> {
+    relation
+        .subtype::<RelationType>()
+        .filter(|relation_type| *relation_type != RelationType::Unknown)
+}
+
+/// Returns true if a pipeline may end at the relation.
+fn is_pipeline_end(relation: &SymbolInfo) -> bool {
+    // Relations that are not defined are reported elsewhere
+    relation.name() == "root"
+        || relation.parent_query_index() >= 0
+        || matches!(relation_type(relation), None | Some(RelationType::Root))
+}
+
+/// Returns the smallest and largest (if limited) number of inputs a type of relation takes.
+fn input_count_range(relation_type: RelationType) -> Option<(usize, Option<usize>)> {
+    match relation_type {
+        RelationType::Read | RelationType::ExtensionLeaf => Some((0, Some(0))),
+        RelationType::Project
+        | RelationType::Filter
+        | RelationType::Fetch
+        | RelationType::Aggregate
+        | RelationType::Sort
+        | RelationType::Exchange
+        | RelationType::Write
+        | RelationType::ExtensionSingle => Some((1, Some(1))),
+        RelationType::Join
+        | RelationType::Cross
+        | RelationType::HashJoin
+        | RelationType::MergeJoin => Some((2, Some(2))),
+        RelationType::Set => Some((2, None)),
+        RelationType::Unknown
+        | RelationType::Root
+        | RelationType::Ddl
+        | RelationType::ExtensionMulti => None,
+    }
+}
+
+/// Checks that a relation has as many inputs as its type takes.
+fn check_input_count(
+    relation: &SymbolInfo,
+    inputs: &[&PipelineEdge],
+    consumers: &[&PipelineEdge],
+) -> Option<Diagnostic> {
+    let relation_type = relation_type(relation)?;
+    let (min, max) = input_count_range(relation_type)?;
+    let count = inputs.len();
+    let expected = match max {
+        Some(0) => "no inputs".to_string(),
+        Some(1) if min == 1 => "one input".to_string(),
+        Some(max) if max == min => format!("{} inputs", max),
+        _ => format!("at least {} inputs", min),
+    };
+
+    let location = match max {
+        // The first arrow beyond those allowed
+        Some(max) if count > max => inputs[max].location,
+        // Missing inputs belong where the relation is used
+        _ if count < min => inputs
+            .last()
+            .or_else(|| consumers.first())
+            .map(|edge| edge.location)?,
+        _ => return None,
+    };
+    Some(Diagnostic::error(
+        DiagnosticCode::ArityMismatch,
+        format!(
+            "The {} relation '{}' takes {} but has {}",
+            relation_type.as_str(),
+            relation.name(),
+            expected,
+            count
+        ),
+        location,
+    ))
+}
+
+/// Reports the relations defined more than once.
+fn duplicate_definitions(symbol_table: &SymbolTable) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut first_definitions: HashMap<&str, &Arc<SymbolInfo>> = HashMap::new();
+    for symbol in symbol_table.symbols() {
+        if symbol.symbol_type() != SymbolType::Relation || relation_type(symbol).is_none() {
+            continue;
+        }
+        let Some(first) = first_definitions.get(symbol.name()) else {
+            first_definitions.insert(symbol.name(), symbol);
+            continue;
+        };
+        let (Some(location), Some(first_location)) = (text_location(symbol), text_location(first))
+        else {
+            continue;
+        };
+        diagnostics.push(
+            Diagnostic::error(
+                DiagnosticCode::DuplicateDefinition,
+                format!("Relation '{}' is defined more than once", symbol.name()),
+                location,
+            )
+            .with_related(first_location, "first defined here"),
+        );
+    }
+    diagnostics
+}
+
+/// Warns about the defined relations that no pipeline connects to a root or a subquery.
+fn orphans(
+    symbol_table: &SymbolTable,
+    inputs: &HashMap<&str, Vec<&PipelineEdge>>,
+    consumers: &HashMap<&str, Vec<&PipelineEdge>>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut reported = HashSet::new();
+    for symbol in symbol_table.symbols() {
+        if symbol.symbol_type() != SymbolType::Relation
+            || relation_type(symbol).is_none()
+            || is_pipeline_end(symbol)
+            || inputs.contains_key(symbol.name())
+            || consumers.contains_key(symbol.name())
+            || !reported.insert(symbol.name())
+        {
+            continue;
+        }
+        let Some(location) = text_location(symbol) else {
+            continue;
+        };
+        diagnostics.push(Diagnostic::warning(
+            DiagnosticCode::InvalidPlan,
+            format!("Relation '{}' is not part of any pipeline", symbol.name()),
+            location,
+        ));
+    }
+    diagnostics
+}
+
+fn text_location(symbol: &SymbolInfo) -> Option<TextLocation> {
+    symbol
+        .source_location()
+        .as_any()
+        .downcast_ref::<TextLocation>()
+        .copied()
+}
+
+/// Reports each cycle at the arrow that closes it.
+fn cycles(
+    relations: &[&Arc<SymbolInfo>],
+    consumers: &HashMap<&str, Vec<&PipelineEdge>>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut finished = HashSet::new();
+    for relation in relations {
+        let mut path = Vec::new();
+        find_cycles(
+            relation.name(),
+            consumers,
+            &mut path,
+            &mut finished,
+            &mut diagnostics,
+        );
+    }
+    diagnostics
+}
+
+/// Depth first search along the pipelines, `path` holding the relations being visited.
+fn find_cycles<'a>(
+    name: &'a str,
+    consumers: &HashMap<&'a str, Vec<&'a PipelineEdge>>,
+    path: &mut Vec<&'a str>,
+    finished: &mut HashSet<&'a str>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if finished.contains(name) {
+        return;
+    }
+    path.push(name);
+    for edge in consumers.get(name).into_iter().flatten() {
+        let consumer = edge.consumer.name();
+        if let Some(start) = path.iter().position(|visited| *visited == consumer) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(consumer);
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::Cycle,
+                format!("Pipelines form a cycle: {}", cycle.join(" -> ")),
+                edge.location,
+            ));
+        } else {
+            find_cycles(consumer, consumers, path, finished, diagnostics);
+        }
+    }
+    path.pop();
+    finished.insert(name);
+}
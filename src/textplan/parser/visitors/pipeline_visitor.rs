@@ -11,13 +11,14 @@ use antlr_rust::token::{GenericToken, Token};
 use antlr_rust::tree::{ParseTree, ParseTreeVisitor};
 use antlr_rust::TidExt;
 
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
 use crate::textplan::common::structured_symbol_data::RelationData;
 use crate::textplan::parser::antlr::substraitplanparser::*;
 use crate::textplan::parser::antlr::substraitplanparservisitor::SubstraitPlanParserVisitor;
 use crate::textplan::parser::error_listener::ErrorListener;
 use crate::textplan::symbol_table::{RelationType, SymbolInfo, SymbolTable, SymbolType};
 
-use super::{token_to_location, PlanVisitor};
+use super::{token_to_location, PipelineGraph, PlanVisitor};
 
 /// The PipelineVisitor processes pipeline definitions.
 ///
@@ -25,6 +26,7 @@ use super::{token_to_location, PlanVisitor};
 pub struct PipelineVisitor<'input> {
     symbol_table: SymbolTable,
     error_listener: Arc<ErrorListener>,
+    graph: PipelineGraph,
     _phantom: std::marker::PhantomData<&'input ()>,
 }
 
@@ -34,10 +36,20 @@ impl<'input> PipelineVisitor<'input> {
         Self {
             symbol_table,
             error_listener,
+            graph: PipelineGraph::new(),
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Reports the problems with the graph formed by the pipelines.
+    ///
+    /// Must be called after the subqueries are known as their pipelines end without a root.
+    pub fn validate_pipelines(&self) {
+        for diagnostic in self.graph.validate(&self.symbol_table) {
+            self.error_listener.add_diagnostic(diagnostic);
+        }
+    }
+
     /// Gets the symbol table.
     pub fn symbol_table(&self) -> SymbolTable {
         self.symbol_table.clone()
@@ -78,12 +90,27 @@ impl<'input> PipelineVisitor<'input> {
             return symbol;
         }
 
-        // Symbol doesn't exist - create a stub Relation with Unknown type
+        // Symbol doesn't exist - create a stub Relation with Unknown type so that the
+        // rest of the pipeline can still be connected.  Only the root may be left implied.
         println!(
             "  Creating stub Relation symbol '{}' (missing definition)",
             relation_name
         );
         let location = token_to_location(&ctx.start());
+        if relation_name != "root" {
+            let mut diagnostic = Diagnostic::error(
+                DiagnosticCode::InvalidPlan,
+                format!("Relation '{}' is not defined", relation_name),
+                location,
+            );
+            if let Some(suggestion) = self
+                .symbol_table
+                .suggest_symbol_name(relation_name, SymbolType::Relation)
+            {
+                diagnostic = diagnostic.with_suggestion(location, suggestion);
+            }
+            self.error_listener.add_diagnostic(diagnostic);
+        }
         let relation_data = RelationData::new_empty();
         let blob = Some(Arc::new(Mutex::new(relation_data)) as Arc<Mutex<dyn Any + Send + Sync>>);
 
@@ -174,13 +201,26 @@ impl<'input> SubstraitPlanParserVisitor<'input> for PipelineVisitor<'input> {
             println!("  Processing nested pipeline for '{}'", relation_name);
             self.visit_pipeline(&nested_pipeline);
             println!("  Finished nested pipeline for '{}'", relation_name);
+
+            // Remember the link made by the arrow so the whole graph can be validated
+            let input = nested_pipeline
+                .relation_ref()
+                .and_then(|relation_ref| relation_ref.id(0))
+                .and_then(|id| self.symbol_table.lookup_symbol_by_name(&id.get_text()));
+            if let (Some(input), Some(arrow)) = (input, ctx.ARROW()) {
+                self.graph
+                    .add_edge(input, symbol.clone(), token_to_location(&arrow.symbol));
+            }
         }
 
         // Get the RelationData for this symbol
         let blob_lock = if let Some(blob) = &symbol.blob {
             blob
         } else {
-            eprintln!("Warning: Symbol '{}' has no blob", relation_name);
+            self.add_error(
+                &ctx.start(),
+                &format!("'{}' is not a relation", relation_name),
+            );
             return;
         };
 
@@ -195,16 +235,16 @@ impl<'input> SubstraitPlanParserVisitor<'input> for PipelineVisitor<'input> {
         let relation_data = if let Some(data) = blob_data.downcast_mut::<RelationData>() {
             data
         } else {
-            eprintln!("Warning: Blob is not RelationData for '{}'", relation_name);
+            self.add_error(
+                &ctx.start(),
+                &format!("'{}' is not a relation", relation_name),
+            );
             return;
         };
 
-        // Check for accidental cross-pipeline use
+        // Check for accidental cross-pipeline use, which validate_pipelines reports as a
+        // relation with too many inputs or consumers
         if relation_data.continuing_pipeline.is_some() {
-            eprintln!(
-                "Error: Relation {} is already a non-terminating participant in a pipeline",
-                relation_name
-            );
             return;
         }
 
@@ -298,7 +338,8 @@ impl<'input> SubstraitPlanParserVisitor<'input> for PipelineVisitor<'input> {
         // Connect to the left symbol
         if let Some(left) = left_symbol {
             // Determine the relation category for pipeline connections:
-            // - Binary relations (Join, Cross, etc.) use new_pipelines for multiple inputs
+            // - Binary and N-ary relations (Join, Set, ExtensionMulti, etc.) use new_pipelines
+            //   for multiple inputs
             // - Root/terminal relations (Fetch, etc.) use new_pipelines to be identified as terminals
             // - Unary relations (Filter, Project, etc.) use continuing_pipeline for single input
 
@@ -317,6 +358,7 @@ impl<'input> SubstraitPlanParserVisitor<'input> for PipelineVisitor<'input> {
                     Some(::substrait::proto::rel::RelType::MergeJoin(_)) => "binary",
                     Some(::substrait::proto::rel::RelType::Fetch(_)) => "unary",
                     Some(::substrait::proto::rel::RelType::ExtensionSingle(_)) => "unary",
+                    Some(::substrait::proto::rel::RelType::ExtensionMulti(_)) => "binary",
                     Some(::substrait::proto::rel::RelType::ExtensionLeaf(_)) => "terminal",
                     _ => "unary",
                 }
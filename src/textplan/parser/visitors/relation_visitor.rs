@@ -11,15 +11,30 @@ use antlr_rust::token::{GenericToken, Token};
 use antlr_rust::tree::{ParseTree, ParseTreeVisitor};
 use antlr_rust::TidExt;
 
-use crate::textplan::common::structured_symbol_data::RelationData;
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode, Severity};
+use crate::textplan::common::extension_registry::{
+    CallArgument, ExtensionRegistry, FunctionKind, ResolvedFunction,
+};
+use crate::textplan::common::projection_mask;
+use crate::textplan::common::relation_schema;
+use crate::textplan::common::string_utils;
+use crate::textplan::common::structured_symbol_data::{
+    ExtensionSpaceData, FunctionData, ParameterData, RelationData,
+};
+use crate::textplan::common::temporal;
 use crate::textplan::common::text_location::TextLocation;
+use crate::textplan::common::type_utils;
 use crate::textplan::parser::antlr::substraitplanparser::*;
 use crate::textplan::parser::antlr::substraitplanparservisitor::SubstraitPlanParserVisitor;
 use crate::textplan::parser::error_listener::ErrorListener;
+use crate::textplan::printer::expression_printer::ExpressionPrinter;
 use crate::textplan::symbol_table::{RelationType, SymbolInfo, SymbolTable, SymbolType};
 use ::substrait::proto::rel::RelType;
 
-use super::{token_to_location, PlanVisitor, TypeVisitor};
+use super::{
+    add_advanced_extension_details, lookup_or_define_parameter, token_to_location, PlanVisitor,
+    TypeVisitor,
+};
 
 /// The RelationVisitor processes relation definitions and expressions.
 ///
@@ -31,6 +46,9 @@ pub struct RelationVisitor<'input> {
     prescan_mode: bool,
     processing_emit: bool, // Track if we're currently processing an emit clause
     subquery_index_counters: std::collections::HashMap<String, i32>, // Track subquery indices per parent
+    extension_registry: Arc<ExtensionRegistry>, // Used to declare functions called by their plain name
+    synthesized_functions: std::collections::HashSet<String>, // Aliases of the functions declared from calls
+    unresolved_columns: Vec<(Arc<SymbolInfo>, String, TextLocation)>, // Columns not found while building expressions
     _phantom: std::marker::PhantomData<&'input ()>,
 }
 
@@ -60,6 +78,127 @@ fn parse_sort_direction(text: &str) -> i32 {
     }
 }
 
+/// How a FILTER detail is applied (only read relations accept best effort filters).
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FilterBehavior {
+    Mandatory,
+    BestEffort,
+}
+
+/// Helper function to parse the optional filter behavior preceding FILTER.
+/// Accepts spellings such as "best_effort", "best-effort" and "BEST EFFORT".
+fn parse_filter_behavior(text: &str) -> Option<FilterBehavior> {
+    let normalized = text
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>();
+
+    match normalized.as_str() {
+        "" | "mandatory" => Some(FilterBehavior::Mandatory),
+        "besteffort" => Some(FilterBehavior::BestEffort),
+        _ => None,
+    }
+}
+
+/// Helper function to parse the behavior following ON_FAILURE in a cast.
+fn parse_cast_failure_behavior(
+    text: &str,
+) -> Option<::substrait::proto::expression::cast::FailureBehavior> {
+    use ::substrait::proto::expression::cast::FailureBehavior;
+
+    let normalized = text
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>();
+
+    match normalized.as_str() {
+        "unspecified" => Some(FailureBehavior::Unspecified),
+        "returnnull" => Some(FailureBehavior::ReturnNull),
+        "throwexception" => Some(FailureBehavior::ThrowException),
+        _ => None,
+    }
+}
+
+/// Helper function to apply a single COMMON hint (e.g. `row_count 1000;`) to a RelCommon hint.
+fn apply_common_hint(
+    hint: &mut ::substrait::proto::rel_common::Hint,
+    name: &str,
+    values: &[String],
+) -> Result<(), String> {
+    use ::substrait::proto::rel_common::hint::{
+        LoadedComputation, RuntimeConstraint, SavedComputation, Stats,
+    };
+
+    let parse_f64 = |value: &str| {
+        value
+            .parse::<f64>()
+            .map_err(|_| format!("Expected a number for '{}', found '{}'", name, value))
+    };
+    let parse_computation = |values: &[String]| -> Result<(i32, i32), String> {
+        if values.is_empty() || values.len() > 2 {
+            return Err(format!(
+                "'{}' expects a computation id and an optional computation type",
+                name
+            ));
+        }
+        let id = values[0]
+            .parse::<i32>()
+            .map_err(|_| format!("Expected a computation id, found '{}'", values[0]))?;
+        let computation_type = match values.get(1) {
+            Some(text) => crate::textplan::common::rel_common::parse_computation_type(text)
+                .ok_or_else(|| {
+                    format!(
+                        "Unrecognized computation type '{}', expected hash_table, bloom_filter or unknown",
+                        text
+                    )
+                })?,
+            None => ::substrait::proto::rel_common::hint::ComputationType::Unspecified,
+        };
+        Ok((id, computation_type as i32))
+    };
+    let single_value = || match values {
+        [value] => Ok(value.as_str()),
+        _ => Err(format!("'{}' expects exactly one value", name)),
+    };
+
+    match name.to_lowercase().as_str() {
+        "alias" => hint.alias = single_value()?.to_string(),
+        "row_count" => {
+            let row_count = parse_f64(single_value()?)?;
+            hint.stats.get_or_insert_with(Stats::default).row_count = row_count;
+        }
+        "record_size" => {
+            let record_size = parse_f64(single_value()?)?;
+            hint.stats.get_or_insert_with(Stats::default).record_size = record_size;
+        }
+        "constraint" => {
+            if !values.is_empty() {
+                return Err("'constraint' does not take any values".to_string());
+            }
+            hint.constraint = Some(RuntimeConstraint::default());
+        }
+        "output_names" => hint.output_names.extend(values.iter().cloned()),
+        "saved_computation" => {
+            let (computation_id, computation_type) = parse_computation(values)?;
+            hint.saved_computations.push(SavedComputation {
+                computation_id,
+                r#type: computation_type,
+            });
+        }
+        "loaded_computation" => {
+            let (computation_id_reference, computation_type) = parse_computation(values)?;
+            hint.loaded_computations.push(LoadedComputation {
+                computation_id_reference,
+                r#type: computation_type,
+            });
+        }
+        _ => return Err(format!("Unrecognized common hint '{}'", name)),
+    }
+    Ok(())
+}
+
 impl<'input> RelationVisitor<'input> {
     /// Creates a new RelationVisitor.
     pub fn new(symbol_table: SymbolTable, error_listener: Arc<ErrorListener>) -> Self {
@@ -70,6 +209,9 @@ impl<'input> RelationVisitor<'input> {
             prescan_mode: false,
             processing_emit: false,
             subquery_index_counters: std::collections::HashMap::new(),
+            extension_registry: Arc::new(ExtensionRegistry::new()),
+            synthesized_functions: std::collections::HashSet::new(),
+            unresolved_columns: Vec::new(),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -79,6 +221,11 @@ impl<'input> RelationVisitor<'input> {
         self.prescan_mode = prescan;
     }
 
+    /// Sets the registry used to pick the implementation of undeclared functions.
+    pub fn set_extension_registry(&mut self, registry: Arc<ExtensionRegistry>) {
+        self.extension_registry = registry;
+    }
+
     /// Gets and increments the next subquery index for a given parent relation.
     fn get_next_subquery_index(&mut self, parent_name: &str) -> i32 {
         let counter = self
@@ -115,6 +262,126 @@ impl<'input> RelationVisitor<'input> {
         self.error_listener.add_error(message.to_string(), location);
     }
 
+    /// Adds an error with a specific diagnostic code to the error listener.
+    pub fn add_error_with_code<'a>(
+        &self,
+        token: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+        code: DiagnosticCode,
+        message: &str,
+    ) {
+        let location = token_to_location(token);
+        self.error_listener
+            .add_diagnostic(Diagnostic::error(code, message, location));
+    }
+
+    /// Remembers a column that could not be found in the current relation so that it can
+    /// be reported once the enclosing queries of subqueries are known.
+    fn note_unresolved_column<'a>(
+        &mut self,
+        token: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+        column_name: &str,
+    ) {
+        if let Some(relation_symbol) = self.current_relation_scope().cloned() {
+            self.unresolved_columns.push((
+                relation_symbol,
+                column_name.to_string(),
+                token_to_location(token),
+            ));
+        } else {
+            self.add_error(
+                token,
+                &format!("Column '{}' is used outside of a relation", column_name),
+            );
+        }
+    }
+
+    /// Reports the columns that could not be found while building expressions.
+    ///
+    /// Must be called after the subqueries have been connected to their enclosing query
+    /// as columns of a subquery may also come from the enclosing query.
+    pub fn report_unresolved_columns(&mut self) {
+        let unresolved_columns = std::mem::take(&mut self.unresolved_columns);
+        let previous_scope = self.current_relation_scope.clone();
+        let mut reported = std::collections::HashSet::new();
+        for (relation_symbol, column_name, location) in unresolved_columns {
+            // Expressions may be built more than once
+            if !reported.insert(location) {
+                continue;
+            }
+            let in_subquery = relation_symbol.parent_query_index() >= 0
+                || relation_symbol
+                    .with_blob::<RelationData, _, _>(|relation_data| {
+                        relation_data
+                            .pipeline_start
+                            .as_ref()
+                            .is_some_and(|start| start.parent_query_index() >= 0)
+                    })
+                    .unwrap_or(false);
+            if in_subquery {
+                self.set_current_relation_scope(Some(relation_symbol.clone()));
+                if self
+                    .lookup_field_index_in_parent_relation(&column_name)
+                    .is_some()
+                {
+                    continue;
+                }
+            }
+            let suggestion = self.suggest_column_name(&relation_symbol, &column_name);
+            self.report_unresolved_name(
+                Severity::Error,
+                DiagnosticCode::UnknownColumn,
+                location,
+                format!(
+                    "Column '{}' is not in the input of relation '{}'",
+                    column_name,
+                    relation_symbol.name()
+                ),
+                suggestion,
+            );
+        }
+        self.set_current_relation_scope(previous_scope);
+    }
+
+    /// Suggests the input column of a relation a misspelled column name was likely meant
+    /// to be, keeping any schema qualifier the name was written with.
+    fn suggest_column_name(
+        &self,
+        relation_symbol: &SymbolInfo,
+        column_name: &str,
+    ) -> Option<String> {
+        let (qualifier, field_name) = match column_name.rfind('.') {
+            Some(dot_pos) => column_name.split_at(dot_pos + 1),
+            None => ("", column_name),
+        };
+        let names = relation_schema::relation_input_schema(&self.symbol_table, relation_symbol)
+            .map(|schema| schema.names())
+            .unwrap_or_default();
+        let suggestion = if names.is_empty() {
+            self.symbol_table
+                .suggest_symbol_name(field_name, SymbolType::SchemaColumn)
+        } else {
+            string_utils::closest_match(field_name, names.iter().map(String::as_str))
+                .map(str::to_string)
+        };
+        suggestion.map(|name| format!("{}{}", qualifier, name))
+    }
+
+    /// Reports a name that does not resolve, along with the name that was likely meant.
+    fn report_unresolved_name(
+        &self,
+        severity: Severity,
+        code: DiagnosticCode,
+        location: TextLocation,
+        message: String,
+        suggestion: Option<String>,
+    ) {
+        let mut diagnostic = Diagnostic::new(severity, code, message, location);
+        if let Some(suggestion) = suggestion {
+            diagnostic = diagnostic.with_suggestion(location, suggestion);
+        }
+        self.error_listener.add_diagnostic(diagnostic);
+    }
+
     /// Gets the current relation scope, if any.
     pub fn current_relation_scope(&self) -> Option<&Arc<SymbolInfo>> {
         self.current_relation_scope.as_ref()
@@ -390,24 +657,27 @@ impl<'input> RelationVisitor<'input> {
                                                 ),
                                             ) = &ref_segment.reference_type
                                             {
-                                                // Simple field selection
-                                                let field_index = struct_field.field as usize;
-                                                if field_index < relation_data.field_references.len() {
-                                                    let field_symbol = relation_data.field_references
-                                                        [field_index]
-                                                        .clone();
-                                                    println!(
-                                                        "        Expr {}: field selection -> will add '{}'",
-                                                        expression_number, field_symbol.name()
-                                                    );
-                                                    infos.push(ExprInfo::FieldSelection(field_symbol));
-                                                } else {
-                                                    println!(
-                                                        "        Expr {}: field index {} out of range",
-                                                        expression_number, field_index
-                                                    );
+                                                // Nested references such as addr.1 produce new values
+                                                if struct_field.child.is_none() {
+                                                    // Simple field selection
+                                                    let field_index = struct_field.field as usize;
+                                                    if field_index < relation_data.field_references.len() {
+                                                        let field_symbol = relation_data.field_references
+                                                            [field_index]
+                                                            .clone();
+                                                        println!(
+                                                            "        Expr {}: field selection -> will add '{}'",
+                                                            expression_number, field_symbol.name()
+                                                        );
+                                                        infos.push(ExprInfo::FieldSelection(field_symbol));
+                                                    } else {
+                                                        println!(
+                                                            "        Expr {}: field index {} out of range",
+                                                            expression_number, field_index
+                                                        );
+                                                    }
+                                                    continue;
                                                 }
-                                                continue;
                                             }
                                         }
                                     }
@@ -755,6 +1025,14 @@ impl<'input> RelationVisitor<'input> {
                 println!("  Building set predicate subquery expression");
                 self.build_set_predicate_subquery(ctx)
             }
+            ExpressionContextAll::ExpressionParameterContext(ctx) => {
+                println!("  Building dynamic parameter expression");
+                self.build_dynamic_parameter(ctx)
+            }
+            ExpressionContextAll::ExpressionMaskContext(ctx) => {
+                println!("  Building masked reference expression");
+                self.build_mask_expression(ctx)
+            }
             _ => {
                 println!("  Building unknown expression type (placeholder)");
                 ::substrait::proto::Expression {
@@ -786,13 +1064,37 @@ impl<'input> RelationVisitor<'input> {
         println!("    Column reference: {}", column_name);
 
         // Check if this is an outer reference (from a parent scope)
-        let (field_index, steps_out) = self.lookup_field_with_scope(&column_name);
+        let (mut field_index, mut steps_out) = self.lookup_field_with_scope(&column_name);
+
+        // addr.city reads like a column qualified by its schema but may select a field of
+        // the struct column addr instead
+        let mut leading_field = None;
+        if field_index.is_none() {
+            if let Some((base_name, field_name)) = column_name.rsplit_once('.') {
+                let (base_index, base_steps_out) = self.lookup_field_with_scope(base_name);
+                if base_index.is_some() {
+                    (field_index, steps_out) = (base_index, base_steps_out);
+                    leading_field = Some(field_name.to_string());
+                }
+            }
+        }
+        let field_index = field_index.unwrap_or_else(|| {
+            self.note_unresolved_column(&ctx.start(), &column_name);
+            0
+        });
 
         println!(
             "      -> field index: {}, steps_out: {}",
             field_index, steps_out
         );
 
+        // Nested segments such as addr.city, tags[0] or attrs["k"] hang off the column's
+        // segment.  Columns of an enclosing query are not looked into.
+        let column = (steps_out == 0)
+            .then(|| self.input_column(field_index))
+            .flatten();
+        let child = self.build_reference_segments(ctx, column, leading_field);
+
         // Create the appropriate root_type based on whether this is an outer reference
         let root_type = if steps_out > 0 {
             Some(
@@ -818,7 +1120,7 @@ impl<'input> RelationVisitor<'input> {
                             reference_type: Some(::substrait::proto::expression::reference_segment::ReferenceType::StructField(Box::new(
                                 ::substrait::proto::expression::reference_segment::StructField {
                                     field: field_index as i32,
-                                    child: None,
+                                    child,
                                 }
                             ))),
                         }
@@ -829,6 +1131,414 @@ impl<'input> RelationVisitor<'input> {
         }
     }
 
+    /// Build the chain of segments that drill into a nested column.
+    ///
+    /// `.N` selects a struct field by position, `.name` a struct field by name, `[N]` a
+    /// list element and `[constant]` a map key.  When the column's type is known it decides
+    /// between list and map access and supplies the map key type; otherwise an unsuffixed
+    /// number selects a list element.  `leading_field` is a struct field selected by name
+    /// before the segments, as the `city` of `addr.city`.
+    fn build_reference_segments(
+        &mut self,
+        ctx: &ExpressionColumnContext<'input>,
+        column: Option<relation_schema::SchemaField>,
+        leading_field: Option<String>,
+    ) -> Option<Box<::substrait::proto::expression::ReferenceSegment>> {
+        use ::substrait::proto::expression::reference_segment::{
+            self, ListElement, MapKey, ReferenceType,
+        };
+        use ::substrait::proto::r#type::Kind;
+
+        let segment_ctxs = ctx.reference_segment_all();
+        if segment_ctxs.is_empty() && leading_field.is_none() {
+            return None;
+        }
+
+        // The names of the struct fields nested in the current value, depth first
+        let mut current_names = column
+            .as_ref()
+            .map(|column| column.depth_first_nested_names())
+            .unwrap_or_default();
+        let mut current_type = column.and_then(|column| column.r#type);
+        let mut segments: Vec<ReferenceType> = Vec::new();
+        if let Some(field_name) = leading_field {
+            match Self::struct_field_by_name(current_type.take(), &current_names, &field_name) {
+                Ok((field, field_type, names)) => {
+                    current_type = field_type;
+                    current_names = names;
+                    segments.push(ReferenceType::StructField(Box::new(
+                        reference_segment::StructField { field, child: None },
+                    )));
+                }
+                Err(message) => {
+                    self.add_error(&ctx.start(), &message);
+                    return None;
+                }
+            }
+        }
+        for segment_ctx in &segment_ctxs {
+            if let Some(id_ctx) = segment_ctx.id() {
+                let field_name = id_ctx.get_text();
+                match Self::struct_field_by_name(current_type.take(), &current_names, &field_name) {
+                    Ok((field, field_type, names)) => {
+                        current_type = field_type;
+                        current_names = names;
+                        segments.push(ReferenceType::StructField(Box::new(
+                            reference_segment::StructField { field, child: None },
+                        )));
+                    }
+                    Err(message) => {
+                        self.add_error(&segment_ctx.start(), &message);
+                        return None;
+                    }
+                }
+            } else if let Some(number_token) = segment_ctx.NUMBER() {
+                // The lexer reads .0.1 as a period followed by the number 0.1
+                for part in number_token.get_text().split('.') {
+                    let Ok(field) = part.parse::<i32>() else {
+                        self.add_error(
+                            &segment_ctx.start(),
+                            &format!("Invalid struct field position '{}'", part),
+                        );
+                        return None;
+                    };
+                    current_type = match current_type.take().and_then(|t| t.kind) {
+                        Some(Kind::Struct(struct_type)) => {
+                            current_names =
+                                relation_schema::struct_field_names(&struct_type, &current_names)
+                                    .and_then(|mut fields| {
+                                        (field >= 0 && (field as usize) < fields.len())
+                                            .then(|| fields.swap_remove(field as usize).1)
+                                    })
+                                    .unwrap_or_default();
+                            match struct_type.types.get(field as usize) {
+                                Some(field_type) if field >= 0 => Some(field_type.clone()),
+                                _ => {
+                                    self.add_error(
+                                        &segment_ctx.start(),
+                                        &format!(
+                                            "Struct field {} is out of range for a struct with {} fields",
+                                            field,
+                                            struct_type.types.len()
+                                        ),
+                                    );
+                                    return None;
+                                }
+                            }
+                        }
+                        Some(kind) => {
+                            self.add_error(
+                                &segment_ctx.start(),
+                                &format!(
+                                    "Cannot select field {} of a {} value",
+                                    field,
+                                    type_utils::type_kind_name(&::substrait::proto::Type {
+                                        kind: Some(kind)
+                                    })
+                                ),
+                            );
+                            return None;
+                        }
+                        None => None,
+                    };
+                    segments.push(ReferenceType::StructField(Box::new(
+                        reference_segment::StructField { field, child: None },
+                    )));
+                }
+            } else if let Some(constant_ctx) = segment_ctx.constant() {
+                let is_plain_number =
+                    constant_ctx.NUMBER().is_some() && constant_ctx.literal_basic_type().is_none();
+                match current_type.take().and_then(|t| t.kind) {
+                    Some(Kind::Map(map_type)) => {
+                        let map_key = self.build_literal(&constant_ctx, map_type.key.as_deref());
+                        let key_name_count = map_type
+                            .key
+                            .as_deref()
+                            .map_or(0, relation_schema::nested_name_count);
+                        current_names =
+                            current_names.split_off(key_name_count.min(current_names.len()));
+                        current_type = map_type.value.map(|value_type| *value_type);
+                        segments.push(ReferenceType::MapKey(Box::new(MapKey {
+                            map_key: Some(map_key),
+                            child: None,
+                        })));
+                    }
+                    Some(Kind::List(list_type)) if is_plain_number => {
+                        let offset = self.parse_list_offset(&constant_ctx)?;
+                        current_type = list_type.r#type.map(|element_type| *element_type);
+                        segments.push(ReferenceType::ListElement(Box::new(ListElement {
+                            offset,
+                            child: None,
+                        })));
+                    }
+                    Some(Kind::List(_)) => {
+                        self.add_error(
+                            &segment_ctx.start(),
+                            "List elements must be selected by a number",
+                        );
+                        return None;
+                    }
+                    Some(kind) => {
+                        self.add_error(
+                            &segment_ctx.start(),
+                            &format!(
+                                "Cannot index into a {} value",
+                                type_utils::type_kind_name(&::substrait::proto::Type {
+                                    kind: Some(kind)
+                                })
+                            ),
+                        );
+                        return None;
+                    }
+                    None if is_plain_number => {
+                        let offset = self.parse_list_offset(&constant_ctx)?;
+                        segments.push(ReferenceType::ListElement(Box::new(ListElement {
+                            offset,
+                            child: None,
+                        })));
+                    }
+                    None => {
+                        let map_key = self.build_literal(&constant_ctx, None);
+                        segments.push(ReferenceType::MapKey(Box::new(MapKey {
+                            map_key: Some(map_key),
+                            child: None,
+                        })));
+                    }
+                }
+            }
+        }
+
+        // Link the segments from the innermost outward
+        let mut child: Option<Box<::substrait::proto::expression::ReferenceSegment>> = None;
+        for mut segment in segments.into_iter().rev() {
+            match &mut segment {
+                ReferenceType::StructField(s) => s.child = child,
+                ReferenceType::ListElement(l) => l.child = child,
+                ReferenceType::MapKey(m) => m.child = child,
+            }
+            child = Some(Box::new(::substrait::proto::expression::ReferenceSegment {
+                reference_type: Some(segment),
+            }));
+        }
+        child
+    }
+
+    /// Parse the offset of a list element segment such as `[2]`.
+    fn parse_list_offset(&self, constant_ctx: &Rc<ConstantContextAll<'input>>) -> Option<i32> {
+        let text = constant_ctx.get_text();
+        match text.parse::<i32>() {
+            Ok(offset) => Some(offset),
+            Err(_) => {
+                self.add_error(
+                    &constant_ctx.start(),
+                    &format!("Invalid list element offset '{}'", text),
+                );
+                None
+            }
+        }
+    }
+
+    /// Build a masked reference such as `mask{addr{0}, tags[1:3]}` that selects parts of
+    /// the input record.
+    fn build_mask_expression(
+        &mut self,
+        ctx: &ExpressionMaskContext<'input>,
+    ) -> ::substrait::proto::Expression {
+        use ::substrait::proto::expression::field_reference::{ReferenceType, RootType};
+        use ::substrait::proto::expression::mask_expression::{StructItem, StructSelect};
+
+        let mut struct_items = Vec::new();
+        for mask_item_ctx in ctx.mask_item_all() {
+            let column_name = mask_item_ctx
+                .column_name()
+                .map(|c| c.get_text())
+                .unwrap_or_default();
+            let field = self.lookup_field_index(&column_name).unwrap_or_else(|| {
+                self.note_unresolved_column(&mask_item_ctx.start(), &column_name);
+                0
+            }) as i32;
+            let child = mask_item_ctx
+                .mask_select()
+                .and_then(|select_ctx| self.build_mask_select(&select_ctx));
+            struct_items.push(StructItem { field, child });
+        }
+
+        ::substrait::proto::Expression {
+            rex_type: Some(::substrait::proto::expression::RexType::Selection(
+                Box::new(::substrait::proto::expression::FieldReference {
+                    reference_type: Some(ReferenceType::MaskedReference(
+                        ::substrait::proto::expression::MaskExpression {
+                            select: Some(StructSelect { struct_items }),
+                            maintain_singular_struct: false,
+                        },
+                    )),
+                    root_type: Some(RootType::RootReference(
+                        ::substrait::proto::expression::field_reference::RootReference {},
+                    )),
+                }),
+            )),
+        }
+    }
+
+    /// Build the nested selection of a projection mask item, e.g. the `{0, 2}` in `addr{0, 2}`.
+    fn build_mask_select(
+        &self,
+        ctx: &Rc<Mask_selectContextAll<'input>>,
+    ) -> Option<::substrait::proto::expression::mask_expression::Select> {
+        use ::substrait::proto::expression::mask_expression::{
+            select, ListSelect, Select, StructItem, StructSelect,
+        };
+
+        let select_type = match ctx.as_ref() {
+            Mask_selectContextAll::MaskStructSelectContext(struct_ctx) => {
+                let mut struct_items = Vec::new();
+                for item_ctx in struct_ctx.mask_struct_item_all() {
+                    let field = self.parse_mask_position(
+                        item_ctx.NUMBER().map(|token| token.get_text()),
+                        &item_ctx.start(),
+                    )?;
+                    let child = item_ctx
+                        .mask_select()
+                        .and_then(|select_ctx| self.build_mask_select(&select_ctx));
+                    struct_items.push(StructItem { field, child });
+                }
+                select::Type::Struct(StructSelect { struct_items })
+            }
+            Mask_selectContextAll::MaskListSelectContext(list_ctx) => {
+                let mut selection = Vec::new();
+                for item_ctx in list_ctx.mask_list_item_all() {
+                    let start = self.parse_mask_position(
+                        item_ctx.NUMBER(0).map(|token| token.get_text()),
+                        &item_ctx.start(),
+                    )?;
+                    match item_ctx.NUMBER(1) {
+                        Some(end_token) => {
+                            let end = self.parse_mask_position(
+                                Some(end_token.get_text()),
+                                &item_ctx.start(),
+                            )?;
+                            selection.push(projection_mask::list_slice(start, end));
+                        }
+                        None => selection.push(projection_mask::list_element(start)),
+                    }
+                }
+                let child = list_ctx
+                    .mask_select()
+                    .and_then(|select_ctx| self.build_mask_select(&select_ctx));
+                select::Type::List(Box::new(ListSelect {
+                    selection,
+                    child: child.map(Box::new),
+                }))
+            }
+            Mask_selectContextAll::MaskMapSelectContext(map_ctx) => {
+                let key = map_ctx
+                    .STRING()
+                    .map(|token| string_utils::unescape_string(&token.get_text()))
+                    .unwrap_or_default();
+                let child = map_ctx
+                    .mask_select()
+                    .and_then(|select_ctx| self.build_mask_select(&select_ctx));
+                select::Type::Map(Box::new(projection_mask::map_key_select(
+                    key,
+                    map_ctx.EXPRESSION().is_some(),
+                    child,
+                )))
+            }
+            _ => return None,
+        };
+        Some(Select {
+            r#type: Some(select_type),
+        })
+    }
+
+    /// Parse a struct field position or list index of a projection mask.
+    fn parse_mask_position<'a>(
+        &self,
+        text: Option<String>,
+        start: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+    ) -> Option<i32> {
+        let text = text.unwrap_or_default();
+        match text.parse::<i32>() {
+            Ok(position) => Some(position),
+            Err(_) => {
+                self.add_error(
+                    start,
+                    &format!("Invalid projection mask position '{}'", text),
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns the column at a position of the current relation's input, with its type and
+    /// the names of its nested struct fields, if it comes from a schema.
+    fn input_column(&self, field_index: usize) -> Option<relation_schema::SchemaField> {
+        let relation_symbol = self.current_relation_scope()?;
+        let field_symbol = relation_symbol
+            .with_blob::<RelationData, _, _>(|relation_data| {
+                relation_data
+                    .field_references
+                    .iter()
+                    .chain(&relation_data.generated_field_references)
+                    .nth(field_index)
+                    .cloned()
+            })
+            .flatten()?;
+        if field_symbol.symbol_type() != SymbolType::SchemaColumn {
+            return None;
+        }
+        let schema_symbol = field_symbol.schema()?;
+        relation_schema::schema_definition(&self.symbol_table, &schema_symbol)
+            .fields
+            .into_iter()
+            .find(|field| field.name == field_symbol.name())
+    }
+
+    /// Selects a field of a struct value by name, returning the field's position, type and
+    /// the names nested within it.
+    fn struct_field_by_name(
+        value_type: Option<::substrait::proto::Type>,
+        nested_names: &[String],
+        field_name: &str,
+    ) -> Result<(i32, Option<::substrait::proto::Type>, Vec<String>), String> {
+        use ::substrait::proto::r#type::Kind;
+
+        match value_type.and_then(|value_type| value_type.kind) {
+            Some(Kind::Struct(struct_type)) => {
+                let Some(mut fields) =
+                    relation_schema::struct_field_names(&struct_type, nested_names)
+                else {
+                    return Err(format!(
+                        "Cannot select field '{}' of a struct whose field names are unknown",
+                        field_name
+                    ));
+                };
+                let Some(position) = fields.iter().position(|(name, _)| name == field_name) else {
+                    let names: Vec<&str> = fields.iter().map(|(name, _)| name.as_str()).collect();
+                    return Err(format!(
+                        "The struct has no field named '{}' (its fields are {})",
+                        field_name,
+                        names.join(", ")
+                    ));
+                };
+                Ok((
+                    position as i32,
+                    struct_type.types.get(position).cloned(),
+                    fields.swap_remove(position).1,
+                ))
+            }
+            Some(kind) => Err(format!(
+                "Cannot select field '{}' of a {} value",
+                field_name,
+                type_utils::type_kind_name(&::substrait::proto::Type { kind: Some(kind) })
+            )),
+            None => Err(format!(
+                "Cannot select field '{}' of a value whose type is unknown",
+                field_name
+            )),
+        }
+    }
+
     /// Get the parent query location for a relation, following C++ getParentQueryLocation.
     /// First checks the relation's own parent_query info, then checks pipeline_start.
     /// Returns the relation symbol that has parent_query info set, or None.
@@ -931,7 +1641,7 @@ impl<'input> RelationVisitor<'input> {
     /// Look up a field with scope information, returning (field_index, steps_out).
     /// If the field is found in the current relation, steps_out = 0.
     /// If the field is found in a parent relation, steps_out = number of levels up.
-    fn lookup_field_with_scope(&mut self, column_name: &str) -> (usize, usize) {
+    fn lookup_field_with_scope(&mut self, column_name: &str) -> (Option<usize>, usize) {
         // First determine if this is an outer reference by checking if the schema belongs to a parent
         let steps_out = self.calculate_steps_out(column_name);
 
@@ -952,7 +1662,7 @@ impl<'input> RelationVisitor<'input> {
 
     /// Look up a field index in the parent relation.
     /// This is used for outer references.
-    fn lookup_field_index_in_parent_relation(&mut self, column_name: &str) -> usize {
+    fn lookup_field_index_in_parent_relation(&mut self, column_name: &str) -> Option<usize> {
         if let Some(current_rel) = self.current_relation_scope() {
             // Find the parent relation by checking parent_query_location
             let parent_loc = current_rel.parent_query_location();
@@ -993,7 +1703,7 @@ impl<'input> RelationVisitor<'input> {
                             // Search through field_references
                             for (index, field_sym) in relation_data.field_references.iter().enumerate() {
                                 if self.field_matches(field_sym, field_name, schema_name, column_name) {
-                                    return index;
+                                    return Some(index);
                                 }
                             }
 
@@ -1002,21 +1712,23 @@ impl<'input> RelationVisitor<'input> {
                             for (index, field_sym) in relation_data.generated_field_references.iter().enumerate() {
                                 if self.field_matches(field_sym, field_name, schema_name, column_name) {
                                     let actual_index = field_ref_size + index;
-                                    return actual_index;
+                                    return Some(actual_index);
                                 }
                             }
 
-                            println!("      WARNING: Field '{}' not found in parent '{}', defaulting to 0", column_name, parent.name());
+                            println!("      WARNING: Field '{}' not found in parent '{}'", column_name, parent.name());
                         }
                     }
                 }
             } else {
-                println!("      WARNING: Could not find parent relation for outer reference '{}', defaulting to 0", column_name);
+                println!(
+                    "      WARNING: Could not find parent relation for outer reference '{}'",
+                    column_name
+                );
             }
         }
 
-        // Default to 0 if not found
-        0
+        None
     }
 
     /// Calculate steps_out for a field reference to determine if it's an outer reference.
@@ -1149,7 +1861,7 @@ impl<'input> RelationVisitor<'input> {
         false
     }
 
-    fn lookup_field_index(&mut self, column_name: &str) -> usize {
+    fn lookup_field_index(&mut self, column_name: &str) -> Option<usize> {
         // Parse column name - can be "field" or "schema.field"
         let (schema_name, field_name) = if let Some(dot_pos) = column_name.rfind('.') {
             (&column_name[..dot_pos], &column_name[dot_pos + 1..])
@@ -1214,25 +1926,25 @@ impl<'input> RelationVisitor<'input> {
                         for (rev_idx, field_symbol) in relation_data.generated_field_references.iter().rev().enumerate() {
                             if self.field_matches(field_symbol, field_name, schema_name, column_name) {
                                 let actual_idx = relation_data.generated_field_references.len() - rev_idx - 1;
-                                return field_ref_size + actual_idx;
+                                return Some(field_ref_size + actual_idx);
                             }
                         }
 
                         // Then search field_references (in reverse order, like C++)
                         for (rev_idx, field_symbol) in relation_data.field_references.iter().rev().enumerate() {
                             if self.field_matches(field_symbol, field_name, schema_name, column_name) {
-                                return relation_data.field_references.len() - rev_idx - 1;
+                                return Some(relation_data.field_references.len() - rev_idx - 1);
                             }
                         }
 
                         // Not found in either list
                         // Like substrait-cpp, when field_references are populated but field not found,
-                        // just return a default value rather than doing global schema lookup
+                        // report it as missing rather than doing global schema lookup
                         println!(
-                            "      WARNING: Field '{}' not found in current relation (field_refs={}, generated={}), returning index 0",
+                            "      WARNING: Field '{}' not found in current relation (field_refs={}, generated={})",
                             column_name, field_ref_size, relation_data.generated_field_references.len()
                         );
-                        return 0;
+                        return None;
                     }
                 }
             }
@@ -1245,7 +1957,7 @@ impl<'input> RelationVisitor<'input> {
                 if let Some(field_index) =
                     self.get_field_index_from_schema(&schema_symbol, field_name)
                 {
-                    return field_index;
+                    return Some(field_index);
                 }
             }
         } else {
@@ -1256,7 +1968,7 @@ impl<'input> RelationVisitor<'input> {
                         if let Some(relation_data) = blob_data.downcast_ref::<crate::textplan::common::structured_symbol_data::RelationData>() {
                             if let Some(schema_arc) = &relation_data.schema {
                                 if let Some(field_index) = self.get_field_index_from_schema(schema_arc, field_name) {
-                                    return field_index;
+                                    return Some(field_index);
                                 }
                             }
                         }
@@ -1265,12 +1977,8 @@ impl<'input> RelationVisitor<'input> {
             }
         }
 
-        println!(
-            "      WARNING: Field '{}' not found anywhere, defaulting to index 0",
-            column_name
-        );
-        // Default to 0 if not found
-        0
+        println!("      WARNING: Field '{}' not found anywhere", column_name);
+        None
     }
 
     /// Get field index from a schema symbol by looking up the field name
@@ -1361,49 +2069,28 @@ impl<'input> RelationVisitor<'input> {
             return self.build_if_then_expression(ctx);
         }
 
-        // Look up function reference from symbol table
-        let function_reference = self.lookup_function_reference(&function_name);
-        println!("      -> function reference: {}", function_reference);
-
         // Recursively build arguments
-        // Check if an expression is actually an enum argument (ends with _enum)
-        let mut arguments = Vec::new();
-        for expr_ctx in ctx.expression_all() {
-            use crate::textplan::parser::antlr::substraitplanparser::ExpressionContextAll;
-
-            // Check if this is a column reference ending in _enum
-            if let ExpressionContextAll::ExpressionColumnContext(column_expr_ctx) =
-                expr_ctx.as_ref()
-            {
-                if let Some(column_ctx) = column_expr_ctx.column_name() {
-                    let column_text = column_ctx.get_text();
-                    if column_text.ends_with("_enum") {
-                        // This is an enum argument, not an expression
-                        let enum_value = column_text.strip_suffix("_enum").unwrap().to_string();
-                        println!("      Enum argument: {}", enum_value);
-                        arguments.push(::substrait::proto::FunctionArgument {
-                            arg_type: Some(::substrait::proto::function_argument::ArgType::Enum(
-                                enum_value,
-                            )),
-                        });
-                        continue;
-                    }
-                }
-            }
-
-            // Not an enum, build as expression
-            let arg_expr = self.build_expression(&expr_ctx);
-            arguments.push(::substrait::proto::FunctionArgument {
-                arg_type: Some(::substrait::proto::function_argument::ArgType::Value(
-                    arg_expr,
-                )),
-            });
-        }
+        let arguments: Vec<_> = ctx
+            .expression_all()
+            .iter()
+            .map(|expr_ctx| self.build_function_argument(expr_ctx))
+            .collect();
 
         println!("      with {} arguments", arguments.len());
 
+        // Look up function reference from symbol table
+        let (function_reference, derived_type) = self.resolve_function_reference(
+            &ctx.start(),
+            &function_name,
+            FunctionKind::Scalar,
+            &arguments,
+        );
+        println!("      -> function reference: {}", function_reference);
+
+        let options = self.build_function_options(ctx);
+
         // Extract output type if present (from ARROW literal_complex_type)
-        let output_type = if let Some(type_ctx) = ctx.literal_complex_type() {
+        let annotated_type = if let Some(type_ctx) = ctx.literal_complex_type() {
             let type_text = type_ctx.get_text();
             // Create a temporary TypeVisitor to parse the type
             let type_visitor =
@@ -1412,6 +2099,8 @@ impl<'input> RelationVisitor<'input> {
         } else {
             None
         };
+        let output_type =
+            self.function_output_type(&ctx.start(), &function_name, annotated_type, derived_type);
 
         ::substrait::proto::Expression {
             rex_type: Some(::substrait::proto::expression::RexType::ScalarFunction(
@@ -1419,13 +2108,68 @@ impl<'input> RelationVisitor<'input> {
                     function_reference,
                     arguments,
                     output_type,
-                    options: Vec::new(),
+                    options,
                     ..Default::default()
                 },
             )),
         }
     }
 
+    /// Build a single function argument.
+    /// A column reference ending in `_enum` (such as `FLOOR_enum`) is an enum argument.
+    fn build_function_argument(
+        &mut self,
+        expr_ctx: &Rc<ExpressionContextAll<'input>>,
+    ) -> ::substrait::proto::FunctionArgument {
+        use ::substrait::proto::function_argument::ArgType;
+
+        if let ExpressionContextAll::ExpressionColumnContext(column_expr_ctx) = expr_ctx.as_ref() {
+            if let Some(column_ctx) = column_expr_ctx.column_name() {
+                let column_text = column_ctx.get_text();
+                if let Some(enum_value) = column_text.strip_suffix("_enum") {
+                    // This is an enum argument, not an expression
+                    println!("      Enum argument: {}", enum_value);
+                    return ::substrait::proto::FunctionArgument {
+                        arg_type: Some(ArgType::Enum(enum_value.to_string())),
+                    };
+                }
+            }
+        }
+
+        // Not an enum, build as expression
+        ::substrait::proto::FunctionArgument {
+            arg_type: Some(ArgType::Value(self.build_expression(expr_ctx))),
+        }
+    }
+
+    /// Build the function options given as `[name: PREFERENCE, ...]` after a function call.
+    /// Repeating an option name appends another preference to that option.
+    fn build_function_options(
+        &self,
+        ctx: &ExpressionFunctionUseContext<'input>,
+    ) -> Vec<::substrait::proto::FunctionOption> {
+        let mut options: Vec<::substrait::proto::FunctionOption> = Vec::new();
+        let Some(options_ctx) = ctx.function_options() else {
+            return options;
+        };
+        for option_ctx in options_ctx.function_option_all() {
+            let ids = option_ctx.id_all();
+            let (Some(name), Some(preference)) = (ids.first(), ids.get(1)) else {
+                continue;
+            };
+            let name = name.get_text();
+            let preference = preference.get_text();
+            match options.iter_mut().find(|option| option.name == name) {
+                Some(option) => option.preference.push(preference),
+                None => options.push(::substrait::proto::FunctionOption {
+                    name,
+                    preference: vec![preference],
+                }),
+            }
+        }
+        options
+    }
+
     /// Build a cast expression from a cast context.
     fn build_cast_expression(
         &mut self,
@@ -1497,18 +2241,105 @@ impl<'input> RelationVisitor<'input> {
             }
         }
 
+        // Optional ON_FAILURE clause (RETURN_NULL or THROW_EXCEPTION)
+        let failure_behavior = match ctx.id() {
+            Some(behavior_ctx) => {
+                let behavior_text = behavior_ctx.get_text();
+                parse_cast_failure_behavior(&behavior_text).unwrap_or_else(|| {
+                    self.add_error(
+                        &behavior_ctx.start(),
+                        &format!(
+                            "Unrecognized cast failure behavior '{}', expected RETURN_NULL or THROW_EXCEPTION",
+                            behavior_text
+                        ),
+                    );
+                    ::substrait::proto::expression::cast::FailureBehavior::Unspecified
+                })
+            }
+            None => ::substrait::proto::expression::cast::FailureBehavior::Unspecified,
+        };
+
         ::substrait::proto::Expression {
             rex_type: Some(::substrait::proto::expression::RexType::Cast(Box::new(
                 ::substrait::proto::expression::Cast {
                     r#type: Some(target_type),
                     input: Some(input_expr),
-                    failure_behavior:
-                        ::substrait::proto::expression::cast::FailureBehavior::Unspecified as i32,
+                    failure_behavior: failure_behavior as i32,
                 },
             ))),
         }
     }
 
+    /// Build a dynamic parameter expression such as `$1` or `$region:string`.
+    ///
+    /// The parameter's type comes from the placeholder or from the parameters block.
+    fn build_dynamic_parameter(
+        &mut self,
+        ctx: &ExpressionParameterContext<'input>,
+    ) -> ::substrait::proto::Expression {
+        let name = ctx.PARAMETER().map(|p| p.get_text()).unwrap_or_default();
+        let location = token_to_location(&ctx.start());
+        let symbol = match lookup_or_define_parameter(self.symbol_table_mut(), &name, location) {
+            Ok(symbol) => symbol,
+            Err(message) => {
+                self.add_error(&ctx.start(), &message);
+                return ::substrait::proto::Expression::default();
+            }
+        };
+
+        let explicit_type = ctx.literal_complex_type().map(|type_ctx| {
+            let type_visitor =
+                TypeVisitor::new(self.symbol_table.clone(), self.error_listener.clone());
+            type_visitor.text_to_type_proto(ctx, &type_ctx.get_text())
+        });
+
+        let (anchor, declared_type) = symbol
+            .with_blob::<ParameterData, _, _>(|data| {
+                // The first typed use declares the type for later untyped uses
+                if data.parameter_type.is_none() {
+                    data.parameter_type = explicit_type.clone();
+                }
+                (data.anchor, data.parameter_type.clone())
+            })
+            .unwrap_or_default();
+
+        let parameter_type = match (explicit_type, declared_type) {
+            (Some(explicit), Some(declared)) => {
+                if !type_utils::same_type_kind(&explicit, &declared) {
+                    self.add_error(
+                        &ctx.start(),
+                        &format!(
+                            "Parameter {} is used as {} but declared as {}",
+                            name,
+                            type_utils::type_kind_name(&explicit),
+                            type_utils::type_kind_name(&declared)
+                        ),
+                    );
+                }
+                Some(explicit)
+            }
+            (explicit, declared) => explicit.or(declared),
+        };
+        if parameter_type.is_none() {
+            self.add_error(
+                &ctx.start(),
+                &format!(
+                    "Parameter {} has no type, write {}:<type> or declare it in a parameters block",
+                    name, name
+                ),
+            );
+        }
+
+        ::substrait::proto::Expression {
+            rex_type: Some(::substrait::proto::expression::RexType::DynamicParameter(
+                ::substrait::proto::DynamicParameter {
+                    r#type: parameter_type,
+                    parameter_reference: anchor,
+                },
+            )),
+        }
+    }
+
     /// Extract a numeric value from a constant context.
     fn extract_number_from_constant(constant_ctx: &Rc<ConstantContextAll<'input>>) -> i32 {
         if let Some(number_token) = constant_ctx.NUMBER() {
@@ -1523,25 +2354,50 @@ impl<'input> RelationVisitor<'input> {
         &self,
         constant_ctx: &Rc<ConstantContextAll<'input>>,
     ) -> ::substrait::proto::Expression {
-        use ::substrait::proto::expression::literal::LiteralType;
-
+        ::substrait::proto::Expression {
+            rex_type: Some(::substrait::proto::expression::RexType::Literal(
+                self.build_literal(constant_ctx, None),
+            )),
+        }
+    }
+
+    /// Build a literal from a constant AST node.
+    ///
+    /// `expected_type` is the type declared by an enclosing struct, list or map literal.  It
+    /// supplies the type of constants without a suffix of their own and is checked otherwise.
+    fn build_literal(
+        &self,
+        constant_ctx: &Rc<ConstantContextAll<'input>>,
+        expected_type: Option<&::substrait::proto::Type>,
+    ) -> ::substrait::proto::expression::Literal {
+        use ::substrait::proto::expression::literal::LiteralType;
+
+        // Type name used for constants without a suffix inside a typed literal
+        let expected_type_name = expected_type.map(type_utils::type_kind_name);
+
+        // The complex type suffix of struct, map and null constants
+        let suffix_type = constant_ctx.literal_complex_type().map(|type_ctx| {
+            let type_visitor =
+                TypeVisitor::new(self.symbol_table.clone(), self.error_listener.clone());
+            type_visitor.text_to_type_proto(&**constant_ctx, &type_ctx.get_text())
+        });
+
         // Check what type of constant this is
         let literal_type = if let Some(number_token) = constant_ctx.NUMBER() {
             // Parse number literal
             let number_text = number_token.get_text();
 
             // Check if there's a type suffix (e.g., _date, _decimal<3,2>)
-            if let Some(type_ctx) = constant_ctx.literal_basic_type() {
-                // Get the type name
-                let type_text = type_ctx.get_text();
-
-                // Parse the type name
-                let type_name = if let Some(id_ctx) = type_ctx.id() {
-                    id_ctx.get_text().to_lowercase()
-                } else {
-                    "".to_string()
-                };
-
+            let type_name = match constant_ctx.literal_basic_type() {
+                Some(type_ctx) => Some(
+                    type_ctx
+                        .id()
+                        .map(|id_ctx| id_ctx.get_text().to_lowercase())
+                        .unwrap_or_default(),
+                ),
+                None => expected_type_name.map(str::to_string),
+            };
+            if let Some(type_name) = type_name {
                 // Handle different literal types based on type name
                 match type_name.as_str() {
                     "date" => {
@@ -1558,6 +2414,20 @@ impl<'input> RelationVisitor<'input> {
                             Some(LiteralType::Time(0))
                         }
                     }
+                    "timestamp" | "timestamp_tz" => {
+                        let micros = number_text.parse::<i64>();
+                        if micros.is_err() {
+                            self.add_error(
+                                &constant_ctx.start(),
+                                &format!("Invalid {} literal {}", type_name, number_text),
+                            );
+                        }
+                        match (type_name.as_str(), micros) {
+                            (_, Err(_)) => None,
+                            ("timestamp", Ok(micros)) => Some(LiteralType::Timestamp(micros)),
+                            (_, Ok(micros)) => Some(LiteralType::TimestampTz(micros)),
+                        }
+                    }
                     "interval_year" => {
                         // Parse as years directly
                         if let Ok(years) = number_text.parse::<i32>() {
@@ -1602,24 +2472,31 @@ impl<'input> RelationVisitor<'input> {
                             let bytes = value.to_le_bytes().to_vec();
 
                             // Get precision and scale from literal_specifier if present
-                            let (precision, scale) =
-                                if let Some(spec_ctx) = type_ctx.literal_specifier() {
-                                    // Parse numbers from the specifier (<precision,scale>)
-                                    let numbers: Vec<i32> = spec_ctx
-                                        .NUMBER_all()
-                                        .iter()
-                                        .filter_map(|n| n.get_text().parse::<i32>().ok())
-                                        .collect();
-                                    if numbers.len() >= 2 {
-                                        (numbers[0], numbers[1])
-                                    } else if numbers.len() == 1 {
-                                        (numbers[0], 0)
-                                    } else {
-                                        (38, 0)
-                                    }
+                            let (precision, scale) = if let Some(spec_ctx) = constant_ctx
+                                .literal_basic_type()
+                                .and_then(|type_ctx| type_ctx.literal_specifier())
+                            {
+                                // Parse numbers from the specifier (<precision,scale>)
+                                let numbers: Vec<i32> = spec_ctx
+                                    .NUMBER_all()
+                                    .iter()
+                                    .filter_map(|n| n.get_text().parse::<i32>().ok())
+                                    .collect();
+                                if numbers.len() >= 2 {
+                                    (numbers[0], numbers[1])
+                                } else if numbers.len() == 1 {
+                                    (numbers[0], 0)
                                 } else {
-                                    (38, 0) // Default precision and scale
-                                };
+                                    (38, 0)
+                                }
+                            } else if let Some(::substrait::proto::r#type::Kind::Decimal(
+                                decimal_type,
+                            )) = expected_type.and_then(|t| t.kind.as_ref())
+                            {
+                                (decimal_type.precision, decimal_type.scale)
+                            } else {
+                                (38, 0) // Default precision and scale
+                            };
 
                             Some(LiteralType::Decimal(
                                 ::substrait::proto::expression::literal::Decimal {
@@ -1709,31 +2586,125 @@ impl<'input> RelationVisitor<'input> {
             };
 
             // Check for type suffix for FixedChar or VarChar
-            if let Some(type_ctx) = constant_ctx.literal_basic_type() {
-                if let Some(id_ctx) = type_ctx.id() {
-                    let type_name = id_ctx.get_text().to_lowercase();
-                    match type_name.as_str() {
-                        "fixedchar" => Some(LiteralType::FixedChar(string_value)),
-                        "varchar" => Some(LiteralType::VarChar(
-                            ::substrait::proto::expression::literal::VarChar {
-                                value: string_value,
-                                length: 0,
-                            },
-                        )),
-                        _ => Some(LiteralType::String(string_value)),
+            let type_name = match constant_ctx.literal_basic_type() {
+                Some(type_ctx) => type_ctx.id().map(|id_ctx| id_ctx.get_text().to_lowercase()),
+                None => expected_type_name.map(str::to_string),
+            };
+            if let Some(type_name) = type_name {
+                match type_name.as_str() {
+                    "fixedchar" => Some(LiteralType::FixedChar(string_value)),
+                    "varchar" => Some(LiteralType::VarChar(
+                        ::substrait::proto::expression::literal::VarChar {
+                            value: string_value,
+                            length: 0,
+                        },
+                    )),
+                    "date" | "time" | "timestamp" | "timestamp_tz" => {
+                        let literal_type = match type_name.as_str() {
+                            "date" => temporal::parse_date(&string_value).map(LiteralType::Date),
+                            "time" => temporal::parse_time(&string_value).map(LiteralType::Time),
+                            "timestamp" => {
+                                temporal::parse_timestamp(&string_value).map(LiteralType::Timestamp)
+                            }
+                            _ => temporal::parse_timestamp_tz(&string_value)
+                                .map(LiteralType::TimestampTz),
+                        };
+                        if literal_type.is_none() {
+                            self.add_error(
+                                &constant_ctx.start(),
+                                &format!("Invalid {} literal \"{}\"", type_name, string_value),
+                            );
+                        }
+                        literal_type
                     }
-                } else {
-                    Some(LiteralType::String(string_value))
+                    "uuid" => {
+                        let uuid = string_utils::parse_uuid(&string_value);
+                        if uuid.is_none() {
+                            self.add_error(
+                                &constant_ctx.start(),
+                                &format!("Invalid uuid literal \"{}\"", string_value),
+                            );
+                        }
+                        uuid.map(LiteralType::Uuid)
+                    }
+                    _ => Some(LiteralType::String(string_value)),
                 }
             } else {
                 Some(LiteralType::String(string_value))
             }
+        } else if let Some(hex_token) = constant_ctx.HEX_STRING() {
+            // Parse hex-encoded binary literal (remove the x prefix and quotes)
+            let hex_text = hex_token.get_text();
+            let bytes = string_utils::decode_hex(&hex_text[2..hex_text.len() - 1]);
+            if bytes.is_none() {
+                self.add_error(
+                    &constant_ctx.start(),
+                    &format!(
+                        "Binary literal {} has an odd number of hex digits",
+                        hex_text
+                    ),
+                );
+            }
+            let bytes = bytes.unwrap_or_default();
+
+            let type_name = constant_ctx
+                .literal_basic_type()
+                .and_then(|type_ctx| type_ctx.id())
+                .map(|id_ctx| id_ctx.get_text().to_lowercase())
+                .or_else(|| expected_type_name.map(str::to_string))
+                .unwrap_or_else(|| "binary".to_string());
+            match type_name.as_str() {
+                "fixedbinary" => {
+                    // An explicit length such as _fixedbinary<4> must match the data
+                    let length = constant_ctx
+                        .literal_basic_type()
+                        .and_then(|type_ctx| type_ctx.literal_specifier())
+                        .and_then(|spec_ctx| spec_ctx.NUMBER_all().first().map(|n| n.get_text()))
+                        .and_then(|n| n.parse::<usize>().ok());
+                    if let Some(length) = length {
+                        if length != bytes.len() {
+                            self.add_error(
+                                &constant_ctx.start(),
+                                &format!(
+                                    "fixedbinary<{}> literal has {} bytes",
+                                    length,
+                                    bytes.len()
+                                ),
+                            );
+                        }
+                    }
+                    Some(LiteralType::FixedBinary(bytes))
+                }
+                "uuid" => {
+                    if bytes.len() != 16 {
+                        self.add_error(
+                            &constant_ctx.start(),
+                            &format!("uuid literal has {} bytes, expected 16", bytes.len()),
+                        );
+                    }
+                    Some(LiteralType::Uuid(bytes))
+                }
+                "binary" => Some(LiteralType::Binary(bytes)),
+                _ => {
+                    self.add_error(
+                        &constant_ctx.start(),
+                        &format!("Hex literals cannot have type {}", type_name),
+                    );
+                    Some(LiteralType::Binary(bytes))
+                }
+            }
         } else if constant_ctx.TRUEVAL().is_some() {
             Some(LiteralType::Boolean(true))
         } else if constant_ctx.FALSEVAL().is_some() {
             Some(LiteralType::Boolean(false))
         } else if constant_ctx.NULLVAL().is_some() {
-            Some(LiteralType::Null(::substrait::proto::Type::default()))
+            // The type of a null comes from its suffix or from the enclosing literal
+            Some(LiteralType::Null(
+                suffix_type
+                    .clone()
+                    .or_else(|| expected_type.cloned())
+                    .unwrap_or_default(),
+            ))
         } else if let Some(struct_ctx) = constant_ctx.struct_literal() {
             // Handle struct literals - check if it has an interval type suffix
             // NOTE: struct_literal uses literal_complex_type, not literal_basic_type (per grammar line 108)
@@ -1799,34 +2770,179 @@ impl<'input> RelationVisitor<'input> {
                             ))
                         }
                     }
-                    _ => {
-                        // Unknown struct literal type
-                        Some(LiteralType::I64(0))
-                    }
+                    _ => self.build_container_literal(constant_ctx, suffix_type.as_ref()),
                 }
             } else {
                 // Struct without type suffix
-                Some(LiteralType::I64(0))
+                self.build_container_literal(constant_ctx, expected_type)
             }
+        } else if constant_ctx.map_literal().is_some() {
+            self.build_container_literal(constant_ctx, suffix_type.as_ref().or(expected_type))
         } else {
-            // TODO: Handle map_literal
-            // For now, default to i64(0)
             Some(LiteralType::I64(0))
         };
 
-        ::substrait::proto::Expression {
-            rex_type: Some(::substrait::proto::expression::RexType::Literal(
-                ::substrait::proto::expression::Literal {
-                    literal_type,
-                    nullable: false,
-                    type_variation_reference: 0,
-                },
-            )),
+        // A ? on the type suffix makes the literal nullable
+        let nullable = constant_ctx
+            .literal_basic_type()
+            .is_some_and(|type_ctx| type_ctx.QUESTIONMARK().is_some())
+            || suffix_type.as_ref().is_some_and(type_utils::is_nullable);
+
+        let mut literal = ::substrait::proto::expression::Literal {
+            literal_type,
+            nullable,
+            type_variation_reference: 0,
+        };
+
+        if let Some(expected) = expected_type {
+            if let Some(actual) = type_utils::literal_to_type(&literal) {
+                if !type_utils::same_type_kind(&actual, expected) {
+                    self.add_error(
+                        &constant_ctx.start(),
+                        &format!(
+                            "Literal of type {} does not match the declared type {}",
+                            type_utils::type_kind_name(&actual),
+                            type_utils::type_kind_name(expected)
+                        ),
+                    );
+                }
+            }
+            if matches!(literal.literal_type, Some(LiteralType::Null(_)))
+                && !type_utils::is_nullable(expected)
+            {
+                self.add_error(
+                    &constant_ctx.start(),
+                    &format!(
+                        "null is not allowed for the non-nullable type {}",
+                        type_utils::type_kind_name(expected)
+                    ),
+                );
+            }
+            literal.nullable |= type_utils::is_nullable(expected);
+        }
+
+        literal
+    }
+
+    /// Build a struct, list or map literal from a braced constant.
+    ///
+    /// The container type (from the `_struct<...>`, `_list<...>` or `_map<...>` suffix or from an
+    /// enclosing literal) types each element.  Without one, the elements keep their own types and
+    /// the literal becomes a struct (or a map for `{key: value}` syntax).
+    fn build_container_literal(
+        &self,
+        constant_ctx: &Rc<ConstantContextAll<'input>>,
+        container_type: Option<&::substrait::proto::Type>,
+    ) -> Option<::substrait::proto::expression::literal::LiteralType> {
+        use ::substrait::proto::expression::literal::{self, map::KeyValue, LiteralType};
+        use ::substrait::proto::r#type::Kind;
+
+        let elements = constant_ctx
+            .struct_literal()
+            .map(|struct_ctx| struct_ctx.constant_all())
+            .unwrap_or_default();
+        let pairs = constant_ctx
+            .map_literal()
+            .map(|map_ctx| map_ctx.map_literal_value_all())
+            .unwrap_or_default();
+
+        let build_key_values =
+            |key_type: Option<&::substrait::proto::Type>,
+             value_type: Option<&::substrait::proto::Type>| {
+                pairs
+                    .iter()
+                    .map(|pair_ctx| {
+                        let constants = pair_ctx.constant_all();
+                        KeyValue {
+                            key: constants.first().map(|c| self.build_literal(c, key_type)),
+                            value: constants.get(1).map(|c| self.build_literal(c, value_type)),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+        match container_type.and_then(|t| t.kind.as_ref()) {
+            Some(Kind::Struct(struct_type)) => {
+                if !pairs.is_empty() {
+                    self.add_error(
+                        &constant_ctx.start(),
+                        "A {key: value} literal cannot have a struct type",
+                    );
+                    return None;
+                }
+                if elements.len() != struct_type.types.len() {
+                    self.add_error(
+                        &constant_ctx.start(),
+                        &format!(
+                            "Struct literal has {} fields but its type declares {}",
+                            elements.len(),
+                            struct_type.types.len()
+                        ),
+                    );
+                }
+                let fields = elements
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field_ctx)| self.build_literal(field_ctx, struct_type.types.get(i)))
+                    .collect();
+                Some(LiteralType::Struct(literal::Struct { fields }))
+            }
+            Some(Kind::List(list_type)) => {
+                if !pairs.is_empty() {
+                    self.add_error(
+                        &constant_ctx.start(),
+                        "A {key: value} literal cannot have a list type",
+                    );
+                    return None;
+                }
+                if elements.is_empty() {
+                    return Some(LiteralType::EmptyList((**list_type).clone().into()));
+                }
+                let values = elements
+                    .iter()
+                    .map(|value_ctx| self.build_literal(value_ctx, list_type.r#type.as_deref()))
+                    .collect();
+                Some(LiteralType::List(literal::List { values }))
+            }
+            Some(Kind::Map(map_type)) => {
+                if !elements.is_empty() {
+                    self.add_error(
+                        &constant_ctx.start(),
+                        "A map literal must be written as {key: value, ...}",
+                    );
+                    return None;
+                }
+                if pairs.is_empty() {
+                    return Some(LiteralType::EmptyMap((**map_type).clone().into()));
+                }
+                let key_values =
+                    build_key_values(map_type.key.as_deref(), map_type.value.as_deref());
+                Some(LiteralType::Map(literal::Map { key_values }))
+            }
+            Some(_) => {
+                self.add_error(
+                    &constant_ctx.start(),
+                    &format!(
+                        "Type {} cannot be used for a braced literal",
+                        container_type.map_or("unknown", type_utils::type_kind_name)
+                    ),
+                );
+                None
+            }
+            None if !pairs.is_empty() => Some(LiteralType::Map(literal::Map {
+                key_values: build_key_values(None, None),
+            })),
+            None => Some(LiteralType::Struct(literal::Struct {
+                fields: elements
+                    .iter()
+                    .map(|field_ctx| self.build_literal(field_ctx, None))
+                    .collect(),
+            })),
         }
     }
 
-    /// Look up the function reference (anchor) from the symbol table
-    fn lookup_function_reference(&self, function_name: &str) -> u32 {
+    /// Look up the declaration of a function from the symbol table
+    fn lookup_function_declaration(&self, function_name: &str) -> Option<FunctionData> {
         // Iterate through all symbols to find functions with matching name
         for symbol in self.symbol_table().symbols() {
             if symbol.symbol_type() == SymbolType::Function {
@@ -1835,17 +2951,274 @@ impl<'input> RelationVisitor<'input> {
                     // Get the function data from the blob to get the anchor
                     if let Some(blob_lock) = &symbol.blob {
                         if let Ok(blob_data) = blob_lock.lock() {
-                            if let Some(function_data) = blob_data.downcast_ref::<crate::textplan::common::structured_symbol_data::FunctionData>() {
-                                return function_data.anchor;
+                            if let Some(function_data) = blob_data.downcast_ref::<FunctionData>() {
+                                return Some(function_data.clone());
                             }
                         }
                     }
                 }
             }
         }
+        None
+    }
 
-        // Function not found - use default reference 0
-        0
+    /// Look up the URI of the extension space with the given anchor.
+    fn lookup_extension_uri(&self, anchor: u32) -> Option<String> {
+        self.symbol_table()
+            .symbols()
+            .iter()
+            .filter(|symbol| symbol.symbol_type() == SymbolType::ExtensionSpace)
+            .find(|symbol| {
+                symbol.with_blob::<ExtensionSpaceData, _, _>(|data| data.anchor_reference())
+                    == Some(anchor)
+            })
+            .map(|symbol| symbol.name().to_string())
+    }
+
+    /// Resolve the function reference (anchor) for a call, along with the return type
+    /// derived from the function's definition when the extension file is known.
+    ///
+    /// Functions declared in an extension space are found by their alias.  Otherwise the
+    /// implementation is picked from the extension registry using the argument types and
+    /// declared on the fly, so that the plan only needs to call it by its plain name.  Calls
+    /// that more than one implementation accepts are reported as ambiguous and have to be
+    /// declared explicitly.
+    fn resolve_function_reference<'a>(
+        &mut self,
+        token: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+        function_name: &str,
+        kind: FunctionKind,
+        arguments: &[::substrait::proto::FunctionArgument],
+    ) -> (u32, Option<::substrait::proto::Type>) {
+        use ::substrait::proto::function_argument::ArgType;
+
+        let call_arguments: Vec<CallArgument> = arguments
+            .iter()
+            .map(|argument| match &argument.arg_type {
+                Some(ArgType::Enum(option)) => CallArgument::Enum(option.clone()),
+                Some(ArgType::Value(expression)) => {
+                    CallArgument::Value(self.expression_type(expression))
+                }
+                _ => CallArgument::Value(None),
+            })
+            .collect();
+        let registry = self.extension_registry.clone();
+
+        // Declarations made for earlier calls may be for another overload
+        if !self.synthesized_functions.contains(function_name) {
+            if let Some(function_data) = self.lookup_function_declaration(function_name) {
+                let return_type = function_data
+                    .extension_uri_reference
+                    .and_then(|anchor| self.lookup_extension_uri(anchor))
+                    .and_then(|uri| {
+                        registry.find_declared_function(&uri, &function_data.name, &call_arguments)
+                    })
+                    .and_then(|resolved| resolved.return_type(&call_arguments));
+                return (function_data.anchor, return_type);
+            }
+        }
+
+        match registry.resolve_function(function_name, kind, &call_arguments) {
+            Ok(Some(resolved)) => (
+                self.declare_extension_function(token, function_name, &resolved),
+                resolved.return_type(&call_arguments),
+            ),
+            Ok(None) => {
+                self.report_unresolved_name(
+                    Severity::Error,
+                    DiagnosticCode::UnknownFunction,
+                    token_to_location(token),
+                    format!(
+                        "Function '{}' is not declared and is not defined by any known extension",
+                        function_name
+                    ),
+                    self.symbol_table
+                        .suggest_symbol_name(function_name, SymbolType::Function),
+                );
+                (0, None)
+            }
+            Err(message) => {
+                self.add_error_with_code(token, DiagnosticCode::ExtensionMismatch, &message);
+                (0, None)
+            }
+        }
+    }
+
+    /// Choose the output type of a call from its `-> type` annotation and the type
+    /// derived from the function's definition, warning about any disagreement between
+    /// them.  A type is only derived when every argument type is known and exactly one
+    /// implementation matches the call.  The annotation wins as producers may pick their own decimal precisions and
+    /// string types.  Nullability is not compared as plans often leave it implied.
+    fn function_output_type<'a>(
+        &self,
+        token: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+        function_name: &str,
+        annotated_type: Option<::substrait::proto::Type>,
+        derived_type: Option<::substrait::proto::Type>,
+    ) -> Option<::substrait::proto::Type> {
+        match (annotated_type, derived_type) {
+            (Some(annotated_type), Some(derived_type)) => {
+                if !type_utils::same_type_ignoring_nullability(&annotated_type, &derived_type) {
+                    let printer = ExpressionPrinter::new(&self.symbol_table, None);
+                    let type_text = |type_val: &::substrait::proto::Type| {
+                        printer
+                            .print_type(type_val)
+                            .unwrap_or_else(|_| type_utils::type_kind_name(type_val).to_string())
+                    };
+                    self.error_listener.add_diagnostic(Diagnostic::warning(
+                        DiagnosticCode::TypeMismatch,
+                        format!(
+                            "Function '{}' returns {} but is annotated as returning {}",
+                            function_name,
+                            type_text(&derived_type),
+                            type_text(&annotated_type)
+                        ),
+                        token_to_location(token),
+                    ));
+                }
+                Some(annotated_type)
+            }
+            (annotated_type, derived_type) => annotated_type.or(derived_type),
+        }
+    }
+
+    /// Declare the function implementation picked for a call, along with its extension
+    /// space, unless an earlier call already did.  Returns the function anchor.
+    fn declare_extension_function<'a>(
+        &mut self,
+        token: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+        function_name: &str,
+        resolved: &ResolvedFunction,
+    ) -> u32 {
+        let declaration_name = resolved.declaration_name();
+        let extension_uri = resolved.extension_uri();
+
+        let mut space_anchor = None;
+        let mut next_space_anchor = 0;
+        let mut next_function_anchor = 0;
+        for symbol in self.symbol_table().symbols() {
+            match symbol.symbol_type() {
+                SymbolType::ExtensionSpace => {
+                    let Some(anchor) = symbol
+                        .with_blob::<ExtensionSpaceData, _, _>(|data| data.anchor_reference())
+                    else {
+                        continue;
+                    };
+                    next_space_anchor = next_space_anchor.max(anchor + 1);
+                    if symbol.name() == extension_uri {
+                        space_anchor = Some(anchor);
+                    }
+                }
+                SymbolType::Function => {
+                    let Some((name, anchor)) = symbol
+                        .with_blob::<FunctionData, _, _>(|data| (data.name.clone(), data.anchor))
+                    else {
+                        continue;
+                    };
+                    if name == declaration_name {
+                        return anchor;
+                    }
+                    next_function_anchor = next_function_anchor.max(anchor + 1);
+                }
+                _ => {}
+            }
+        }
+
+        let location = token_to_location(token);
+        let space_anchor = match space_anchor {
+            Some(anchor) => anchor,
+            None => {
+                let blob = Some(Arc::new(std::sync::Mutex::new(ExtensionSpaceData::new(
+                    next_space_anchor,
+                )))
+                    as Arc<std::sync::Mutex<dyn std::any::Any + Send + Sync>>);
+                self.symbol_table_mut().define_symbol(
+                    extension_uri.clone(),
+                    location,
+                    SymbolType::ExtensionSpace,
+                    None,
+                    blob,
+                );
+                next_space_anchor
+            }
+        };
+
+        let alias = self.symbol_table.get_unique_name(function_name);
+        self.synthesized_functions.insert(alias.clone());
+        let blob = Some(Arc::new(std::sync::Mutex::new(FunctionData::new(
+            declaration_name.clone(),
+            Some(space_anchor),
+            next_function_anchor,
+        )))
+            as Arc<std::sync::Mutex<dyn std::any::Any + Send + Sync>>);
+        self.symbol_table_mut()
+            .define_symbol(alias, location, SymbolType::Function, None, blob);
+        next_function_anchor
+    }
+
+    /// Determine the type of an already built expression, if it is known.
+    fn expression_type(
+        &self,
+        expression: &::substrait::proto::Expression,
+    ) -> Option<::substrait::proto::Type> {
+        use ::substrait::proto::expression::RexType;
+
+        match &expression.rex_type {
+            Some(RexType::Literal(literal)) => type_utils::literal_to_type(literal),
+            Some(RexType::Selection(reference)) => self.field_reference_type(reference),
+            Some(RexType::ScalarFunction(function)) => function.output_type.clone(),
+            Some(RexType::Cast(cast)) => cast.r#type.clone(),
+            Some(RexType::DynamicParameter(parameter)) => parameter.r#type.clone(),
+            _ => None,
+        }
+    }
+
+    /// Determine the type of a direct field reference from the current relation's fields.
+    fn field_reference_type(
+        &self,
+        reference: &::substrait::proto::expression::FieldReference,
+    ) -> Option<::substrait::proto::Type> {
+        use ::substrait::proto::expression::field_reference::{ReferenceType, RootType};
+        use ::substrait::proto::expression::reference_segment;
+
+        let Some(ReferenceType::DirectReference(segment)) = &reference.reference_type else {
+            return None;
+        };
+        let Some(reference_segment::ReferenceType::StructField(field)) = &segment.reference_type
+        else {
+            return None;
+        };
+        if field.child.is_some() {
+            return None;
+        }
+        let index = usize::try_from(field.field).ok()?;
+
+        match &reference.root_type {
+            Some(RootType::RootReference(_)) => {
+                let relation_symbol = self.current_relation_scope()?;
+                let field_symbol = relation_symbol
+                    .with_blob::<RelationData, _, _>(|relation_data| {
+                        relation_data
+                            .field_references
+                            .iter()
+                            .chain(relation_data.generated_field_references.iter())
+                            .nth(index)
+                            .cloned()
+                    })
+                    .flatten()?;
+                field_symbol
+                    .with_blob::<::substrait::proto::Type, _, _>(|type_val| type_val.clone())
+                    .or_else(|| {
+                        // Fields computed by earlier relations only have a type in the
+                        // inferred schema of the relation's input
+                        relation_schema::relation_input_schema(&self.symbol_table, relation_symbol)
+                            .ok()?
+                            .field_type(index)
+                            .cloned()
+                    })
+            }
+            _ => None,
+        }
     }
 
     /// Build a set comparison subquery expression (e.g., expression LT ANY SUBQUERY relation)
@@ -2280,8 +3653,26 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
     }
 
     fn visit_relationFilter(&mut self, ctx: &RelationFilterContext<'input>) {
-        // Add filter condition to the current relation (should be a Filter)
+        // Add filter condition to the current relation (Filter or Read)
         // Grammar: relation_filter_behavior? FILTER expression SEMICOLON
+        let behavior_text = ctx
+            .relation_filter_behavior()
+            .map(|behavior| behavior.get_text())
+            .unwrap_or_default();
+        let behavior = match parse_filter_behavior(&behavior_text) {
+            Some(behavior) => behavior,
+            None => {
+                self.add_error(
+                    &ctx.start(),
+                    &format!(
+                        "Unrecognized filter behavior '{}', expected best_effort or mandatory",
+                        behavior_text
+                    ),
+                );
+                FilterBehavior::Mandatory
+            }
+        };
+
         if let Some(relation_symbol) = self.current_relation_scope().cloned() {
             // Build the filter condition expression from AST
             let condition = if let Some(expr_ctx) = ctx.expression() {
@@ -2301,25 +3692,44 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
                 }
             };
 
-            // Add the condition to the FilterRel
+            // Add the condition to the FilterRel or ReadRel
+            let mut misplaced_best_effort = false;
+            let mut unsupported_relation = false;
             if let Some(blob_lock) = &relation_symbol.blob {
                 if let Ok(mut blob_data) = blob_lock.lock() {
                     if let Some(relation_data) = blob_data.downcast_mut::<crate::textplan::common::structured_symbol_data::RelationData>() {
                         // Get mutable access to the Rel
-                        if let Some(::substrait::proto::rel::RelType::Filter(ref mut filter_rel)) = relation_data.relation.rel_type {
-                            filter_rel.condition = Some(Box::new(condition.clone()));
-                            println!("  Added filter condition to filter relation '{}'", relation_symbol.name());
-
-                            // Debug: count subquery expressions in condition
-                            // Note: count_subqueries function was removed
-                            // if let Some(cond_box) = &filter_rel.condition {
-                            //     let count = count_subqueries(&cond_box);
-                            //     println!("    Filter '{}' condition has {} subquery expressions", relation_symbol.name(), count);
-                            // }
+                        match relation_data.relation.rel_type {
+                            Some(RelType::Filter(ref mut filter_rel)) => {
+                                misplaced_best_effort = behavior == FilterBehavior::BestEffort;
+                                filter_rel.condition = Some(Box::new(condition.clone()));
+                                println!("  Added filter condition to filter relation '{}'", relation_symbol.name());
+                            }
+                            Some(RelType::Read(ref mut read_rel)) => {
+                                // Read relations push the filter down to the data source
+                                if behavior == FilterBehavior::BestEffort {
+                                    read_rel.best_effort_filter = Some(Box::new(condition.clone()));
+                                } else {
+                                    read_rel.filter = Some(Box::new(condition.clone()));
+                                }
+                            }
+                            _ => unsupported_relation = true,
                         }
                     }
                 }
             }
+
+            if unsupported_relation {
+                self.add_error(
+                    &ctx.start(),
+                    "Filters are only supported on filter and read relations",
+                );
+            } else if misplaced_best_effort {
+                self.add_error(
+                    &ctx.start(),
+                    "Best effort filters are only supported on read relations",
+                );
+            }
         }
 
         // Visit children to process any nested expressions
@@ -2512,25 +3922,29 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
                 if let Some(expr_ctx) = measure_detail_ctx.expression() {
                     // For aggregate measures, we need to extract the function reference and arguments
                     // directly instead of building a ScalarFunction expression
-                    let (function_reference, arguments, output_type) = match expr_ctx.as_ref() {
+                    let (function_reference, arguments, output_type, options) = match expr_ctx
+                        .as_ref()
+                    {
                         ExpressionContextAll::ExpressionFunctionUseContext(func_ctx) => {
                             // Get function name and look up reference
                             let function_name = func_ctx
                                 .id()
                                 .map(|id| id.get_text())
                                 .unwrap_or_else(|| "unknown".to_string());
-                            let func_ref = self.lookup_function_reference(&function_name);
 
                             // Build arguments directly
-                            let mut args = Vec::new();
-                            for arg_expr in func_ctx.expression_all() {
-                                let expr = self.build_expression(&arg_expr);
-                                args.push(::substrait::proto::FunctionArgument {
-                                    arg_type: Some(
-                                        ::substrait::proto::function_argument::ArgType::Value(expr),
-                                    ),
-                                });
-                            }
+                            let args: Vec<_> = func_ctx
+                                .expression_all()
+                                .iter()
+                                .map(|arg_expr| self.build_function_argument(arg_expr))
+                                .collect();
+                            let (func_ref, derived_type) = self.resolve_function_reference(
+                                &func_ctx.start(),
+                                &function_name,
+                                FunctionKind::Aggregate,
+                                &args,
+                            );
+                            let options = self.build_function_options(func_ctx);
 
                             // Extract output type if present
                             let out_type = if let Some(type_ctx) = func_ctx.literal_complex_type() {
@@ -2543,8 +3957,14 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
                             } else {
                                 None
                             };
+                            let out_type = self.function_output_type(
+                                &func_ctx.start(),
+                                &function_name,
+                                out_type,
+                                derived_type,
+                            );
 
-                            (func_ref, args, out_type)
+                            (func_ref, args, out_type, options)
                         }
                         _ => {
                             // For non-function expressions, wrap in arguments
@@ -2556,7 +3976,7 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
                                     ),
                                 ),
                             };
-                            (0, vec![arg], None)
+                            (0, vec![arg], None, Vec::new())
                         }
                     };
 
@@ -2565,6 +3985,7 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
                         function_reference,
                         arguments,
                         output_type,
+                        options,
                         phase: ::substrait::proto::AggregationPhase::InitialToResult.into(),
                         invocation: invocation.into(),
                         ..Default::default()
@@ -2772,8 +4193,16 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
                         }
                     }
                 } else {
-                    // Schema not found yet (might be defined later in textplan)
-                    // Store the name for later resolution in save_binary
+                    // Plans may leave the schema to be provided elsewhere, so this is not an
+                    // error.  Store the name for later resolution in save_binary
+                    self.report_unresolved_name(
+                        Severity::Warning,
+                        DiagnosticCode::InvalidPlan,
+                        token_to_location(&schema_id.start()),
+                        format!("Schema '{}' is not defined", schema_name),
+                        self.symbol_table
+                            .suggest_symbol_name(&schema_name, SymbolType::Schema),
+                    );
                     if let Some(blob_lock) = &relation_symbol.blob {
                         if let Ok(mut blob_data) = blob_lock.lock() {
                             if let Some(relation_data) = blob_data.downcast_mut::<crate::textplan::common::structured_symbol_data::RelationData>() {
@@ -2806,6 +4235,15 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
                                 }
                             }
                         }
+                    } else {
+                        self.report_unresolved_name(
+                            Severity::Warning,
+                            DiagnosticCode::InvalidPlan,
+                            token_to_location(&source_id.start()),
+                            format!("Source '{}' is not defined", source_name),
+                            self.symbol_table
+                                .suggest_symbol_name(&source_name, SymbolType::Source),
+                        );
                     }
                 }
             }
@@ -2832,7 +4270,11 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
                 let field_name = column_name_ctx.get_text();
 
                 // Look up the field index in the current relation's field space
-                let field_index = self.lookup_field_index(&field_name);
+                let Some(field_index) = self.lookup_field_index(&field_name) else {
+                    self.note_unresolved_column(&column_name_ctx.start(), &field_name);
+                    self.processing_emit = false;
+                    return;
+                };
 
                 println!(
                     "  Emit field '{}' at index {} in relation '{}'",
@@ -2928,6 +4370,283 @@ impl<'input> SubstraitPlanParserVisitor<'input> for RelationVisitor<'input> {
         self.visit_children(ctx);
     }
 
+    fn visit_relationAdvancedExtension(&mut self, ctx: &RelationAdvancedExtensionContext<'input>) {
+        // Handle ADVANCED_EXTENSION { detail* } which populates the relation's advanced_extension
+        // A bare `ADVANCED_EXTENSION;` carries no payload and leaves the relation untouched.
+        let extension_ctx = ctx
+            .advanced_extension()
+            .filter(|extension_ctx| !extension_ctx.advanced_extension_detail_all().is_empty());
+        if let (Some(relation_symbol), Some(extension_ctx)) =
+            (self.current_relation_scope().cloned(), extension_ctx)
+        {
+            let errors = relation_symbol
+                .with_blob::<RelationData, _, _>(|relation_data| {
+                    let extension =
+                        crate::textplan::common::advanced_extension::rel_advanced_extension_mut(
+                            &mut relation_data.relation,
+                        )?;
+                    Some(add_advanced_extension_details(
+                        &extension_ctx,
+                        extension.get_or_insert_with(Default::default),
+                    ))
+                })
+                .flatten();
+            match errors {
+                Some(errors) => {
+                    for message in errors {
+                        self.add_error(&extension_ctx.start(), &message);
+                    }
+                }
+                None => self.add_error(
+                    &ctx.start(),
+                    &format!(
+                        "Relation '{}' does not support advanced extensions",
+                        relation_symbol.name()
+                    ),
+                ),
+            }
+        }
+        self.visit_children(ctx);
+    }
+
+    fn visit_parameter_binding(&mut self, ctx: &Parameter_bindingContext<'input>) {
+        // The MainPlanVisitor defined the parameter and its type, here the bound value is built.
+        let Some(constant_ctx) = ctx.constant() else {
+            return;
+        };
+        let name = ctx.PARAMETER().map(|p| p.get_text()).unwrap_or_default();
+        let Some(symbol) = self.symbol_table().lookup_symbol_by_name(&name) else {
+            return;
+        };
+        let declared_type = symbol
+            .with_blob::<ParameterData, _, _>(|data| data.parameter_type.clone())
+            .flatten();
+        let value = self.build_literal(&constant_ctx, declared_type.as_ref());
+
+        let already_bound = symbol
+            .with_blob::<ParameterData, _, _>(|data| {
+                let already_bound = data.value.is_some();
+                if data.parameter_type.is_none() {
+                    data.parameter_type = type_utils::literal_to_type(&value);
+                }
+                data.value = Some(value);
+                already_bound
+            })
+            .unwrap_or(false);
+        if already_bound {
+            self.add_error(
+                &ctx.start(),
+                &format!("Parameter {} is bound more than once", name),
+            );
+        }
+    }
+
+    fn visit_relationExtensionDetail(&mut self, ctx: &RelationExtensionDetailContext<'input>) {
+        // Handle DETAIL STRING STRING? SEMICOLON for extension relations
+        // The first string is the type URL and the optional second one the serialized message.
+        if let Some(relation_symbol) = self.current_relation_scope().cloned() {
+            let strings = ctx.STRING_all();
+            let type_url = strings
+                .first()
+                .map(|url| crate::textplan::common::string_utils::unescape_string(&url.get_text()))
+                .unwrap_or_default();
+            let value = match strings.get(1).map(|payload| {
+                crate::textplan::common::advanced_extension::parse_payload(
+                    &type_url,
+                    &payload.get_text(),
+                )
+            }) {
+                Some(Ok(value)) => value,
+                Some(Err(message)) => {
+                    self.add_error(&ctx.start(), &message);
+                    return;
+                }
+                None => Vec::new(),
+            };
+
+            let applied = relation_symbol
+                .with_blob::<RelationData, _, _>(|relation_data| {
+                    let detail = match &mut relation_data.relation.rel_type {
+                        Some(RelType::ExtensionLeaf(ext_rel)) => &mut ext_rel.detail,
+                        Some(RelType::ExtensionSingle(ext_rel)) => &mut ext_rel.detail,
+                        Some(RelType::ExtensionMulti(ext_rel)) => &mut ext_rel.detail,
+                        _ => return false,
+                    };
+                    let mut any = detail.take().unwrap_or_default();
+                    any.type_url = type_url;
+                    any.value = value.into();
+                    *detail = Some(any);
+                    true
+                })
+                .unwrap_or(false);
+            if !applied {
+                self.add_error(
+                    &ctx.start(),
+                    "DETAIL is only supported on extension relations",
+                );
+            }
+        }
+        self.visit_children(ctx);
+    }
+
+    fn visit_relationCommon(&mut self, ctx: &RelationCommonContext<'input>) {
+        // Handle COMMON { hint* } which populates RelCommon.hint on the relation
+        let hint_ctxs = ctx.common_hint_all();
+        if hint_ctxs.is_empty() {
+            self.visit_children(ctx);
+            return;
+        }
+
+        if let Some(relation_symbol) = self.current_relation_scope().cloned() {
+            let mut hint = ::substrait::proto::rel_common::Hint::default();
+            for hint_ctx in &hint_ctxs {
+                let name = hint_ctx.id().map(|id| id.get_text()).unwrap_or_default();
+                let values: Vec<String> = hint_ctx
+                    .common_hint_value_all()
+                    .iter()
+                    .map(|value_ctx| {
+                        if value_ctx.STRING().is_some() {
+                            crate::textplan::common::string_utils::unescape_string(
+                                &value_ctx.get_text(),
+                            )
+                        } else {
+                            value_ctx.get_text()
+                        }
+                    })
+                    .collect();
+                if let Err(message) = apply_common_hint(&mut hint, &name, &values) {
+                    self.add_error(&hint_ctx.start(), &message);
+                }
+            }
+
+            let applied = relation_symbol
+                .with_blob::<RelationData, _, _>(|relation_data| {
+                    let common = crate::textplan::common::rel_common::rel_common_mut(
+                        &mut relation_data.relation,
+                    )?;
+                    common
+                        .get_or_insert_with(|| ::substrait::proto::RelCommon {
+                            emit_kind: Some(::substrait::proto::rel_common::EmitKind::Direct(
+                                ::substrait::proto::rel_common::Direct {},
+                            )),
+                            ..Default::default()
+                        })
+                        .hint = Some(hint);
+                    Some(())
+                })
+                .flatten()
+                .is_some();
+            if !applied {
+                self.add_error(
+                    &ctx.start(),
+                    &format!(
+                        "Relation '{}' does not support common hints",
+                        relation_symbol.name()
+                    ),
+                );
+            }
+        }
+        self.visit_children(ctx);
+    }
+
+    fn visit_relationProjection(&mut self, ctx: &RelationProjectionContext<'input>) {
+        // Handle PROJECTION mask_item (COMMA mask_item)* SEMICOLON for Read relations
+        // The mask is recorded by name and turned into field positions in save_binary.
+        if let Some(relation_symbol) = self.current_relation_scope().cloned() {
+            let is_read = relation_symbol
+                .with_blob::<RelationData, _, _>(|relation_data| {
+                    matches!(relation_data.relation.rel_type, Some(RelType::Read(_)))
+                })
+                .unwrap_or(false);
+            if !is_read {
+                self.add_error(
+                    &ctx.start(),
+                    "PROJECTION is only supported on read relations",
+                );
+                self.visit_children(ctx);
+                return;
+            }
+
+            // Make sure the schema columns are available for the lookups below
+            self.add_input_fields_to_schema(&relation_symbol);
+            let has_schema_columns = relation_symbol
+                .with_blob::<RelationData, _, _>(|relation_data| {
+                    !relation_data.field_references.is_empty()
+                })
+                .unwrap_or(false);
+            if !has_schema_columns {
+                // Without the columns the relation's output cannot be narrowed to the
+                // projected ones for the relations that follow
+                self.add_error(
+                    &ctx.start(),
+                    &format!(
+                        "PROJECTION requires the base schema of '{}' to be defined before it",
+                        relation_symbol.name()
+                    ),
+                );
+                self.visit_children(ctx);
+                return;
+            }
+
+            let mut projected_columns = Vec::new();
+            let mut projected_fields = Vec::new();
+            for mask_item_ctx in ctx.mask_item_all() {
+                let column_name = mask_item_ctx
+                    .column_name()
+                    .map(|c| c.get_text())
+                    .unwrap_or_default();
+                let child = mask_item_ctx
+                    .mask_select()
+                    .and_then(|select_ctx| self.build_mask_select(&select_ctx));
+                let field_symbol = relation_symbol
+                    .with_blob::<RelationData, _, _>(|relation_data| {
+                        let (schema_name, field_name) = match column_name.rfind('.') {
+                            Some(dot_pos) => (&column_name[..dot_pos], &column_name[dot_pos + 1..]),
+                            None => ("", column_name.as_str()),
+                        };
+                        relation_data
+                            .field_references
+                            .iter()
+                            .find(|field| {
+                                self.field_matches(field, field_name, schema_name, &column_name)
+                            })
+                            .cloned()
+                    })
+                    .flatten();
+
+                match field_symbol {
+                    Some(field) => {
+                        projected_columns.push((field.name().to_string(), child));
+                        projected_fields.push(field);
+                    }
+                    None => {
+                        self.add_error(
+                            &mask_item_ctx.start(),
+                            &format!(
+                                "Projected column '{}' is not part of the base schema of '{}'",
+                                column_name,
+                                relation_symbol.name()
+                            ),
+                        );
+                    }
+                }
+            }
+
+            if let Some(blob_lock) = &relation_symbol.blob {
+                if let Ok(mut blob_data) = blob_lock.lock() {
+                    if let Some(relation_data) = blob_data.downcast_mut::<RelationData>() {
+                        relation_data.projected_columns.extend(projected_columns);
+                        // Only the projected columns are visible to downstream relations
+                        relation_data
+                            .output_field_references
+                            .extend(projected_fields);
+                    }
+                }
+            }
+        }
+        self.visit_children(ctx);
+    }
+
     // We use the default implementation for other visitor methods,
     // which will call visit_children to traverse the tree
 }
@@ -0,0 +1,461 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Type checking of the expressions of relations.
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use antlr_rust::parser_rule_context::ParserRuleContext;
+use antlr_rust::tree::{ParseTree, ParseTreeVisitor};
+
+use crate::textplan::common::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::textplan::common::extension_registry::type_signature_code;
+use crate::textplan::common::relation_schema::{self, RelationSchema};
+use crate::textplan::common::structured_symbol_data::{FunctionData, RelationData};
+use crate::textplan::common::type_utils;
+use crate::textplan::parser::antlr::substraitplanparser::*;
+use crate::textplan::parser::antlr::substraitplanparservisitor::SubstraitPlanParserVisitor;
+use crate::textplan::parser::error_listener::ErrorListener;
+use crate::textplan::printer::expression_printer::ExpressionPrinter;
+use crate::textplan::symbol_table::{SymbolInfo, SymbolTable, SymbolType};
+use ::substrait::proto::expression::subquery::SubqueryType;
+use ::substrait::proto::expression::RexType;
+use ::substrait::proto::function_argument::ArgType;
+use ::substrait::proto::rel::RelType;
+use ::substrait::proto::{Expression, Type};
+
+use super::{tokens_to_location, PlanVisitor};
+
+/// The TypeCheckVisitor verifies the types of the expressions built by the
+/// RelationVisitor.
+///
+/// This visitor runs after the relations are complete so that the types of the columns
+/// of every relation's input are known.  It checks that conditions are boolean, that the
+/// arguments of functions match the signatures they were declared with, and that casts
+/// are between compatible types.  Expressions whose types are unknown are not checked.
+pub struct TypeCheckVisitor<'input> {
+    symbol_table: SymbolTable,
+    error_listener: Arc<ErrorListener>,
+    // The relation being visited along with the schema its expressions are evaluated on.
+    current_relation: Option<(Arc<SymbolInfo>, RelationSchema)>,
+    // The number of expressions, groupings and measures of the current relation visited
+    // so far.
+    expression_count: usize,
+    grouping_count: usize,
+    measure_count: usize,
+    _phantom: std::marker::PhantomData<&'input ()>,
+}
+
+impl<'input> TypeCheckVisitor<'input> {
+    /// Creates a new TypeCheckVisitor.
+    pub fn new(symbol_table: SymbolTable, error_listener: Arc<ErrorListener>) -> Self {
+        Self {
+            symbol_table,
+            error_listener,
+            current_relation: None,
+            expression_count: 0,
+            grouping_count: 0,
+            measure_count: 0,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Gets the symbol table.
+    pub fn symbol_table(&self) -> SymbolTable {
+        self.symbol_table.clone()
+    }
+
+    /// Gets the error listener.
+    pub fn error_listener(&self) -> Arc<ErrorListener> {
+        self.error_listener.clone()
+    }
+
+    /// Reports a type mismatch covering the given expression.
+    fn add_mismatch(&self, ctx: &Rc<ExpressionContextAll<'input>>, message: &str) {
+        let location = tokens_to_location(&ctx.start(), &ctx.stop());
+        self.error_listener.add_diagnostic(Diagnostic::error(
+            DiagnosticCode::TypeMismatch,
+            message,
+            location,
+        ));
+    }
+
+    fn type_text(&self, type_val: &Type) -> String {
+        ExpressionPrinter::new(&self.symbol_table, None)
+            .print_type(type_val)
+            .unwrap_or_else(|_| type_utils::type_kind_name(type_val).to_string())
+    }
+
+    /// Returns a copy of the current relation's proto.
+    fn current_rel(&self) -> Option<::substrait::proto::Rel> {
+        let (relation_symbol, _) = self.current_relation.as_ref()?;
+        relation_symbol.with_blob::<RelationData, _, _>(|data| data.relation.clone())
+    }
+
+    /// Returns the type of an expression of the current relation, if it is known.
+    fn expression_type(&self, expression: &Expression) -> Option<Type> {
+        let (_, input_schema) = self.current_relation.as_ref()?;
+        relation_schema::expression_type(expression, input_schema)
+    }
+
+    /// Returns the declared name (such as `add:i64_i64`) of the function with the anchor.
+    fn function_declaration(&self, anchor: u32) -> Option<String> {
+        self.symbol_table
+            .symbols()
+            .iter()
+            .filter(|symbol| symbol.symbol_type() == SymbolType::Function)
+            .find_map(|symbol| {
+                symbol
+                    .with_blob::<FunctionData, _, _>(|data| {
+                        (data.anchor == anchor).then(|| data.name.clone())
+                    })
+                    .flatten()
+            })
+    }
+
+    /// Checks an expression used as a condition, which must be boolean.
+    fn check_condition(
+        &self,
+        ctx: &Rc<ExpressionContextAll<'input>>,
+        expression: &Expression,
+        usage: &str,
+    ) {
+        self.check_expression(ctx, expression);
+        if let Some(condition_type) = self.expression_type(expression) {
+            if type_utils::type_kind_name(&condition_type) != "bool" {
+                self.add_mismatch(
+                    ctx,
+                    &format!(
+                        "{} must be boolean but is {}",
+                        usage,
+                        self.type_text(&condition_type)
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Checks the function calls and casts of an expression against the parse tree it was
+    /// built from.
+    fn check_expression(&self, ctx: &Rc<ExpressionContextAll<'input>>, expression: &Expression) {
+        match (ctx.as_ref(), &expression.rex_type) {
+            (
+                ExpressionContextAll::ExpressionFunctionUseContext(function_ctx),
+                Some(RexType::ScalarFunction(function)),
+            ) => {
+                self.check_call(
+                    function_ctx,
+                    function.function_reference,
+                    &function.arguments,
+                );
+            }
+            (
+                ExpressionContextAll::ExpressionFunctionUseContext(function_ctx),
+                Some(RexType::IfThen(if_then)),
+            ) => {
+                // The arguments are the pairs of conditions and results followed by the
+                // result used otherwise
+                let argument_ctxs = function_ctx.expression_all();
+                let mut argument_ctxs = argument_ctxs.iter();
+                for clause in &if_then.ifs {
+                    let (Some(if_ctx), Some(then_ctx)) =
+                        (argument_ctxs.next(), argument_ctxs.next())
+                    else {
+                        return;
+                    };
+                    if let Some(condition) = &clause.r#if {
+                        self.check_condition(if_ctx, condition, "An if condition");
+                    }
+                    if let Some(then) = &clause.then {
+                        self.check_expression(then_ctx, then);
+                    }
+                }
+                if let (Some(else_ctx), Some(otherwise)) = (argument_ctxs.next(), &if_then.r#else) {
+                    self.check_expression(else_ctx, otherwise);
+                }
+            }
+            (
+                ExpressionContextAll::ExpressionInPredicateSubqueryContext(predicate_ctx),
+                Some(RexType::Subquery(subquery)),
+            ) => {
+                let Some(SubqueryType::InPredicate(predicate)) = &subquery.subquery_type else {
+                    return;
+                };
+                let needle_ctxs = predicate_ctx
+                    .expression_list()
+                    .map(|list_ctx| list_ctx.expression_all())
+                    .unwrap_or_default();
+                for (needle_ctx, needle) in needle_ctxs.iter().zip(&predicate.needles) {
+                    self.check_expression(needle_ctx, needle);
+                }
+            }
+            (
+                ExpressionContextAll::ExpressionSetComparisonSubqueryContext(comparison_ctx),
+                Some(RexType::Subquery(subquery)),
+            ) => {
+                let Some(SubqueryType::SetComparison(comparison)) = &subquery.subquery_type else {
+                    return;
+                };
+                if let (Some(left_ctx), Some(left)) =
+                    (comparison_ctx.expression(), &comparison.left)
+                {
+                    self.check_expression(&left_ctx, left);
+                }
+            }
+            (ExpressionContextAll::ExpressionCastContext(cast_ctx), Some(RexType::Cast(cast))) => {
+                let (Some(input_ctx), Some(input)) = (cast_ctx.expression(), &cast.input) else {
+                    return;
+                };
+                self.check_expression(&input_ctx, input);
+                let (Some(from), Some(to)) = (self.expression_type(input), &cast.r#type) else {
+                    return;
+                };
+                if !type_utils::can_cast(&from, to) {
+                    self.add_mismatch(
+                        ctx,
+                        &format!(
+                            "Cannot cast {} to {}",
+                            self.type_text(&from),
+                            self.type_text(to)
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks the arguments of a scalar or aggregate function call, and the expressions
+    /// within them.
+    fn check_call(
+        &self,
+        function_ctx: &ExpressionFunctionUseContext<'input>,
+        function_reference: u32,
+        arguments: &[::substrait::proto::FunctionArgument],
+    ) {
+        let argument_ctxs = function_ctx.expression_all();
+        if argument_ctxs.len() != arguments.len() {
+            return;
+        }
+        for (argument_ctx, argument) in argument_ctxs.iter().zip(arguments) {
+            if let Some(ArgType::Value(value)) = &argument.arg_type {
+                self.check_expression(argument_ctx, value);
+            }
+        }
+        let function_name = function_ctx
+            .id()
+            .map(|id| id.get_text())
+            .unwrap_or_default();
+        self.check_arguments(
+            &function_name,
+            function_reference,
+            &argument_ctxs,
+            arguments,
+        );
+    }
+
+    /// Checks the types of a call's arguments against the signature the function was
+    /// declared with (e.g. `add:i64_i64`).
+    ///
+    /// Calls are only checked when they have one argument for each type of the signature,
+    /// so variadic functions and functions with optional enumeration arguments are skipped.
+    fn check_arguments(
+        &self,
+        function_name: &str,
+        function_reference: u32,
+        argument_ctxs: &[Rc<ExpressionContextAll<'input>>],
+        arguments: &[::substrait::proto::FunctionArgument],
+    ) {
+        let Some(declaration) = self.function_declaration(function_reference) else {
+            return;
+        };
+        let Some((_, signature)) = declaration.split_once(':') else {
+            return;
+        };
+        let codes: Vec<&str> = signature.split('_').collect();
+        if codes.len() != arguments.len() {
+            return;
+        }
+
+        for (position, ((code, argument_ctx), argument)) in
+            codes.iter().zip(argument_ctxs).zip(arguments).enumerate()
+        {
+            if code.starts_with("any") || code.starts_with("u!") || *code == "req" || *code == "opt"
+            {
+                continue;
+            }
+            let Some(ArgType::Value(value)) = &argument.arg_type else {
+                continue;
+            };
+            let Some(argument_type) = self.expression_type(value) else {
+                continue;
+            };
+            let argument_code = type_signature_code(&argument_type);
+            if matches!(
+                argument_code.as_str(),
+                "unsupported" | "unknown" | "user_defined"
+            ) {
+                continue;
+            }
+            if argument_code != *code {
+                self.add_mismatch(
+                    argument_ctx,
+                    &format!(
+                        "Argument {} of '{}' is {} but '{}' expects {}",
+                        position + 1,
+                        function_name,
+                        self.type_text(&argument_type),
+                        declaration,
+                        code
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl<'input> PlanVisitor<'input> for TypeCheckVisitor<'input> {
+    fn error_listener(&self) -> Arc<ErrorListener> {
+        self.error_listener.clone()
+    }
+
+    fn symbol_table(&self) -> SymbolTable {
+        self.symbol_table.clone()
+    }
+}
+
+// ANTLR visitor implementation for TypeCheckVisitor
+impl<'input> ParseTreeVisitor<'input, SubstraitPlanParserContextType> for TypeCheckVisitor<'input> {}
+
+impl<'input> SubstraitPlanParserVisitor<'input> for TypeCheckVisitor<'input> {
+    fn visit_relation(&mut self, ctx: &RelationContext<'input>) {
+        let symbol = ctx
+            .relation_ref()
+            .and_then(|relation_ref| relation_ref.id(0))
+            .and_then(|id| self.symbol_table.lookup_symbol_by_name(&id.get_text()));
+        let Some(relation_symbol) = symbol else {
+            return;
+        };
+
+        // Without an input schema only literals, casts and typed calls can be checked
+        let input_schema =
+            relation_schema::relation_input_schema(&self.symbol_table, &relation_symbol)
+                .unwrap_or_default();
+        self.current_relation = Some((relation_symbol, input_schema));
+        self.expression_count = 0;
+        self.grouping_count = 0;
+        self.measure_count = 0;
+
+        self.visit_children(ctx);
+
+        self.current_relation = None;
+    }
+
+    fn visit_relationFilter(&mut self, ctx: &RelationFilterContext<'input>) {
+        let (Some(expr_ctx), Some(rel)) = (ctx.expression(), self.current_rel()) else {
+            return;
+        };
+        let best_effort = ctx
+            .relation_filter_behavior()
+            .is_some_and(|behavior| behavior.get_text().to_lowercase().contains("best"));
+        let condition = match &rel.rel_type {
+            Some(RelType::Filter(filter_rel)) => filter_rel.condition.as_deref(),
+            Some(RelType::Read(read_rel)) if best_effort => read_rel.best_effort_filter.as_deref(),
+            Some(RelType::Read(read_rel)) => read_rel.filter.as_deref(),
+            _ => None,
+        };
+        if let Some(condition) = condition {
+            self.check_condition(&expr_ctx, condition, "A filter condition");
+        }
+    }
+
+    fn visit_relationExpression(&mut self, ctx: &RelationExpressionContext<'input>) {
+        let (Some(expr_ctx), Some(rel)) = (ctx.expression(), self.current_rel()) else {
+            return;
+        };
+        match &rel.rel_type {
+            Some(RelType::Project(project_rel)) => {
+                if let Some(expression) = project_rel.expressions.get(self.expression_count) {
+                    self.check_expression(&expr_ctx, expression);
+                }
+                self.expression_count += 1;
+            }
+            Some(RelType::Join(join_rel)) => {
+                if let Some(expression) = &join_rel.expression {
+                    self.check_condition(&expr_ctx, expression, "A join condition");
+                }
+            }
+            Some(RelType::HashJoin(join_rel)) => {
+                if let Some(expression) = &join_rel.post_join_filter {
+                    self.check_condition(&expr_ctx, expression, "A join condition");
+                }
+            }
+            Some(RelType::MergeJoin(join_rel)) => {
+                if let Some(expression) = &join_rel.post_join_filter {
+                    self.check_condition(&expr_ctx, expression, "A join condition");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[allow(deprecated)]
+    fn visit_relationGrouping(&mut self, ctx: &RelationGroupingContext<'input>) {
+        let (Some(expr_ctx), Some(rel)) = (ctx.expression(), self.current_rel()) else {
+            return;
+        };
+        if let Some(RelType::Aggregate(aggregate_rel)) = &rel.rel_type {
+            if let Some(expression) = aggregate_rel
+                .groupings
+                .first()
+                .and_then(|grouping| grouping.grouping_expressions.get(self.grouping_count))
+            {
+                self.check_expression(&expr_ctx, expression);
+            }
+            self.grouping_count += 1;
+        }
+    }
+
+    fn visit_relationMeasure(&mut self, ctx: &RelationMeasureContext<'input>) {
+        let Some(RelType::Aggregate(aggregate_rel)) =
+            self.current_rel().and_then(|rel| rel.rel_type)
+        else {
+            return;
+        };
+        // The RelationVisitor builds a measure for each detail with an expression
+        for measure_detail_ctx in ctx.measure_detail_all() {
+            let Some(expr_ctx) = measure_detail_ctx.expression() else {
+                continue;
+            };
+            let measure = aggregate_rel
+                .measures
+                .get(self.measure_count)
+                .and_then(|measure| measure.measure.as_ref());
+            self.measure_count += 1;
+            let Some(function) = measure else {
+                continue;
+            };
+            match expr_ctx.as_ref() {
+                ExpressionContextAll::ExpressionFunctionUseContext(function_ctx) => {
+                    self.check_call(
+                        function_ctx,
+                        function.function_reference,
+                        &function.arguments,
+                    );
+                }
+                _ => {
+                    if let Some(ArgType::Value(value)) = function
+                        .arguments
+                        .first()
+                        .and_then(|argument| argument.arg_type.as_ref())
+                    {
+                        self.check_expression(&expr_ctx, value);
+                    }
+                }
+            }
+        }
+    }
+
+    // We use the default implementation for other visitor methods,
+    // which will call visit_children to traverse the tree
+}
@@ -20,7 +20,7 @@ use ::substrait::proto::r#type::{
 };
 use ::substrait::proto::Type;
 
-use super::{token_to_location, BasePlanVisitor, PlanVisitor};
+use super::{lookup_or_define_parameter, token_to_location, BasePlanVisitor, PlanVisitor};
 
 /// The TypeVisitor processes and validates types in the parse tree.
 ///
@@ -125,7 +125,8 @@ impl<'input> TypeVisitor<'input> {
             let field_types = self.split_struct_fields(struct_content);
 
             for field_type_str in field_types {
-                let field_type = self.text_to_type_proto(ctx, field_type_str.trim());
+                let (_, field_type_str) = split_field_name(&field_type_str);
+                let field_type = self.text_to_type_proto(ctx, field_type_str);
                 struct_type.types.push(field_type);
             }
 
@@ -136,9 +137,11 @@ impl<'input> TypeVisitor<'input> {
             .and_then(|s| s.strip_suffix(">"))
         {
             // Map type - format: map<key_type, value_type>
-            if let Some((key_type_str, value_type_str)) = map_content.split_once(',') {
-                let key_type = self.text_to_type_proto(ctx, key_type_str.trim());
-                let value_type = self.text_to_type_proto(ctx, value_type_str.trim());
+            // Split respecting nesting so that value types such as struct<i32,i64> stay whole
+            if let [key_type_str, value_type_str] = self.split_struct_fields(map_content).as_slice()
+            {
+                let key_type = self.text_to_type_proto(ctx, key_type_str);
+                let value_type = self.text_to_type_proto(ctx, value_type_str);
 
                 let mut map_type = Map::default();
                 map_type.nullability = nullability.into();
@@ -398,6 +401,37 @@ impl<'input> TypeVisitor<'input> {
         result
     }
 
+    /// Returns the names of the nested struct fields of a type in depth first order, as
+    /// listed by a named struct.
+    ///
+    /// Struct fields without a name (as in `struct<i32>` rather than `struct<id: i32>`)
+    /// are named after their position (`field0`).
+    pub fn nested_field_names(&self, type_text: &str) -> Vec<String> {
+        let mut names = Vec::new();
+        let Some((base_type, content)) = type_text
+            .strip_suffix('>')
+            .and_then(|text| text.split_once('<'))
+        else {
+            return names;
+        };
+        match base_type.trim_end_matches('?') {
+            "struct" => {
+                for (index, field) in self.split_struct_fields(content).iter().enumerate() {
+                    let (name, field_type) = split_field_name(field);
+                    names.push(name.map_or_else(|| format!("field{}", index), str::to_string));
+                    names.extend(self.nested_field_names(field_type));
+                }
+            }
+            "list" | "map" => {
+                for element_type in self.split_struct_fields(content) {
+                    names.extend(self.nested_field_names(&element_type));
+                }
+            }
+            _ => {}
+        }
+        names
+    }
+
     /// Determines if the context is inside a struct literal with an external type.
     pub fn inside_struct_literal_with_external_type(
         &self,
@@ -419,6 +453,22 @@ impl<'input> TypeVisitor<'input> {
             .error_listener()
             .add_error(message.to_string(), location);
     }
+
+    /// Defines a numbered parameter such as `$2` so that its anchor is taken before any
+    /// named parameter is given one by the later phases.
+    fn reserve_numbered_parameter<'a>(
+        &mut self,
+        token: &impl std::ops::Deref<Target = GenericToken<std::borrow::Cow<'a, str>>>,
+        name: &str,
+    ) {
+        if name.trim_start_matches('$').parse::<u32>().is_err() {
+            return;
+        }
+        let location = token_to_location(token);
+        if let Err(message) = lookup_or_define_parameter(self.symbol_table_mut(), name, location) {
+            self.add_error(token, &message);
+        }
+    }
 }
 
 impl<'input> PlanVisitor<'input> for TypeVisitor<'input> {
@@ -431,6 +481,14 @@ impl<'input> PlanVisitor<'input> for TypeVisitor<'input> {
     }
 }
 
+/// Splits the name from a struct field type such as `city:string`.
+fn split_field_name(field: &str) -> (Option<&str>, &str) {
+    match field.split_once(':') {
+        Some((name, field_type)) if !name.contains('<') => (Some(name.trim()), field_type.trim()),
+        _ => (None, field.trim()),
+    }
+}
+
 // ANTLR visitor implementation for TypeVisitor
 impl<'input> ParseTreeVisitor<'input, SubstraitPlanParserContextType> for TypeVisitor<'input> {}
 
@@ -456,6 +514,20 @@ impl<'input> SubstraitPlanParserVisitor<'input> for TypeVisitor<'input> {
         self.visit_children(ctx);
     }
 
+    fn visit_expressionParameter(&mut self, ctx: &ExpressionParameterContext<'input>) {
+        if let Some(parameter) = ctx.PARAMETER() {
+            self.reserve_numbered_parameter(&ctx.start(), &parameter.get_text());
+        }
+        self.visit_children(ctx);
+    }
+
+    fn visit_parameter_binding(&mut self, ctx: &Parameter_bindingContext<'input>) {
+        if let Some(parameter) = ctx.PARAMETER() {
+            self.reserve_numbered_parameter(&ctx.start(), &parameter.get_text());
+        }
+        self.visit_children(ctx);
+    }
+
     // We use the default implementation for other visitor methods,
     // which will call visit_children to traverse the entire tree
 }
@@ -5,7 +5,11 @@
 use std::sync::Arc;
 
 use crate::textplan::common::error::TextPlanError;
+use crate::textplan::common::projection_mask;
+use crate::textplan::common::string_utils;
 use crate::textplan::common::structured_symbol_data::{FunctionData, RelationData};
+use crate::textplan::common::temporal;
+use crate::textplan::common::type_utils;
 use crate::textplan::symbol_table::{SymbolInfo, SymbolTable, SymbolType};
 
 /// A printer for converting Substrait proto expressions to textplan format.
@@ -60,10 +64,16 @@ impl<'a> ExpressionPrinter<'a> {
             Some(RexType::Cast(cast)) => self.print_cast(cast),
             Some(RexType::Subquery(subquery)) => self.print_subquery(subquery),
             Some(RexType::Nested(_)) => Ok("NESTED_NOT_YET_IMPLEMENTED".to_string()),
+            // Enums only have a textual form as function arguments (see print_legacy_argument)
             Some(RexType::Enum(_)) => Ok("ENUM_NOT_YET_IMPLEMENTED".to_string()),
-            Some(RexType::DynamicParameter(_)) => {
-                Ok("DYNAMIC_PARAMETER_NOT_YET_IMPLEMENTED".to_string())
-            }
+            Some(RexType::DynamicParameter(parameter)) => match &parameter.r#type {
+                Some(parameter_type) => Ok(format!(
+                    "${}:{}",
+                    parameter.parameter_reference,
+                    self.print_type(parameter_type)?
+                )),
+                None => Ok(format!("${}", parameter.parameter_reference)),
+            },
             None => Err(TextPlanError::InvalidExpression(
                 "Expression has no rex_type".to_string(),
             )),
@@ -77,7 +87,37 @@ impl<'a> ExpressionPrinter<'a> {
     ) -> Result<String, TextPlanError> {
         use ::substrait::proto::expression::literal::LiteralType;
 
-        let mut result = match &literal.literal_type {
+        let mut result = self.print_literal_value(literal)?;
+
+        // Add nullable marker if needed (container literals carry it in their type suffix)
+        let is_container = matches!(
+            literal.literal_type,
+            Some(
+                LiteralType::Struct(_)
+                    | LiteralType::List(_)
+                    | LiteralType::Map(_)
+                    | LiteralType::EmptyList(_)
+                    | LiteralType::EmptyMap(_)
+            )
+        );
+        if literal.nullable && !is_container {
+            result.push('?');
+        }
+
+        Ok(result)
+    }
+
+    /// Prints a literal without its nullable marker.
+    ///
+    /// Elements of struct, list and map literals are printed this way as the container's type
+    /// suffix already declares their nullability.
+    fn print_literal_value(
+        &self,
+        literal: &::substrait::proto::expression::Literal,
+    ) -> Result<String, TextPlanError> {
+        use ::substrait::proto::expression::literal::LiteralType;
+
+        let result = match &literal.literal_type {
             Some(LiteralType::Boolean(b)) => b.to_string(),
             Some(LiteralType::I8(v)) => format!("{}_i8", v),
             Some(LiteralType::I16(v)) => format!("{}_i16", v),
@@ -86,10 +126,22 @@ impl<'a> ExpressionPrinter<'a> {
             Some(LiteralType::Fp32(v)) => format!("{}_fp32", v),
             Some(LiteralType::Fp64(v)) => format!("{}_fp64", v),
             Some(LiteralType::String(s)) => format!("\"{}\"", escape_string(s)),
-            Some(LiteralType::Binary(_)) => "BINARY_LITERAL_NOT_YET_IMPLEMENTED".to_string(),
-            Some(LiteralType::Timestamp(_)) => "TIMESTAMP_LITERAL_NOT_YET_IMPLEMENTED".to_string(),
-            Some(LiteralType::Date(days)) => format!("{}_date", days),
-            Some(LiteralType::Time(micros)) => format!("{}_time", micros),
+            Some(LiteralType::Binary(bytes)) => {
+                format!("x\"{}\"_binary", string_utils::encode_hex(bytes))
+            }
+            // Values the readable forms cannot express keep their raw form
+            Some(LiteralType::Timestamp(micros)) => match temporal::format_timestamp(*micros) {
+                Some(text) => format!("\"{}\"_timestamp", text),
+                None => format!("{}_timestamp", micros),
+            },
+            Some(LiteralType::Date(days)) => match temporal::format_date(*days) {
+                Some(text) => format!("\"{}\"_date", text),
+                None => format!("{}_date", days),
+            },
+            Some(LiteralType::Time(micros)) => match temporal::format_time(*micros) {
+                Some(text) => format!("\"{}\"_time", text),
+                None => format!("{}_time", micros),
+            },
             Some(LiteralType::IntervalYearToMonth(interval)) => {
                 // IntervalYearToMonth has years and months fields
                 // Use struct literal format for multiple components
@@ -131,11 +183,11 @@ impl<'a> ExpressionPrinter<'a> {
                     "\"\"_varchar".to_string()
                 }
             }
-            Some(LiteralType::FixedBinary(bytes)) => {
-                // Convert bytes to hex string manually
-                let hex_str: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
-                format!("0x{}_fixedbinary", hex_str)
-            }
+            Some(LiteralType::FixedBinary(bytes)) => format!(
+                "x\"{}\"_fixedbinary<{}>",
+                string_utils::encode_hex(bytes),
+                bytes.len()
+            ),
             Some(LiteralType::Decimal(dec)) => {
                 // Decimal is stored as 16 bytes (little-endian two's complement)
                 // Convert to i128 and then to string
@@ -148,16 +200,53 @@ impl<'a> ExpressionPrinter<'a> {
                 let value = i128::from_le_bytes(bytes);
                 format!("{}_decimal<{},{}>", value, dec.precision, dec.scale)
             }
-            Some(LiteralType::Struct(_)) => "STRUCT_LITERAL_NOT_YET_IMPLEMENTED".to_string(),
-            Some(LiteralType::Map(_)) => "MAP_LITERAL_NOT_YET_IMPLEMENTED".to_string(),
-            Some(LiteralType::TimestampTz(_)) => {
-                "TIMESTAMP_TZ_LITERAL_NOT_YET_IMPLEMENTED".to_string()
+            Some(LiteralType::Struct(value)) => {
+                let fields = value
+                    .fields
+                    .iter()
+                    .map(|field| self.print_literal_value(field))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.print_container_literal(literal, &fields.join(", "))?
+            }
+            Some(LiteralType::Map(value)) => {
+                let entries = value
+                    .key_values
+                    .iter()
+                    .map(|kv| {
+                        let key = match &kv.key {
+                            Some(key) => self.print_literal_value(key)?,
+                            None => "MISSING_MAP_KEY".to_string(),
+                        };
+                        let value = match &kv.value {
+                            Some(value) => self.print_literal_value(value)?,
+                            None => "MISSING_MAP_VALUE".to_string(),
+                        };
+                        Ok(format!("{}: {}", key, value))
+                    })
+                    .collect::<Result<Vec<_>, TextPlanError>>()?;
+                self.print_container_literal(literal, &entries.join(", "))?
+            }
+            Some(LiteralType::TimestampTz(micros)) => {
+                match temporal::format_timestamp_tz(*micros) {
+                    Some(text) => format!("\"{}\"_timestamp_tz", text),
+                    None => format!("{}_timestamp_tz", micros),
+                }
+            }
+            Some(LiteralType::Uuid(bytes)) => {
+                format!("\"{}\"_uuid", string_utils::format_uuid(bytes))
             }
-            Some(LiteralType::Uuid(_)) => "UUID_LITERAL_NOT_YET_IMPLEMENTED".to_string(),
             Some(LiteralType::Null(_)) => "NULL".to_string(),
-            Some(LiteralType::List(_)) => "LIST_LITERAL_NOT_YET_IMPLEMENTED".to_string(),
-            Some(LiteralType::EmptyList(_)) => "EMPTY_LIST_LITERAL_NOT_YET_IMPLEMENTED".to_string(),
-            Some(LiteralType::EmptyMap(_)) => "EMPTY_MAP_LITERAL_NOT_YET_IMPLEMENTED".to_string(),
+            Some(LiteralType::List(value)) => {
+                let values = value
+                    .values
+                    .iter()
+                    .map(|element| self.print_literal_value(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.print_container_literal(literal, &values.join(", "))?
+            }
+            Some(LiteralType::EmptyList(_)) | Some(LiteralType::EmptyMap(_)) => {
+                self.print_container_literal(literal, "")?
+            }
             Some(LiteralType::UserDefined(_)) => {
                 "USER_DEFINED_LITERAL_NOT_YET_IMPLEMENTED".to_string()
             }
@@ -180,14 +269,26 @@ impl<'a> ExpressionPrinter<'a> {
             }
         };
 
-        // Add nullable marker if needed
-        if literal.nullable {
-            result.push('?');
-        }
-
         Ok(result)
     }
 
+    /// Prints the braced contents of a struct, list or map literal followed by its type suffix.
+    fn print_container_literal(
+        &self,
+        literal: &::substrait::proto::expression::Literal,
+        contents: &str,
+    ) -> Result<String, TextPlanError> {
+        match type_utils::literal_to_type(literal) {
+            Some(literal_type) => Ok(format!(
+                "{{{}}}_{}",
+                contents,
+                self.print_type(&literal_type)?
+            )),
+            // Without a complete type the elements' own suffixes describe the literal
+            None => Ok(format!("{{{}}}", contents)),
+        }
+    }
+
     /// Prints a field reference (selection).
     fn print_field_reference(
         &self,
@@ -208,15 +309,36 @@ impl<'a> ExpressionPrinter<'a> {
                 };
                 self.print_direct_reference(direct_ref, outer_ref)
             }
-            Some(ReferenceType::MaskedReference(_)) => {
-                Ok("MASKED_REFERENCE_NOT_YET_IMPLEMENTED".to_string())
-            }
+            Some(ReferenceType::MaskedReference(mask)) => self.print_masked_reference(mask),
             None => Err(TextPlanError::InvalidExpression(
                 "FieldReference has no reference_type".to_string(),
             )),
         }
     }
 
+    /// Prints a masked reference as `mask{column{...}, ...}`.
+    fn print_masked_reference(
+        &self,
+        mask: &::substrait::proto::expression::MaskExpression,
+    ) -> Result<String, TextPlanError> {
+        let struct_items = mask
+            .select
+            .as_ref()
+            .map(|select| select.struct_items.as_slice())
+            .unwrap_or_default();
+        let mut items = Vec::new();
+        for item in struct_items {
+            let column = self.lookup_field_reference(item.field as usize, None)?;
+            let child = item
+                .child
+                .as_ref()
+                .map(projection_mask::format_select)
+                .unwrap_or_default();
+            items.push(format!("{}{}", column, child));
+        }
+        Ok(format!("mask{{{}}}", items.join(", ")))
+    }
+
     /// Prints a direct reference.
     fn print_direct_reference(
         &self,
@@ -228,7 +350,11 @@ impl<'a> ExpressionPrinter<'a> {
         match &direct_ref.reference_type {
             Some(ReferenceType::StructField(struct_field)) => {
                 let field_index = struct_field.field as usize;
-                self.lookup_field_reference(field_index, outer_ref)
+                let mut result = self.lookup_field_reference(field_index, outer_ref)?;
+                if let Some(child) = &struct_field.child {
+                    result.push_str(&self.print_nested_reference(child)?);
+                }
+                Ok(result)
             }
             Some(ReferenceType::MapKey(_)) => {
                 Ok("MAP_KEY_REFERENCE_NOT_YET_IMPLEMENTED".to_string())
@@ -242,6 +368,39 @@ impl<'a> ExpressionPrinter<'a> {
         }
     }
 
+    /// Prints the segments that drill into a nested column: `.N` for struct fields,
+    /// `[N]` for list elements and `[key]` for map keys.
+    fn print_nested_reference(
+        &self,
+        segment: &::substrait::proto::expression::ReferenceSegment,
+    ) -> Result<String, TextPlanError> {
+        use ::substrait::proto::expression::reference_segment::ReferenceType;
+
+        let (text, child) = match &segment.reference_type {
+            Some(ReferenceType::StructField(struct_field)) => {
+                (format!(".{}", struct_field.field), &struct_field.child)
+            }
+            Some(ReferenceType::ListElement(list_element)) => {
+                (format!("[{}]", list_element.offset), &list_element.child)
+            }
+            Some(ReferenceType::MapKey(map_key)) => {
+                let key = map_key.map_key.as_ref().ok_or_else(|| {
+                    TextPlanError::InvalidExpression("MapKey has no map_key".to_string())
+                })?;
+                (format!("[{}]", self.print_literal(key)?), &map_key.child)
+            }
+            None => {
+                return Err(TextPlanError::InvalidExpression(
+                    "ReferenceSegment has no reference_type".to_string(),
+                ))
+            }
+        };
+        match child {
+            Some(child) => Ok(format!("{}{}", text, self.print_nested_reference(child)?)),
+            None => Ok(text),
+        }
+    }
+
     /// Looks up a field reference in the current scope or outer scope.
     fn lookup_field_reference(
         &self,
@@ -447,7 +606,7 @@ impl<'a> ExpressionPrinter<'a> {
 
         // Print args (older protobuf style, for compatibility)
         for arg in &func.args {
-            let arg_str = self.print_expression(arg)?;
+            let arg_str = self.print_legacy_argument(arg)?;
             // Add comma/space separator for non-first arguments
             if !first {
                 result.push_str(", ");
@@ -458,6 +617,9 @@ impl<'a> ExpressionPrinter<'a> {
 
         result.push(')');
 
+        // Add options (if any)
+        result.push_str(&Self::print_function_options(&func.options));
+
         // Add return type annotation
         if let Some(output_type) = &func.output_type {
             result.push_str("->");
@@ -467,6 +629,43 @@ impl<'a> ExpressionPrinter<'a> {
         Ok(result)
     }
 
+    /// Prints an argument given in the deprecated `args` field of a function.
+    ///
+    /// Enum arguments are printed as `X_enum`, which only parses in argument position.
+    fn print_legacy_argument(
+        &mut self,
+        arg: &::substrait::proto::Expression,
+    ) -> Result<String, TextPlanError> {
+        use ::substrait::proto::expression::r#enum::EnumKind;
+        use ::substrait::proto::expression::RexType;
+
+        match &arg.rex_type {
+            Some(RexType::Enum(enum_val)) => match &enum_val.enum_kind {
+                Some(EnumKind::Specified(value)) => Ok(format!("{}_enum", value)),
+                _ => Ok("ENUM_NOT_YET_IMPLEMENTED".to_string()),
+            },
+            _ => self.print_expression(arg),
+        }
+    }
+
+    /// Prints function options as `[name: PREFERENCE, ...]`.
+    /// Each preference of an option is printed as its own `name: PREFERENCE` entry.
+    fn print_function_options(options: &[::substrait::proto::FunctionOption]) -> String {
+        let entries: Vec<String> = options
+            .iter()
+            .flat_map(|option| {
+                option
+                    .preference
+                    .iter()
+                    .map(move |pref| format!("{}: {}", option.name, pref))
+            })
+            .collect();
+        if entries.is_empty() {
+            return String::new();
+        }
+        format!("[{}]", entries.join(", "))
+    }
+
     /// Prints an aggregate function.
     pub fn print_aggregate_function(
         &mut self,
@@ -515,7 +714,7 @@ impl<'a> ExpressionPrinter<'a> {
 
         // Print args (older protobuf style, for compatibility)
         for arg in &func.args {
-            let arg_str = self.print_expression(arg)?;
+            let arg_str = self.print_legacy_argument(arg)?;
             // Add comma/space separator for non-first arguments
             if !first {
                 result.push_str(", ");
@@ -527,14 +726,7 @@ impl<'a> ExpressionPrinter<'a> {
         result.push(')');
 
         // Add options (if any)
-        for option in &func.options {
-            result.push('#');
-            result.push_str(&option.name);
-            for pref in &option.preference {
-                result.push(';');
-                result.push_str(pref);
-            }
-        }
+        result.push_str(&Self::print_function_options(&func.options));
 
         // Add return type annotation
         if let Some(output_type) = &func.output_type {
@@ -663,9 +855,47 @@ impl<'a> ExpressionPrinter<'a> {
                 result.push_str(&format!("<{},{}>", dec_type.precision, dec_type.scale));
                 return Ok(result);
             }
-            Some(Kind::Struct(_)) => return Ok("STRUCT_TYPE_NOT_YET_IMPLEMENTED".to_string()),
-            Some(Kind::List(_)) => return Ok("LIST_TYPE_NOT_YET_IMPLEMENTED".to_string()),
-            Some(Kind::Map(_)) => return Ok("MAP_TYPE_NOT_YET_IMPLEMENTED".to_string()),
+            Some(Kind::Struct(struct_type)) => {
+                let fields = struct_type
+                    .types
+                    .iter()
+                    .map(|field_type| self.print_type(field_type))
+                    .collect::<Result<Vec<_>, _>>()?;
+                result.push_str("struct");
+                if type_utils::is_nullable(type_val) {
+                    result.push('?');
+                }
+                result.push_str(&format!("<{}>", fields.join(",")));
+                return Ok(result);
+            }
+            Some(Kind::List(list_type)) => {
+                let element = match &list_type.r#type {
+                    Some(element_type) => self.print_type(element_type)?,
+                    None => "MISSING_LIST_ELEMENT_TYPE".to_string(),
+                };
+                result.push_str("list");
+                if type_utils::is_nullable(type_val) {
+                    result.push('?');
+                }
+                result.push_str(&format!("<{}>", element));
+                return Ok(result);
+            }
+            Some(Kind::Map(map_type)) => {
+                let key = match &map_type.key {
+                    Some(key_type) => self.print_type(key_type)?,
+                    None => "MISSING_MAP_KEY_TYPE".to_string(),
+                };
+                let value = match &map_type.value {
+                    Some(value_type) => self.print_type(value_type)?,
+                    None => "MISSING_MAP_VALUE_TYPE".to_string(),
+                };
+                result.push_str("map");
+                if type_utils::is_nullable(type_val) {
+                    result.push('?');
+                }
+                result.push_str(&format!("<{},{}>", key, value));
+                return Ok(result);
+            }
             Some(Kind::UserDefined(_)) => {
                 return Ok("USER_DEFINED_TYPE_NOT_YET_IMPLEMENTED".to_string())
             }
@@ -761,6 +991,13 @@ impl<'a> ExpressionPrinter<'a> {
             result.push_str("MISSING_CAST_TYPE");
         }
 
+        use ::substrait::proto::expression::cast::FailureBehavior;
+        match FailureBehavior::try_from(cast.failure_behavior) {
+            Ok(FailureBehavior::ReturnNull) => result.push_str(" ON_FAILURE RETURN_NULL"),
+            Ok(FailureBehavior::ThrowException) => result.push_str(" ON_FAILURE THROW_EXCEPTION"),
+            _ => {}
+        }
+
         Ok(result)
     }
 
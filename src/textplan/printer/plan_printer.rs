@@ -6,6 +6,7 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::textplan::common::error::TextPlanError;
+use crate::textplan::common::projection_mask;
 use crate::textplan::common::structured_symbol_data::RelationData;
 use crate::textplan::printer::expression_printer::ExpressionPrinter;
 use crate::textplan::symbol_table::{
@@ -109,9 +110,83 @@ impl PlanPrinter {
         // Process extension space
         self.process_extension_space(symbol_table, &mut result)?;
 
+        // Process the plan level advanced extension
+        for symbol in symbol_table.symbols() {
+            if symbol.symbol_type() == SymbolType::AdvancedExtension {
+                if let Some(extension) = symbol
+                    .with_blob::<::substrait::proto::extensions::AdvancedExtension, _, _>(
+                        |extension| extension.clone(),
+                    )
+                {
+                    result.push_str(&Self::format_advanced_extension(&extension, ""));
+                }
+            }
+        }
+
+        // Process the dynamic parameter bindings
+        self.process_parameters(symbol_table, &mut result)?;
+
         Ok(result)
     }
 
+    /// Processes the dynamic parameters in the symbol table, printing a parameters block.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol_table` - The symbol table to process
+    /// * `result` - The string to append the result to
+    ///
+    /// # Returns
+    ///
+    /// Result indicating success or an error
+    fn process_parameters(
+        &self,
+        symbol_table: &SymbolTable,
+        result: &mut String,
+    ) -> Result<(), TextPlanError> {
+        use crate::textplan::common::structured_symbol_data::ParameterData;
+
+        let mut parameters: Vec<(String, ParameterData)> = symbol_table
+            .symbols()
+            .iter()
+            .filter(|symbol| symbol.symbol_type() == SymbolType::Parameter)
+            .filter_map(|symbol| {
+                symbol
+                    .with_blob::<ParameterData, _, _>(|data| data.clone())
+                    .map(|data| (symbol.name().to_string(), data))
+            })
+            .collect();
+        if parameters.is_empty() {
+            return Ok(());
+        }
+        parameters.sort_by_key(|(_, data)| data.anchor);
+
+        let indent = " ".repeat(self.indent_size);
+        let mut expr_printer = ExpressionPrinter::new(symbol_table, None);
+        result.push_str("parameters {\n");
+        for (name, data) in &parameters {
+            result.push_str(&indent);
+            result.push_str(name);
+            if let Some(parameter_type) = &data.parameter_type {
+                result.push_str(": ");
+                result.push_str(&expr_printer.print_type(parameter_type)?);
+            }
+            if let Some(value) = &data.value {
+                result.push_str(" = ");
+                result.push_str(&expr_printer.print_expression(
+                    &::substrait::proto::Expression {
+                        rex_type: Some(::substrait::proto::expression::RexType::Literal(
+                            value.clone(),
+                        )),
+                    },
+                )?);
+            }
+            result.push_str(";\n");
+        }
+        result.push_str("}\n");
+        Ok(())
+    }
+
     /// Processes ROOT relations in the symbol table.
     ///
     /// # Arguments
@@ -308,7 +383,7 @@ impl PlanPrinter {
         };
 
         // Convert the relation type to a string
-        let rel_type_str = Self::rel_type_to_string(rel_type);
+        let rel_type_str = rel_type.as_str();
 
         // Start the relation definition
         result.push_str(&format!(
@@ -348,6 +423,11 @@ impl PlanPrinter {
             RelationType::Join => {
                 self.add_join_relation_properties(relation, symbol_table, &indent, &mut result)?;
             }
+            RelationType::ExtensionLeaf
+            | RelationType::ExtensionSingle
+            | RelationType::ExtensionMulti => {
+                self.add_extension_relation_properties(relation, &indent, &mut result);
+            }
             // Add cases for other relation types as needed
             _ => {
                 // Default case: add a comment for unimplemented relation types
@@ -360,12 +440,169 @@ impl PlanPrinter {
             }
         }
 
+        // Hints in RelCommon apply to every relation type
+        self.add_common_properties(relation, &indent, &mut result);
+
+        // Advanced extension payloads attached to the relation
+        let advanced_extension = relation
+            .with_blob::<RelationData, _, _>(|relation_data| {
+                crate::textplan::common::advanced_extension::rel_advanced_extension(
+                    &relation_data.relation,
+                )
+                .cloned()
+            })
+            .flatten();
+        if let Some(extension) = advanced_extension {
+            result.push_str(&Self::format_advanced_extension(&extension, &indent));
+        }
+
         // End the relation definition
         result.push_str("}\n");
 
         Ok(result)
     }
 
+    /// Formats an advanced extension block with one line per optimization or enhancement.
+    fn format_advanced_extension(
+        extension: &::substrait::proto::extensions::AdvancedExtension,
+        indent: &str,
+    ) -> String {
+        use crate::textplan::common::advanced_extension::format_payload;
+        use crate::textplan::common::string_utils::escape_string;
+
+        let mut result = format!("{}advanced_extension {{\n", indent);
+        for optimization in &extension.optimization {
+            result.push_str(&format!(
+                "{}  optimization {} {};\n",
+                indent,
+                escape_string(&optimization.type_url),
+                format_payload(&optimization.type_url, &optimization.value)
+            ));
+        }
+        if let Some(enhancement) = &extension.enhancement {
+            result.push_str(&format!(
+                "{}  enhancement {} {};\n",
+                indent,
+                escape_string(&enhancement.type_url),
+                format_payload(&enhancement.type_url, &enhancement.value)
+            ));
+        }
+        result.push_str(&format!("{}}}\n", indent));
+        result
+    }
+
+    /// Adds properties for an extension relation (its detail payload).
+    ///
+    /// # Arguments
+    ///
+    /// * `relation` - The relation symbol
+    /// * `indent` - The indentation string
+    /// * `result` - The string to append the result to
+    fn add_extension_relation_properties(
+        &self,
+        relation: &Arc<SymbolInfo>,
+        indent: &str,
+        result: &mut String,
+    ) {
+        use crate::textplan::common::advanced_extension::format_payload;
+        use crate::textplan::common::string_utils::escape_string;
+        use ::substrait::proto::rel::RelType;
+
+        let detail_text = relation
+            .with_blob::<RelationData, _, _>(|relation_data| {
+                let detail = match &relation_data.relation.rel_type {
+                    Some(RelType::ExtensionLeaf(ext_rel)) => ext_rel.detail.as_ref(),
+                    Some(RelType::ExtensionSingle(ext_rel)) => ext_rel.detail.as_ref(),
+                    Some(RelType::ExtensionMulti(ext_rel)) => ext_rel.detail.as_ref(),
+                    _ => None,
+                };
+                detail.map(|any| {
+                    format!(
+                        "{} {}",
+                        escape_string(&any.type_url),
+                        format_payload(&any.type_url, &any.value)
+                    )
+                })
+            })
+            .flatten();
+
+        if let Some(detail_text) = detail_text {
+            result.push_str(&format!("{}detail {};\n", indent, detail_text));
+        }
+    }
+
+    /// Adds the RelCommon hints (stats, constraint, alias, output names and computations).
+    ///
+    /// # Arguments
+    ///
+    /// * `relation` - The relation symbol
+    /// * `indent` - The indentation string
+    /// * `result` - The string to append the result to
+    fn add_common_properties(&self, relation: &Arc<SymbolInfo>, indent: &str, result: &mut String) {
+        use crate::textplan::common::rel_common::{computation_type_to_string, rel_common};
+        use crate::textplan::common::string_utils::escape_string;
+
+        let hint = relation
+            .with_blob::<RelationData, _, _>(|relation_data| {
+                rel_common(&relation_data.relation).and_then(|common| common.hint.clone())
+            })
+            .flatten();
+        let hint = match hint {
+            Some(hint) if hint != ::substrait::proto::rel_common::Hint::default() => hint,
+            _ => return,
+        };
+
+        let hint_indent = format!("{}  ", indent);
+        result.push_str(&format!("{}common {{\n", indent));
+        if !hint.alias.is_empty() {
+            result.push_str(&format!(
+                "{}alias {};\n",
+                hint_indent,
+                escape_string(&hint.alias)
+            ));
+        }
+        if let Some(stats) = &hint.stats {
+            // An empty stats message is still written so that it stays present
+            if stats.row_count != 0.0 || stats.record_size == 0.0 {
+                result.push_str(&format!("{}row_count {};\n", hint_indent, stats.row_count));
+            }
+            if stats.record_size != 0.0 {
+                result.push_str(&format!(
+                    "{}record_size {};\n",
+                    hint_indent, stats.record_size
+                ));
+            }
+        }
+        if hint.constraint.is_some() {
+            result.push_str(&format!("{}constraint;\n", hint_indent));
+        }
+        if !hint.output_names.is_empty() {
+            let names: Vec<String> = hint.output_names.iter().map(|n| escape_string(n)).collect();
+            result.push_str(&format!(
+                "{}output_names {};\n",
+                hint_indent,
+                names.join(", ")
+            ));
+        }
+        for saved in &hint.saved_computations {
+            result.push_str(&format!(
+                "{}saved_computation {} {};\n",
+                hint_indent,
+                saved.computation_id,
+                computation_type_to_string(saved.r#type)
+            ));
+        }
+        for loaded in &hint.loaded_computations {
+            result.push_str(&format!(
+                "{}loaded_computation {} {};\n",
+                hint_indent,
+                loaded.computation_id_reference,
+                computation_type_to_string(loaded.r#type)
+            ));
+        }
+        result.push_str(&format!("{}}}\n", indent));
+    }
+
     /// Adds properties for a read relation.
     ///
     /// # Arguments
@@ -388,29 +625,37 @@ impl PlanPrinter {
         use ::substrait::proto::rel::RelType;
 
         // Extract the data we need (clone to avoid holding the lock)
-        let (source_name, schema_name, filter_expr) = if let Some(blob_lock) = &relation.blob {
-            if let Ok(blob_data) = blob_lock.lock() {
-                if let Some(relation_data) = blob_data.downcast_ref::<RelationData>() {
-                    // Extract the ReadRel from the protobuf
-                    if let Some(RelType::Read(read_rel)) = &relation_data.relation.rel_type {
-                        let source_name =
-                            relation_data.source.as_ref().map(|s| s.name().to_string());
-                        let schema_name =
-                            relation_data.schema.as_ref().map(|s| s.name().to_string());
-                        let filter_expr = read_rel.filter.clone();
-                        (source_name, schema_name, filter_expr)
+        let (source_name, schema_name, filter_expr, best_effort_filter_expr, projection_names) =
+            if let Some(blob_lock) = &relation.blob {
+                if let Ok(blob_data) = blob_lock.lock() {
+                    if let Some(relation_data) = blob_data.downcast_ref::<RelationData>() {
+                        // Extract the ReadRel from the protobuf
+                        if let Some(RelType::Read(read_rel)) = &relation_data.relation.rel_type {
+                            let source_name =
+                                relation_data.source.as_ref().map(|s| s.name().to_string());
+                            let schema_name =
+                                relation_data.schema.as_ref().map(|s| s.name().to_string());
+                            let projection_names =
+                                Self::read_projection_names(read_rel, relation_data);
+                            (
+                                source_name,
+                                schema_name,
+                                read_rel.filter.clone(),
+                                read_rel.best_effort_filter.clone(),
+                                projection_names,
+                            )
+                        } else {
+                            (None, None, None, None, Vec::new())
+                        }
                     } else {
-                        (None, None, None)
+                        (None, None, None, None, Vec::new())
                     }
                 } else {
-                    (None, None, None)
+                    (None, None, None, None, Vec::new())
                 }
             } else {
-                (None, None, None)
-            }
-        } else {
-            (None, None, None)
-        };
+                (None, None, None, None, Vec::new())
+            };
 
         // Now print the properties (lock is released)
         if let Some(source) = source_name {
@@ -421,15 +666,70 @@ impl PlanPrinter {
             result.push_str(&format!("{}base_schema {};\n", indent, schema));
         }
 
+        if !projection_names.is_empty() {
+            result.push_str(&format!(
+                "{}projection {};\n",
+                indent,
+                projection_names.join(", ")
+            ));
+        }
+
         if let Some(filter) = filter_expr {
             let mut expr_printer = ExpressionPrinter::new(symbol_table, Some(relation));
             let filter_text = expr_printer.print_expression(&filter)?;
             result.push_str(&format!("{}filter {};\n", indent, filter_text));
         }
 
+        if let Some(filter) = best_effort_filter_expr {
+            let mut expr_printer = ExpressionPrinter::new(symbol_table, Some(relation));
+            let filter_text = expr_printer.print_expression(&filter)?;
+            result.push_str(&format!("{}best_effort filter {};\n", indent, filter_text));
+        }
+
         Ok(())
     }
 
+    /// Returns the schema columns selected by a read relation's projection mask, each
+    /// followed by the selection of its nested value (if any).
+    ///
+    /// Uses the names from the base schema when present and falls back to the
+    /// relation's field references otherwise.
+    fn read_projection_names(
+        read_rel: &::substrait::proto::ReadRel,
+        relation_data: &RelationData,
+    ) -> Vec<String> {
+        let struct_items = match read_rel
+            .projection
+            .as_ref()
+            .and_then(|mask| mask.select.as_ref())
+        {
+            Some(select) => &select.struct_items,
+            None => return Vec::new(),
+        };
+
+        struct_items
+            .iter()
+            .map(|item| {
+                let field_idx = item.field as usize;
+                let name = read_rel
+                    .base_schema
+                    .as_ref()
+                    .and_then(|schema| schema.names.get(field_idx).cloned())
+                    .or_else(|| {
+                        relation_data
+                            .field_references
+                            .get(field_idx)
+                            .map(|field| field.name().to_string())
+                    })
+                    .unwrap_or_else(|| format!("field#{}", field_idx));
+                match &item.child {
+                    Some(child) => format!("{}{}", name, projection_mask::format_select(child)),
+                    None => name,
+                }
+            })
+            .collect()
+    }
+
     /// Adds properties for a filter relation.
     ///
     /// # Arguments
@@ -685,7 +985,13 @@ impl PlanPrinter {
             let mut expr_printer = ExpressionPrinter::new(symbol_table, Some(relation));
             for sort_field in &sorts {
                 // Print the expression
-                let expr_text = expr_printer.print_expression(sort_field.expr.as_ref().unwrap())?;
+                let expr = sort_field.expr.as_ref().ok_or_else(|| {
+                    TextPlanError::InvalidExpression(format!(
+                        "A sort field of relation '{}' has no expression",
+                        relation.name()
+                    ))
+                })?;
+                let expr_text = expr_printer.print_expression(expr)?;
 
                 // Print the direction (following C++ format: "by DIRECTION_NAME")
                 use ::substrait::proto::sort_field::SortKind;
@@ -935,39 +1241,6 @@ impl PlanPrinter {
         }
     }
 
-    /// Converts a RelationType enum to a lowercase string.
-    ///
-    /// # Arguments
-    ///
-    /// * `rel_type` - The relation type
-    ///
-    /// # Returns
-    ///
-    /// The lowercase string representation
-    fn rel_type_to_string(rel_type: RelationType) -> &'static str {
-        match rel_type {
-            RelationType::Unknown => "unknown",
-            RelationType::Read => "read",
-            RelationType::Project => "project",
-            RelationType::Join => "join",
-            RelationType::Cross => "cross",
-            RelationType::Fetch => "fetch",
-            RelationType::Aggregate => "aggregate",
-            RelationType::Sort => "sort",
-            RelationType::Filter => "filter",
-            RelationType::Set => "set",
-            RelationType::Root => "root",
-            RelationType::HashJoin => "hash_join",
-            RelationType::MergeJoin => "merge_join",
-            RelationType::Exchange => "exchange",
-            RelationType::Ddl => "ddl",
-            RelationType::Write => "write",
-            RelationType::ExtensionLeaf => "extension_leaf",
-            RelationType::ExtensionSingle => "extension_single",
-            RelationType::ExtensionMulti => "extension_multi",
-        }
-    }
-
     /// Outputs the pipelines section of the textplan.
     ///
     /// # Arguments
@@ -1228,6 +1501,11 @@ impl PlanPrinter {
                                 }
 
                                 result.push_str("  ]\n");
+                                if let Some(extension) = &local_files.advanced_extension {
+                                    result.push_str(&Self::format_advanced_extension(
+                                        extension, "  ",
+                                    ));
+                                }
                                 result.push_str("}\n\n");
                             }
                         }
@@ -1249,6 +1527,11 @@ impl PlanPrinter {
                                     result.push_str(&format!("\"{}\"", name));
                                 }
                                 result.push_str("]\n");
+                                if let Some(extension) = &named_table.advanced_extension {
+                                    result.push_str(&Self::format_advanced_extension(
+                                        extension, "  ",
+                                    ));
+                                }
                                 result.push_str("}\n\n");
                             }
                         }
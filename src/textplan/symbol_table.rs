@@ -8,6 +8,9 @@ use std::fmt;
 use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::{Arc, Mutex};
 
+use crate::textplan::common::error::TextPlanError;
+use crate::textplan::common::relation_schema::{self, RelationSchema};
+use crate::textplan::common::string_utils;
 use crate::textplan::common::Location;
 use crate::textplan::common::UnknownLocation;
 use crate::textplan::TextLocation;
@@ -39,6 +42,10 @@ pub enum SymbolType {
     Table,
     /// A measure.
     Measure,
+    /// The advanced extension attached to the plan itself.
+    AdvancedExtension,
+    /// A dynamic parameter, such as "$1".
+    Parameter,
     /// An unknown symbol type.
     Unknown,
 }
@@ -58,6 +65,8 @@ impl fmt::Display for SymbolType {
             SymbolType::Root => write!(f, "Root"),
             SymbolType::Table => write!(f, "Table"),
             SymbolType::Measure => write!(f, "Measure"),
+            SymbolType::AdvancedExtension => write!(f, "AdvancedExtension"),
+            SymbolType::Parameter => write!(f, "Parameter"),
             SymbolType::Unknown => write!(f, "Unknown"),
         }
     }
@@ -94,6 +103,33 @@ pub enum RelationType {
     ExtensionMulti,
 }
 
+impl RelationType {
+    /// Returns the lowercase name the relation type has in a textplan (e.g. `hash_join`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationType::Unknown => "unknown",
+            RelationType::Read => "read",
+            RelationType::Project => "project",
+            RelationType::Join => "join",
+            RelationType::Cross => "cross",
+            RelationType::Fetch => "fetch",
+            RelationType::Aggregate => "aggregate",
+            RelationType::Sort => "sort",
+            RelationType::Filter => "filter",
+            RelationType::Set => "set",
+            RelationType::Root => "root",
+            RelationType::HashJoin => "hash_join",
+            RelationType::MergeJoin => "merge_join",
+            RelationType::Exchange => "exchange",
+            RelationType::Ddl => "ddl",
+            RelationType::Write => "write",
+            RelationType::ExtensionLeaf => "extension_leaf",
+            RelationType::ExtensionSingle => "extension_single",
+            RelationType::ExtensionMulti => "extension_multi",
+        }
+    }
+}
+
 /// Types of relations in a plan.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SourceType {
@@ -549,20 +585,13 @@ impl SymbolTable {
             .cloned()
     }
 
-    /// Returns the nth symbol of the given type.
-    ///
-    /// Panics if n is out of bounds.
-    pub fn nth_symbol_by_type(&self, n: usize, symbol_type: SymbolType) -> Arc<SymbolInfo> {
-        let mut count = 0;
-        for symbol in &self.symbols {
-            if symbol.symbol_type() == symbol_type {
-                if count == n {
-                    return symbol.clone();
-                }
-                count += 1;
-            }
-        }
-        panic!("No symbol of type {} at index {}", symbol_type, n);
+    /// Returns the nth symbol of the given type, if there are more than n of them.
+    pub fn nth_symbol_by_type(&self, n: usize, symbol_type: SymbolType) -> Option<Arc<SymbolInfo>> {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.symbol_type() == symbol_type)
+            .nth(n)
+            .cloned()
     }
 
     /// Returns all symbols.
@@ -579,6 +608,28 @@ impl SymbolTable {
     pub fn is_empty(&self) -> bool {
         self.symbols.is_empty()
     }
+
+    /// Computes the output schema (names, types and nullability) of a relation symbol.
+    pub fn relation_output_schema(
+        &self,
+        symbol: &SymbolInfo,
+    ) -> Result<RelationSchema, TextPlanError> {
+        relation_schema::relation_output_schema(self, symbol)
+    }
+
+    /// Suggests the defined symbol of the given type a misspelled name was likely meant
+    /// to refer to.
+    ///
+    /// Relations that were only mentioned in pipelines without being defined are not
+    /// suggested.
+    pub fn suggest_symbol_name(&self, name: &str, symbol_type: SymbolType) -> Option<String> {
+        let candidates = self.symbols.iter().filter(|symbol| {
+            symbol.symbol_type() == symbol_type
+                && symbol.subtype::<RelationType>() != Some(RelationType::Unknown)
+        });
+        string_utils::closest_match(name, candidates.map(|symbol| symbol.name()))
+            .map(str::to_string)
+    }
 }
 
 impl fmt::Display for SymbolTable {
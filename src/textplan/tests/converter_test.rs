@@ -168,4 +168,72 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_validate_plan() {
+        use crate::textplan::converter::load_json::load_plan_from_json_str;
+        use crate::textplan::converter::validate_plan;
+
+        let filename = "src/textplan/tests/data/converter/q6_first_stage.json";
+        let plan = load_json::load_from_json_file(filename).expect("Failed to load test file");
+        let diagnostics = validate_plan(&plan);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let json = r#"{
+            "extensions": [
+                {"extension_function": {"function_anchor": 1, "name": "gt:i64_i64"}}
+            ],
+            "relations": [
+                {"root": {"input": {"filter": {
+                    "input": {"read": {
+                        "base_schema": {"names": ["a"], "struct": {"types": [{"i64": {}}]}},
+                        "named_table": {"names": ["t"]}
+                    }},
+                    "condition": {"scalar_function": {
+                        "function_reference": 2,
+                        "arguments": [{"value": {"selection": {
+                            "direct_reference": {"struct_field": {"field": 3}},
+                            "root_reference": {}
+                        }}}]
+                    }}
+                }}, "names": ["a"]}},
+                {"rel": {"project": {}}}
+            ]
+        }"#;
+        let plan = load_plan_from_json_str(json).expect("Failed to load plan");
+        let diagnostics: Vec<String> = validate_plan(&plan)
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "error[E0104] at relations[0].root.input.filter.condition.scalar_function.function_reference: \
+                 Function anchor 2 is not declared by any extension",
+                "error[E0103] at relations[0].root.input.filter.condition.scalar_function.arguments[0].value.selection.direct_reference.struct_field.field: \
+                 Field 3 is out of range as the input has 1 fields",
+                "error[E0106] at relations[1].rel.project.input: The project relation has no input",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_relations_fail_conversion() {
+        use crate::textplan::common::error::TextPlanError;
+        use crate::textplan::converter::load_json::load_plan_from_json_str;
+
+        let json = r#"{
+            "relations": [
+                {"root": {"input": {"reference": {"subtree_ordinal": 0}}, "names": ["a"]}}
+            ]
+        }"#;
+        let plan = load_plan_from_json_str(json).expect("Failed to load plan");
+        match process_plan_with_visitor(&plan) {
+            Err(TextPlanError::BinaryConversionError(message)) => assert_eq!(
+                message,
+                "relations[0].root.input: Reference relations are not supported in textplans"
+            ),
+            other => panic!("Expected a conversion error, got {:?}", other),
+        }
+    }
 }
@@ -35,8 +35,8 @@ mod tests {
                 input: r"pipelines {
                     root -> project -> read;
                 }",
-                expected_symbols: vec!["read", "project", "root"],
-                should_succeed: true,
+                expected_symbols: vec![],
+                should_succeed: false,
             },
             TestCase {
                 name: "test3-schema",
@@ -122,6 +122,440 @@ mod tests {
                 expected_symbols: vec!["schema", "r_regionkey"],
                 should_succeed: true,
             },
+            TestCase {
+                name: "test-read-pushdown",
+                input: r##"
+                extension_space blah.yaml {
+                    function equal:any_any as equal;
+                }
+
+                schema schema {
+                    r_regionkey i32;
+                    r_name string?;
+                }
+
+                source named_table named {
+                    names = [
+                        "#2",
+                    ]
+                }
+
+                read relation myread {
+                    base_schema schema;
+                    source named;
+                    projection r_name;
+                    filter equal(r_regionkey, 1_i32)->bool;
+                    best_effort filter equal(r_name, "x")->bool;
+                }
+                "##,
+                expected_symbols: vec!["equal", "schema", "r_regionkey", "r_name", "myread"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-read-projection-unknown-column",
+                input: r##"
+                schema schema {
+                    r_regionkey i32;
+                }
+
+                read relation myread {
+                    base_schema schema;
+                    projection r_missing;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-read-projection-without-schema",
+                input: r##"
+                schema schema {
+                    r_regionkey i32;
+                }
+
+                read relation myread {
+                    projection r_regionkey;
+                    base_schema schema;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-common-hints",
+                input: r##"
+                read relation myread {
+                    base_schema schemaone;
+                    source mynamedtable;
+                    common {
+                        alias "scan_orders";
+                        row_count 1000;
+                        record_size 24.5;
+                        constraint;
+                        output_names "a", "b";
+                        saved_computation 1 hash_table;
+                        loaded_computation 2 bloom_filter;
+                    }
+                }
+                "##,
+                expected_symbols: vec!["myread"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-common-unknown-hint",
+                input: r"read relation myread {
+                    common {
+                        cardinality 10;
+                    }
+                }",
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-advanced-extensions",
+                input: r##"
+                source named_table named {
+                    names = [
+                        "#2",
+                    ]
+                    advanced_extension {
+                        optimization "type.googleapis.com/acme.TableStats" "\x08\x01";
+                    }
+                }
+
+                source local_files myfiles {
+                    advanced_extension {
+                        enhancement "type.googleapis.com/acme.FileStats" "C:\x5c";
+                    }
+                }
+
+                read relation myread {
+                    source named;
+                    advanced_extension {
+                        optimization "type.googleapis.com/acme.ScanHint" "";
+                        enhancement "type.googleapis.com/acme.Sampling" "\x10\x64";
+                    }
+                }
+
+                advanced_extension {
+                    optimization "type.googleapis.com/acme.PlanHint" "fast";
+                    optimization "type.googleapis.com/google.protobuf.Struct" `{"rows": 10}`;
+                }
+                "##,
+                expected_symbols: vec!["named", "myfiles", "myread", "advanced_extension"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-advanced-extension-bad-escape",
+                input: r##"
+                advanced_extension {
+                    optimization "type.googleapis.com/acme.PlanHint" "\q";
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-advanced-extension-json-for-unknown-type",
+                input: r##"
+                advanced_extension {
+                    optimization "type.googleapis.com/acme.PlanHint" `{"rows": 10}`;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-extension-relations",
+                input: r##"
+                pipelines {
+                    left -> dedup;
+                    middle -> dedup;
+                    right -> dedup;
+                    dedup -> root;
+                }
+
+                extension_leaf relation left {
+                    detail "type.googleapis.com/acme.Source" "\x0a\x01a";
+                }
+
+                extension_leaf relation middle {
+                    detail "type.googleapis.com/acme.Source" "\x0a\x01b";
+                }
+
+                extension_leaf relation right {
+                    detail "type.googleapis.com/acme.Source" "\x0a\x01c";
+                }
+
+                extension_multi relation dedup {
+                    detail "type.googleapis.com/acme.StreamingDedup" "";
+                }
+                "##,
+                expected_symbols: vec!["left", "middle", "right", "dedup"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-detail-on-non-extension-relation",
+                input: r##"
+                read relation myread {
+                    detail "type.googleapis.com/acme.Source" "";
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-function-options-and-cast-failure",
+                input: r##"
+                extension_space blah.yaml {
+                    function add:i64_i64 as add;
+                    function extract:req_ts as extract;
+                }
+
+                pipelines {
+                    myread -> myproject -> root;
+                }
+
+                schema schema {
+                    a i64;
+                    b i64;
+                    c string;
+                    ts timestamp;
+                }
+
+                source named_table named {
+                    names = [
+                        "#2",
+                    ]
+                }
+
+                read relation myread {
+                    base_schema schema;
+                    source named;
+                }
+
+                project relation myproject {
+                    expression add(a, b)[overflow: SILENT, overflow: SATURATE]->i64;
+                    expression extract(YEAR_enum, ts)->i64;
+                    expression c AS i64 ON_FAILURE RETURN_NULL;
+                }
+                "##,
+                expected_symbols: vec![
+                    "add",
+                    "extract",
+                    "a",
+                    "b",
+                    "c",
+                    "ts",
+                    "myread",
+                    "myproject",
+                ],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-temporal-literals",
+                input: r##"
+                project relation myproject {
+                    expression "2024-01-31"_date;
+                    expression "12:30:00.123"_time;
+                    expression "2024-01-31T12:30:00"_timestamp;
+                    expression "2024-01-31T12:30:00Z"_timestamp_tz;
+                    expression 19753_date;
+                    expression -800000_date;
+                    expression 86400000000_time;
+                    expression 1706704200000000_timestamp;
+                    expression 1706704200000000_timestamp_tz;
+                }
+                "##,
+                expected_symbols: vec!["myproject"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-invalid-date-literal",
+                input: r##"
+                project relation myproject {
+                    expression "2023-02-29"_date;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-invalid-timestamp-literal",
+                input: r##"
+                project relation myproject {
+                    expression 1.5_timestamp_tz;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-binary-and-uuid-literals",
+                input: r##"
+                project relation myproject {
+                    expression x"deadbeef"_binary;
+                    expression x"deadbeef"_fixedbinary<4>;
+                    expression "123e4567-e89b-12d3-a456-426614174000"_uuid;
+                }
+                "##,
+                expected_symbols: vec!["myproject"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-fixedbinary-length-mismatch",
+                input: r##"
+                project relation myproject {
+                    expression x"dead"_fixedbinary<4>;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-typed-struct-literals",
+                input: r##"
+                project relation myproject {
+                    expression {1, "a", null}_struct<i64,string,fp64?>;
+                    expression {{1_i32, 2_i32}, {"k": 2}}_struct<list<i32>,map<string,i64>>;
+                    expression {}_list<i32>;
+                }
+                "##,
+                expected_symbols: vec!["myproject"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-struct-literal-field-mismatch",
+                input: r##"
+                project relation myproject {
+                    expression {"a", 1}_struct<i64,string>;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-dynamic-parameters",
+                input: r##"
+                project relation myproject {
+                    expression $1;
+                    expression $region:string;
+                    expression $limit;
+                }
+
+                parameters {
+                    $1 = 42_i64;
+                    $region = "EUROPE";
+                    $limit: i32;
+                }
+                "##,
+                expected_symbols: vec!["$1", "$region", "$limit", "myproject"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-untyped-dynamic-parameter",
+                input: r##"
+                project relation myproject {
+                    expression $missing;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-nested-field-references",
+                input: r##"
+                pipelines {
+                    myread -> myproject -> root;
+                }
+
+                schema schema {
+                    addr struct<string, string>;
+                    tags list<string>;
+                    attrs map<string, i32>;
+                }
+
+                source named_table named {
+                    names = [
+                        "#2",
+                    ]
+                }
+
+                read relation myread {
+                    base_schema schema;
+                    source named;
+                }
+
+                project relation myproject {
+                    expression addr.1;
+                    expression tags[0];
+                    expression attrs["k"];
+                }
+                "##,
+                expected_symbols: vec!["addr", "tags", "attrs", "myread", "myproject"],
+                should_succeed: true,
+            },
+            TestCase {
+                name: "test-nested-field-out-of-range",
+                input: r##"
+                pipelines {
+                    myread -> myproject -> root;
+                }
+
+                schema schema {
+                    addr struct<string, string>;
+                }
+
+                source named_table named {
+                    names = [
+                        "#2",
+                    ]
+                }
+
+                read relation myread {
+                    base_schema schema;
+                    source named;
+                }
+
+                project relation myproject {
+                    expression addr.2;
+                }
+                "##,
+                expected_symbols: vec![],
+                should_succeed: false,
+            },
+            TestCase {
+                name: "test-projection-masks",
+                input: r##"
+                pipelines {
+                    myread -> myproject -> root;
+                }
+
+                schema schema {
+                    addr struct<string, string, i32>;
+                    tags list<string>;
+                    attrs map<string, i32>;
+                }
+
+                source named_table named {
+                    names = [
+                        "#2",
+                    ]
+                }
+
+                read relation myread {
+                    base_schema schema;
+                    source named;
+                    projection addr{0, 2}, tags[0, 2:5], attrs["k"];
+                }
+
+                project relation myproject {
+                    expression mask{addr{1}, attrs[expression "k.*"]};
+                }
+                "##,
+                expected_symbols: vec!["addr", "tags", "attrs", "myread", "myproject"],
+                should_succeed: true,
+            },
         ]
     }
 
@@ -169,6 +603,868 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_numbered_parameters_keep_their_anchors() {
+        use crate::textplan::common::structured_symbol_data::ParameterData;
+
+        let text = r##"
+            parameters {
+                $region = "EUROPE";
+            }
+
+            project relation myproject {
+                expression $region;
+                expression $1:i64;
+            }
+            "##;
+        let result = parse_stream(text);
+        assert!(result.successful(), "{:?}", result.all_errors());
+        let anchor = |name: &str| {
+            result
+                .symbol_table()
+                .lookup_symbol_by_name(name)
+                .and_then(|symbol| symbol.with_blob::<ParameterData, _, _>(|data| data.anchor))
+                .unwrap()
+        };
+        // The named parameter comes first but does not take the anchor of $1
+        assert_eq!(anchor("$1"), 1);
+        assert_eq!(anchor("$region"), 2);
+    }
+
+    #[test]
+    fn test_function_declarations_checked_against_extensions() {
+        use crate::textplan::common::extension_registry::ExtensionRegistry;
+        use crate::textplan::parser::parse_stream_with_registry;
+        use std::sync::Arc;
+
+        let mut registry = ExtensionRegistry::new();
+        registry
+            .add_yaml(
+                "functions_arithmetic.yaml",
+                r#"
+scalar_functions:
+  - name: "add"
+    impls:
+      - args:
+          - value: i64
+          - value: i64
+        return: i64
+"#,
+            )
+            .expect("Failed to load the extension");
+        let registry = Arc::new(registry);
+
+        let valid = "extension_space functions_arithmetic.yaml { function add:i64_i64 as add; }";
+        let result = parse_stream_with_registry(valid, registry.clone());
+        assert!(result.successful(), "{:?}", result.all_errors());
+
+        let wrong_signature =
+            "extension_space functions_arithmetic.yaml { function add:str_str as add; }";
+        let result = parse_stream_with_registry(wrong_signature, registry.clone());
+        assert!(!result.successful());
+
+        let unknown_function =
+            "extension_space functions_arithmetic.yaml { function modulus:i64_i64 as modulus; }";
+        let result = parse_stream_with_registry(unknown_function, registry);
+        assert!(!result.successful());
+    }
+
+    #[test]
+    fn test_undeclared_functions_declared_from_extensions() {
+        use crate::textplan::common::extension_registry::ExtensionRegistry;
+        use crate::textplan::common::structured_symbol_data::FunctionData;
+        use crate::textplan::converter::save_binary::create_plan_from_symbol_table;
+        use crate::textplan::parser::parse_stream_with_registry;
+        use crate::textplan::symbol_table::SymbolType;
+        use ::substrait::proto::extensions::simple_extension_declaration::MappingType;
+        use std::sync::Arc;
+
+        let mut registry = ExtensionRegistry::new();
+        registry
+            .add_yaml(
+                "functions_arithmetic.yaml",
+                r#"
+scalar_functions:
+  - name: "add"
+    impls:
+      - args:
+          - value: i32
+          - value: i32
+        return: i32
+      - args:
+          - value: i64
+          - value: i64
+        return: i64
+"#,
+            )
+            .expect("Failed to load the extension");
+        let registry = Arc::new(registry);
+
+        let plan = |expression: &str| {
+            format!(
+                r##"
+                pipelines {{
+                    myread -> myproject -> root;
+                }}
+
+                schema schema {{
+                    a i64;
+                }}
+
+                source named_table named {{
+                    names = [
+                        "#2",
+                    ]
+                }}
+
+                read relation myread {{
+                    base_schema schema;
+                    source named;
+                }}
+
+                project relation myproject {{
+                    expression {};
+                    expression {};
+                }}
+                "##,
+                expression, expression
+            )
+        };
+
+        let result = parse_stream_with_registry(&plan("add(a, 1_i64)->i64"), registry.clone());
+        assert!(result.successful(), "{:?}", result.all_errors());
+        let symbol_table = result.symbol_table();
+        let declarations: Vec<String> = symbol_table
+            .symbols()
+            .iter()
+            .filter(|symbol| symbol.symbol_type() == SymbolType::Function)
+            .filter_map(|symbol| symbol.with_blob::<FunctionData, _, _>(|data| data.name.clone()))
+            .collect();
+        // Both calls share the one declaration
+        assert_eq!(declarations, vec!["add:i64_i64".to_string()]);
+        assert!(symbol_table
+            .symbols()
+            .iter()
+            .any(|symbol| symbol.symbol_type() == SymbolType::ExtensionSpace
+                && symbol.name().ends_with("/functions_arithmetic.yaml")));
+
+        // The synthesized declarations make it into the binary plan
+        let binary_plan = create_plan_from_symbol_table(symbol_table).unwrap();
+        assert_eq!(binary_plan.extension_uris.len(), 1);
+        let uri = &binary_plan.extension_uris[0];
+        assert!(uri.uri.ends_with("/functions_arithmetic.yaml"));
+        assert_eq!(binary_plan.extensions.len(), 1);
+        match &binary_plan.extensions[0].mapping_type {
+            Some(MappingType::ExtensionFunction(function)) => {
+                assert_eq!(function.name, "add:i64_i64");
+                assert_eq!(function.extension_uri_reference, uri.extension_uri_anchor);
+            }
+            other => panic!("Expected a function declaration, found {:?}", other),
+        }
+
+        let result = parse_stream_with_registry(&plan("add(a, \"one\")->i64"), registry);
+        assert!(!result.successful());
+    }
+
+    #[test]
+    fn test_function_return_types_derived_from_extensions() {
+        use crate::textplan::common::extension_registry::ExtensionRegistry;
+        use crate::textplan::common::structured_symbol_data::RelationData;
+        use crate::textplan::parser::parse_stream_with_registry;
+        use ::substrait::proto::expression::RexType;
+        use ::substrait::proto::r#type::Kind;
+        use ::substrait::proto::rel::RelType;
+        use std::sync::Arc;
+
+        let mut registry = ExtensionRegistry::new();
+        registry
+            .add_yaml(
+                "functions_arithmetic_decimal.yaml",
+                r#"
+scalar_functions:
+  - name: "add"
+    impls:
+      - args:
+          - value: decimal<P1,S1>
+          - value: decimal<P2,S2>
+        return: |-
+          init_scale = max(S1,S2)
+          init_prec = init_scale + max(P1 - S1, P2 - S2) + 1
+          min_scale = min(init_scale, 6)
+          delta = init_prec - 38
+          prec = min(init_prec, 38)
+          scale_after_borrow = max(init_scale - delta, min_scale)
+          scale = init_prec > 38 ? scale_after_borrow : init_scale
+          DECIMAL<prec, scale>
+"#,
+            )
+            .expect("Failed to load the extension");
+        let registry = Arc::new(registry);
+
+        let plan = |expression: &str| {
+            format!(
+                r##"
+                pipelines {{
+                    myread -> myproject -> root;
+                }}
+
+                schema schema {{
+                    price decimal<10,2>;
+                    tax decimal?<12,4>;
+                }}
+
+                source named_table named {{
+                    names = [
+                        "#2",
+                    ]
+                }}
+
+                read relation myread {{
+                    base_schema schema;
+                    source named;
+                }}
+
+                project relation myproject {{
+                    expression {};
+                }}
+                "##,
+                expression
+            )
+        };
+
+        let result = parse_stream_with_registry(&plan("add(price, tax)"), registry.clone());
+        assert!(result.successful(), "{:?}", result.all_errors());
+        let project = result
+            .symbol_table()
+            .lookup_symbol_by_name("myproject")
+            .expect("Missing project relation");
+        let output_type = project
+            .with_blob::<RelationData, _, _>(|data| match &data.relation.rel_type {
+                Some(RelType::Project(project)) => match &project.expressions[0].rex_type {
+                    Some(RexType::ScalarFunction(function)) => function.output_type.clone(),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .flatten()
+            .expect("The output type should have been derived");
+        match output_type.kind {
+            Some(Kind::Decimal(decimal)) => {
+                assert_eq!((decimal.precision, decimal.scale), (13, 4));
+            }
+            other => panic!("Expected a decimal, found {:?}", other),
+        }
+
+        // Annotations that agree with the definition are accepted
+        let result =
+            parse_stream_with_registry(&plan("add(price, tax)->decimal?<13,4>"), registry.clone());
+        assert!(result.successful(), "{:?}", result.all_errors());
+
+        let result = parse_stream_with_registry(&plan("add(price, tax)->decimal<10,2>"), registry);
+        assert!(!result.successful());
+    }
+
+    #[test]
+    fn test_diagnostics_locate_errors() {
+        use crate::textplan::common::diagnostic::DiagnosticCode;
+
+        let text = "schema s {\n  a i64;\n  b i64 i64;\n}\n";
+        let result = parse_stream(text);
+        assert!(!result.successful());
+        let diagnostic = result
+            .diagnostics()
+            .iter()
+            .find(|diagnostic| diagnostic.is_error())
+            .expect("Expected a diagnostic");
+        assert_eq!(diagnostic.code(), DiagnosticCode::SyntaxError);
+        let position = diagnostic.location().position() as usize;
+        assert_eq!(result.source_map().line_column(position).line, 3);
+        assert_eq!(result.syntax_errors().len(), result.all_errors().len());
+        assert!(result.all_errors()[0].starts_with("error[E0001] 3:"));
+        assert!(result.render_errors().contains("3 |   b i64 i64;\n"));
+    }
+
+    #[test]
+    fn test_syntax_errors_in_separate_blocks() {
+        use crate::textplan::common::structured_symbol_data::RelationData;
+        use crate::textplan::symbol_table::SymbolType;
+
+        let text = "schema broken {\n  a i64\n}\n\
+                    source named_table named {\n  names = [\n    \"#2\",\n  ]\n}\n\
+                    read relation myread {\n  source named\n}\n\
+                    schema schema {\n  b i64;\n}\n\
+                    pipelines {\n  myread -> myproject -> root;\n}\n\
+                    project relation myproject {\n  expression a;\n}\n";
+        let result = parse_stream(text);
+        assert!(!result.successful());
+
+        // Each broken block reports its own error without affecting the others
+        let syntax_errors = result.syntax_errors();
+        assert_eq!(syntax_errors.len(), 2, "{:?}", syntax_errors);
+        assert!(syntax_errors[0].starts_with("error[E0001] 3:"));
+        assert!(syntax_errors[1].starts_with("error[E0001] 11:"));
+
+        // The blocks without errors still make up a symbol table
+        let symbol_table = result.symbol_table();
+        assert!(symbol_table.lookup_symbol_by_name("named").is_some());
+        let schema = symbol_table.lookup_symbol_by_name("schema").unwrap();
+        assert_eq!(schema.symbol_type(), SymbolType::Schema);
+        assert!(symbol_table.lookup_symbol_by_name("b").is_some());
+        assert!(symbol_table.lookup_symbol_by_name("broken").is_none());
+        let myread = symbol_table.lookup_symbol_by_name("myread").unwrap();
+        let myread_source = myread.with_blob::<RelationData, _, _>(|data| data.source.clone());
+        assert!(myread_source.unwrap().is_none());
+
+        // Relations using a broken block do not report errors of their own
+        assert_eq!(result.diagnostics().len(), 2, "{:?}", result.all_errors());
+    }
+
+    #[test]
+    fn test_pipeline_graph_validation() {
+        use crate::textplan::common::diagnostic::DiagnosticCode;
+
+        let text = r##"
+            pipelines {
+                first_read -> second_read -> root;
+                left_read -> myjoin -> root;
+                a_read -> myfilter -> myproject;
+                b_read -> myfilter;
+                loop_a -> loop_b -> loop_a;
+            }
+
+            read relation first_read {}
+            read relation second_read {}
+            read relation left_read {}
+            read relation a_read {}
+            read relation b_read {}
+            join relation myjoin {}
+            filter relation myfilter {}
+            project relation myproject {}
+            project relation loop_a {}
+            project relation loop_b {}
+            project relation loop_b {}
+            read relation orphan_read {}
+            "##;
+        let result = parse_stream(text);
+        assert!(!result.successful());
+
+        let messages: Vec<&str> = result
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message())
+            .collect();
+        for expected in [
+            "The read relation 'second_read' takes no inputs but has 1",
+            "The join relation 'myjoin' takes 2 inputs but has 1",
+            "The filter relation 'myfilter' takes one input but has 2",
+            "Pipeline ends at 'myproject' instead of at a root",
+            "Pipelines form a cycle: loop_a -> loop_b -> loop_a",
+            "Relation 'loop_b' is defined more than once",
+            "Relation 'orphan_read' is not part of any pipeline",
+        ] {
+            assert!(
+                messages.contains(&expected),
+                "Missing '{}' in {:?}",
+                expected,
+                messages
+            );
+        }
+
+        for (message, code) in [
+            ("The join relation 'myjoin'", DiagnosticCode::ArityMismatch),
+            ("Pipeline ends at", DiagnosticCode::InvalidPlan),
+            ("Pipelines form a cycle", DiagnosticCode::Cycle),
+            (
+                "Relation 'loop_b' is defined",
+                DiagnosticCode::DuplicateDefinition,
+            ),
+        ] {
+            let diagnostic = result
+                .diagnostics()
+                .iter()
+                .find(|diagnostic| diagnostic.message().starts_with(message))
+                .unwrap();
+            assert_eq!(diagnostic.code(), code, "{}", message);
+        }
+
+        // Relations outside of the pipelines are only warned about where they are defined
+        let orphan = result
+            .diagnostics()
+            .iter()
+            .find(|diagnostic| diagnostic.message().contains("orphan_read"))
+            .unwrap();
+        assert!(!orphan.is_error());
+        let start = orphan.location().position() as usize;
+        assert!(text[start..].starts_with("read relation orphan_read"));
+
+        // Apart from duplicate definitions the problems are reported at the arrows
+        for diagnostic in result.diagnostics() {
+            if diagnostic.message().contains("defined more than once") {
+                continue;
+            }
+            if diagnostic.message().starts_with("The ")
+                || diagnostic.message().starts_with("Pipeline")
+            {
+                let location = diagnostic.location();
+                let start = location.position() as usize;
+                let end = start + location.length() as usize;
+                assert_eq!(&text[start..end], "->", "{}", diagnostic.message());
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_on_unsupported_relation() {
+        let text = r##"
+            pipelines {
+                myread -> myproject -> root;
+            }
+
+            schema schema {
+                r_regionkey i32;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+
+            project relation myproject {
+                expression r_regionkey;
+                filter r_regionkey;
+            }
+            "##;
+        let result = parse_stream(text);
+        assert!(result.syntax_errors().is_empty());
+        assert_eq!(
+            result.semantic_errors().len(),
+            1,
+            "{:?}",
+            result.all_errors()
+        );
+        assert!(result.semantic_errors()[0]
+            .contains("Filters are only supported on filter and read relations"));
+    }
+
+    #[test]
+    fn test_semantic_errors_fail_loading() {
+        use crate::textplan::common::error::TextPlanError;
+        use crate::textplan::parser::load_from_text;
+
+        let text = r##"
+            pipelines {
+                myread -> myproject -> root;
+            }
+
+            schema schema {
+                a i64;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+
+            project relation myproject {
+                expression missing;
+            }
+            "##;
+        let result = parse_stream(text);
+        assert!(result.syntax_errors().is_empty());
+        assert_eq!(
+            result.semantic_errors().len(),
+            1,
+            "{:?}",
+            result.all_errors()
+        );
+        assert!(result.semantic_errors()[0].contains("'missing'"));
+
+        match load_from_text(text) {
+            Err(TextPlanError::SemanticError(message)) => assert!(message.contains("'missing'")),
+            other => panic!("Expected a semantic error, got {:?}", other),
+        }
+
+        // The C API hands the reason back to the caller instead of printing it
+        let c_text = std::ffi::CString::new(text).unwrap();
+        let mut error = std::ptr::null_mut();
+        let plan = crate::load_from_text(c_text.as_ptr(), &mut error);
+        assert!(plan.is_null());
+        assert!(!error.is_null());
+        let message = unsafe { std::ffi::CStr::from_ptr(error) }
+            .to_string_lossy()
+            .to_string();
+        crate::free_text_plan(error);
+        assert!(message.contains("'missing'"), "{}", message);
+    }
+
+    #[test]
+    fn test_relation_output_schema() {
+        use crate::textplan::common::type_utils;
+        use crate::textplan::converter::save_binary::create_plan_from_symbol_table;
+        use crate::textplan::symbol_table::SymbolTable;
+        use ::substrait::proto::plan_rel::RelType;
+
+        let text = r##"
+            pipelines {
+                orders_read -> myjoin -> myagg -> root;
+                customers_read -> myjoin;
+            }
+
+            extension_space blah.yaml {
+                function equal:any_any as equal;
+                function sum:fp64 as sum;
+            }
+
+            schema orders_schema {
+                o_custkey i64;
+                o_total fp64;
+            }
+
+            schema customers_schema {
+                c_custkey i64;
+                c_name string;
+            }
+
+            source named_table orders {
+                names = [
+                    "orders",
+                ]
+            }
+
+            source named_table customers {
+                names = [
+                    "customers",
+                ]
+            }
+
+            read relation orders_read {
+                base_schema orders_schema;
+                source orders;
+            }
+
+            read relation customers_read {
+                base_schema customers_schema;
+                source customers;
+            }
+
+            join relation myjoin {
+                type LEFT;
+                expression equal(o_custkey, c_custkey)->bool;
+            }
+
+            aggregate relation myagg {
+                grouping c_name;
+                measure {
+                    measure sum(o_total)->fp64 NAMED total;
+                }
+            }
+            "##;
+        let result = parse_stream(text);
+        assert!(result.successful(), "{:?}", result.all_errors());
+        let symbol_table = result.symbol_table();
+
+        let join = symbol_table.lookup_symbol_by_name("myjoin").unwrap();
+        let schema = symbol_table.relation_output_schema(&join).unwrap();
+        assert_eq!(
+            schema.names(),
+            vec!["o_custkey", "o_total", "c_custkey", "c_name"]
+        );
+        // The right side of a left join is nullable
+        let nullable: Vec<bool> = schema.fields.iter().map(|f| f.is_nullable()).collect();
+        assert_eq!(nullable, vec![false, false, true, true]);
+
+        let aggregate = symbol_table.lookup_symbol_by_name("myagg").unwrap();
+        let schema = symbol_table.relation_output_schema(&aggregate).unwrap();
+        let kinds: Vec<&str> = schema
+            .fields
+            .iter()
+            .map(|f| type_utils::type_kind_name(f.r#type.as_ref().unwrap()))
+            .collect();
+        assert_eq!(kinds, vec!["string", "fp64"]);
+        assert!(schema.fields[0].is_nullable());
+
+        // A root without names takes them from its input
+        let root_names = |symbol_table: &SymbolTable| {
+            let plan = create_plan_from_symbol_table(symbol_table).unwrap();
+            plan.relations
+                .iter()
+                .find_map(|relation| match &relation.rel_type {
+                    Some(RelType::Root(root)) if root.input.is_some() => Some(root.names.clone()),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(root_names(symbol_table), schema.names());
+
+        // The names of nested struct fields follow their field, depth first
+        let text = r##"
+            pipelines {
+                myread -> root;
+            }
+
+            schema schema {
+                id i64;
+                addr struct<string, struct<i32>>;
+                total fp64;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+            "##;
+        let result = parse_stream(text);
+        assert!(result.successful(), "{:?}", result.all_errors());
+        assert_eq!(
+            root_names(result.symbol_table()),
+            vec!["id", "addr", "field0", "field1", "field0", "total"]
+        );
+    }
+
+    #[test]
+    fn test_struct_fields_by_name() {
+        use crate::textplan::converter::save_binary::create_plan_from_symbol_table;
+        use ::substrait::proto::expression::field_reference::ReferenceType;
+        use ::substrait::proto::expression::reference_segment;
+        use ::substrait::proto::expression::RexType;
+        use ::substrait::proto::plan_rel::RelType;
+        use ::substrait::proto::rel::RelType as RelKind;
+
+        let text = r##"
+            pipelines {
+                myread -> myproject -> root;
+            }
+
+            schema schema {
+                id i64;
+                addr struct<city: string, geo: struct<lat: fp64, lon: fp64>>;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+
+            project relation myproject {
+                expression addr.city;
+                expression addr.geo.lon;
+                expression addr.1.lat;
+            }
+            "##;
+        let result = parse_stream(text);
+        assert!(result.successful(), "{:?}", result.all_errors());
+
+        let plan = create_plan_from_symbol_table(result.symbol_table()).unwrap();
+        let root = plan
+            .relations
+            .iter()
+            .find_map(|relation| match &relation.rel_type {
+                Some(RelType::Root(root)) if root.input.is_some() => Some(root.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(root.names, vec!["id", "addr", "city", "geo", "lat", "lon"]);
+        let Some(RelKind::Project(project)) = root.input.unwrap().rel_type else {
+            panic!("expected a project relation");
+        };
+
+        // Each expression selects field 1 (addr) and then the given path of struct fields
+        let field_path = |expression: &::substrait::proto::Expression| {
+            let Some(RexType::Selection(selection)) = &expression.rex_type else {
+                panic!("expected a field reference");
+            };
+            let Some(ReferenceType::DirectReference(segment)) = &selection.reference_type else {
+                panic!("expected a direct reference");
+            };
+            let mut path = Vec::new();
+            let mut next = Some(segment);
+            while let Some(segment) = next {
+                let Some(reference_segment::ReferenceType::StructField(field)) =
+                    &segment.reference_type
+                else {
+                    panic!("expected a struct field");
+                };
+                path.push(field.field);
+                next = field.child.as_deref();
+            }
+            path
+        };
+        let paths: Vec<Vec<i32>> = project.expressions.iter().map(field_path).collect();
+        assert_eq!(paths, vec![vec![1, 0], vec![1, 1, 1], vec![1, 1, 0]]);
+
+        // Unknown field names are reported
+        let result = parse_stream(&text.replace("addr.city", "addr.town"));
+        assert!(!result.successful());
+        assert!(result
+            .all_errors()
+            .iter()
+            .any(|error| error.contains("no field named 'town'")));
+    }
+
+    #[test]
+    fn test_type_check_expressions() {
+        use crate::textplan::common::diagnostic::DiagnosticCode;
+
+        let text = r##"
+            pipelines {
+                myread -> myfilter -> myproject -> myaggregate -> root;
+            }
+
+            extension_space blah.yaml {
+                function add:i64_i64 as add;
+                function sum:i64 as sum;
+            }
+
+            schema schema {
+                a i64;
+                s string;
+                d date;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+
+            filter relation myfilter {
+                filter a;
+            }
+
+            project relation myproject {
+                expression add(a, s)->i64;
+                expression d AS i64;
+                expression s AS i64;
+                expression IFTHEN(s, a, d AS i64);
+            }
+
+            aggregate relation myaggregate {
+                measure {
+                    measure sum(s)->i64 NAMED total;
+                }
+            }
+            "##;
+        let result = parse_stream(text);
+        assert!(result.syntax_errors().is_empty());
+        let mismatches: Vec<_> = result
+            .diagnostics()
+            .into_iter()
+            .filter(|diagnostic| diagnostic.code() == DiagnosticCode::TypeMismatch)
+            .collect();
+        assert_eq!(mismatches.len(), 6, "{:?}", result.all_errors());
+
+        // Each mismatch points at the offending expression, including those nested in
+        // if-then expressions and aggregate measures
+        let located: Vec<&str> = mismatches
+            .iter()
+            .map(|diagnostic| {
+                let start = diagnostic.location().position() as usize;
+                &text[start..start + diagnostic.location().length() as usize]
+            })
+            .collect();
+        assert_eq!(located, vec!["a", "s", "d AS i64", "s", "d AS i64", "s"]);
+    }
+
+    #[test]
+    fn test_unresolved_names_suggest_alternatives() {
+        use crate::textplan::common::diagnostic::DiagnosticCode;
+
+        let text = r##"
+            pipelines {
+                myread -> myproject -> root;
+            }
+
+            extension_space blah.yaml {
+                function multiply:fp64_fp64 as multiply;
+            }
+
+            schema lineitem {
+                l_extendedprice fp64;
+                l_discount fp64;
+            }
+
+            source named_table named {
+                names = [
+                    "lineitem",
+                ]
+            }
+
+            read relation myread {
+                base_schema lineitem;
+                source nmaed;
+            }
+
+            project relation myproject {
+                expression multply(l_extendedprice, l_discount)->fp64;
+                expression lineitem.l_extendedprce;
+            }
+            "##;
+        let result = parse_stream(text);
+        let messages: Vec<&str> = result
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message())
+            .collect();
+        for expected in [
+            "Source 'nmaed' is not defined; did you mean 'named'?",
+            "Function 'multply' is not declared and is not defined by any known extension; \
+             did you mean 'multiply'?",
+            "Column 'lineitem.l_extendedprce' is not in the input of relation 'myproject'; \
+             did you mean 'lineitem.l_extendedprice'?",
+        ] {
+            assert!(messages.contains(&expected), "{:?}", messages);
+        }
+        let column = result
+            .diagnostics()
+            .iter()
+            .find(|diagnostic| diagnostic.message().starts_with("Column"))
+            .unwrap();
+        assert_eq!(column.fixes()[0].replacement, "lineitem.l_extendedprice");
+        assert_eq!(column.code(), DiagnosticCode::UnknownColumn);
+        let function = result
+            .diagnostics()
+            .iter()
+            .find(|diagnostic| diagnostic.message().starts_with("Function"))
+            .unwrap();
+        assert_eq!(function.code(), DiagnosticCode::UnknownFunction);
+
+        let result = parse_stream(&text.replace("-> myproject ->", "-> myProjct ->"));
+        assert!(result
+            .all_errors()
+            .iter()
+            .any(|error| error
+                .contains("Relation 'myProjct' is not defined; did you mean 'myproject'?")));
+    }
+
     #[test]
     #[ignore] // TODO: antlr4rust has a bug with lexer modes - URI tokens in EXTENSIONS mode don't work
               // See: https://github.com/rrevenantt/antlr4rust - lexer mode transitions not properly handled
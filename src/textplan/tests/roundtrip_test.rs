@@ -477,6 +477,399 @@ mod tests {
         println!("✓ Roundtrip successful: Plans match for {}", file_path);
     }
 
+    /// Parses a textplan and checks that printing its binary plan and parsing the printed
+    /// textplan again gives back the same binary plan.
+    fn run_text_roundtrip_test(text: &str) {
+        let parse_result = parse_stream(text);
+        assert!(
+            parse_result.successful(),
+            "Failed to parse textplan: {:?}",
+            parse_result.all_errors()
+        );
+        let binary = save_to_binary(parse_result.symbol_table())
+            .expect("Failed to convert symbol table to binary");
+        let plan = crate::proto::load_plan_from_binary(&binary).expect("Failed to load binary");
+
+        let text_plan = process_plan_with_visitor(&plan).expect("Failed to print plan");
+        let reparsed = parse_stream(&text_plan);
+        assert!(
+            reparsed.successful(),
+            "Failed to parse printed textplan:\n{}\n{:?}",
+            add_line_numbers(&text_plan),
+            reparsed.all_errors()
+        );
+        let roundtrip_binary = save_to_binary(reparsed.symbol_table())
+            .expect("Failed to convert reparsed symbol table to binary");
+        let roundtrip_plan = crate::proto::load_plan_from_binary(&roundtrip_binary)
+            .expect("Failed to load roundtrip binary");
+
+        let differences = compare_plans(
+            &normalize_plan(plan),
+            &normalize_plan(roundtrip_plan),
+            &ProtoMatcherConfig::ignoring_version(),
+        );
+        assert!(
+            differences.is_empty(),
+            "{}\nPrinted textplan:\n{}",
+            format_differences(&differences, 10),
+            add_line_numbers(&text_plan)
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_projection_masks() {
+        // A function named mask is still called like any other function
+        run_text_roundtrip_test(
+            r##"
+            extension_space blah.yaml {
+                function mask:any as mask;
+            }
+
+            pipelines {
+                myread -> myproject -> root;
+            }
+
+            schema schema {
+                addr struct<string, string, i32>;
+                tags list<string>;
+                attrs map<string, i32>;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+
+            project relation myproject {
+                expression mask{addr{1}, attrs[expression "k.*"]};
+                expression mask{addr, tags};
+                expression mask(tags)->list<string>;
+            }
+            "##,
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_extension_relations() {
+        run_text_roundtrip_test(
+            r##"
+            pipelines {
+                left -> dedup;
+                middle -> dedup;
+                right -> dedup;
+                dedup -> root;
+            }
+
+            extension_leaf relation left {
+                detail "type.googleapis.com/acme.Source" "\x0a\x01a";
+            }
+
+            extension_leaf relation middle {
+                detail "type.googleapis.com/acme.Source" "\x0a\x01b";
+            }
+
+            extension_leaf relation right {
+                detail "type.googleapis.com/acme.Source" "\x0a\x01c";
+            }
+
+            extension_multi relation dedup {
+                detail "type.googleapis.com/acme.StreamingDedup" "";
+            }
+            "##,
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_binary_and_uuid_literals() {
+        run_text_roundtrip_test(
+            r##"
+            extension_space blah.yaml {
+                function equal:any_any as equal;
+            }
+
+            pipelines {
+                myread -> myfilter -> myproject -> root;
+            }
+
+            schema schema {
+                hash fixedbinary<4>;
+                id uuid;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+
+            filter relation myfilter {
+                filter equal(hash, x"deadbeef"_fixedbinary<4>)->bool;
+            }
+
+            project relation myproject {
+                expression equal(id, "123e4567-e89b-12d3-a456-426614174000"_uuid)->bool;
+                expression x"00ff"_binary;
+                expression x""_binary;
+            }
+            "##,
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_struct_literals() {
+        run_text_roundtrip_test(
+            r##"
+            pipelines {
+                myread -> myproject -> root;
+            }
+
+            schema schema {
+                id i64;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+
+            project relation myproject {
+                expression {1, "a", null}_struct<i64,string,fp64?>;
+                expression {{1_i32, 2_i32}, {"k": 2}}_struct<list<i32>,map<string,i64>>;
+                expression {{1, {}}, "b"}_struct<struct<i32,list<string>>,string?>;
+                expression {}_list<i32>;
+                expression {}_map<string,i32?>;
+            }
+            "##,
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_function_options_and_enum_arguments() {
+        run_text_roundtrip_test(
+            r##"
+            extension_space blah.yaml {
+                function add:i64_i64 as add;
+                function extract:req_ts as extract;
+            }
+
+            pipelines {
+                myread -> myproject -> root;
+            }
+
+            schema schema {
+                a i64;
+                b i64;
+                c string;
+                ts timestamp;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+            }
+
+            project relation myproject {
+                expression add(a, b)[overflow: SILENT, overflow: SATURATE]->i64;
+                expression extract(YEAR_enum, ts)->i64;
+                expression c AS i64 ON_FAILURE RETURN_NULL;
+            }
+            "##,
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_common_hints() {
+        use crate::textplan::common::rel_common::rel_common;
+        use ::substrait::proto::plan_rel::RelType as PlanRelType;
+        use ::substrait::proto::rel::RelType;
+
+        let text = r##"
+            pipelines {
+                myread -> myproject -> root;
+            }
+
+            schema schema {
+                id i64;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+                common {
+                    alias "scan_orders";
+                    row_count 1000;
+                    record_size 24.5;
+                    constraint;
+                    output_names "a";
+                    saved_computation 1 hash_table;
+                    loaded_computation 2 bloom_filter;
+                }
+            }
+
+            project relation myproject {
+                expression id;
+                common {
+                    row_count 5;
+                }
+            }
+            "##;
+        run_text_roundtrip_test(text);
+
+        let parse_result = parse_stream(text);
+        let binary = save_to_binary(parse_result.symbol_table())
+            .expect("Failed to convert symbol table to binary");
+        let plan = crate::proto::load_plan_from_binary(&binary).expect("Failed to load binary");
+
+        let Some(PlanRelType::Root(root)) = &plan.relations[0].rel_type else {
+            panic!("Expected a root relation");
+        };
+        let project = root.input.as_ref().unwrap();
+        let Some(RelType::Project(project_rel)) = &project.rel_type else {
+            panic!("Expected a project relation");
+        };
+        let read = project_rel.input.as_ref().unwrap();
+
+        let hint = rel_common(read)
+            .and_then(|common| common.hint.as_ref())
+            .unwrap();
+        assert_eq!(hint.alias, "scan_orders");
+        let stats = hint.stats.as_ref().unwrap();
+        assert_eq!((stats.row_count, stats.record_size), (1000.0, 24.5));
+        assert!(hint.constraint.is_some());
+        assert_eq!(hint.output_names, vec!["a".to_string()]);
+        assert_eq!(hint.saved_computations[0].computation_id, 1);
+        assert_eq!(hint.loaded_computations[0].computation_id_reference, 2);
+
+        let hint = rel_common(project)
+            .and_then(|common| common.hint.as_ref())
+            .unwrap();
+        assert_eq!(hint.stats.as_ref().unwrap().row_count, 5.0);
+
+        // Only the stats that were set are printed
+        let printed = process_plan_with_visitor(&plan).expect("Failed to print plan");
+        assert!(printed.contains("record_size 24.5;"), "{}", printed);
+        assert!(printed.contains("row_count 5;"), "{}", printed);
+        assert_eq!(printed.matches("record_size").count(), 1, "{}", printed);
+    }
+
+    #[test]
+    fn test_roundtrip_advanced_extension_payloads() {
+        use ::prost::Message;
+        use ::substrait::proto::plan_rel::RelType as PlanRelType;
+        use ::substrait::proto::read_rel::ReadType;
+        use ::substrait::proto::rel::RelType;
+
+        let text = r##"
+            pipelines {
+                myread -> root;
+            }
+
+            schema schema {
+                id i64;
+            }
+
+            source named_table named {
+                names = [
+                    "#2",
+                ]
+                advanced_extension {
+                    optimization "type.googleapis.com/acme.TableStats" "\x08\x01";
+                }
+            }
+
+            read relation myread {
+                base_schema schema;
+                source named;
+                advanced_extension {
+                    optimization "type.googleapis.com/google.protobuf.Struct" `{"rows":10.0}`;
+                    enhancement "type.googleapis.com/acme.Sampling" "C:\x5c";
+                }
+            }
+
+            advanced_extension {
+                optimization "type.googleapis.com/google.protobuf.StringValue" `"fast"`;
+            }
+            "##;
+        run_text_roundtrip_test(text);
+
+        let parse_result = parse_stream(text);
+        let binary = save_to_binary(parse_result.symbol_table())
+            .expect("Failed to convert symbol table to binary");
+        let plan = crate::proto::load_plan_from_binary(&binary).expect("Failed to load binary");
+
+        let plan_extension = plan.advanced_extensions.as_ref().unwrap();
+        assert_eq!(
+            plan_extension.optimization[0].value.to_vec(),
+            "fast".to_string().encode_to_vec()
+        );
+        let Some(PlanRelType::Root(root)) = &plan.relations[0].rel_type else {
+            panic!("Expected a root relation");
+        };
+        let Some(RelType::Read(read)) = root.input.as_ref().and_then(|rel| rel.rel_type.as_ref())
+        else {
+            panic!("Expected a read relation");
+        };
+        let read_extension = read.advanced_extension.as_ref().unwrap();
+        let rows = ::prost_types::Struct::decode(&read_extension.optimization[0].value[..])
+            .expect("Failed to decode the struct payload");
+        assert_eq!(
+            rows.fields["rows"].kind,
+            Some(::prost_types::value::Kind::NumberValue(10.0))
+        );
+        assert_eq!(
+            read_extension.enhancement.as_ref().unwrap().value.to_vec(),
+            b"C:\\".to_vec()
+        );
+        let Some(ReadType::NamedTable(named_table)) = &read.read_type else {
+            panic!("Expected a named table");
+        };
+        let named_extension = named_table.advanced_extension.as_ref().unwrap();
+        assert_eq!(
+            named_extension.optimization[0].value.to_vec(),
+            vec![0x08, 0x01]
+        );
+
+        // The payloads print back in the form they were written in
+        let printed = process_plan_with_visitor(&plan).expect("Failed to print plan");
+        for payload in [
+            r#""\x08\x01""#,
+            r#"`{"rows":10.0}`"#,
+            r#""C:\x5c""#,
+            r#"`"fast"`"#,
+        ] {
+            assert!(printed.contains(payload), "{} not in\n{}", payload, printed);
+        }
+    }
+
     // Macro to generate individual test functions for each data file
     macro_rules! roundtrip_tests {
         ($($name:ident: $file:expr,)*) => {